@@ -0,0 +1,78 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Generates the lists of embedded SQL statements, so all of them can be checked
+//! on startup, and of the schema migrations.
+
+use std::{env, fs, io, path::Path};
+
+const SQL_DIR: &str = "src/sql";
+/// Files are named as `<version>_<name>.sql`, e.g. `0002_add_tips.sql`.
+const MIGRATIONS_DIR: &str = "db/migrations";
+
+fn main() -> io::Result<()> {
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR isn't set by Cargo");
+    fs::write(
+        Path::new(&out_dir).join("statements.rs"),
+        format!("&[\n{}\n]", statements()?.join("\n")),
+    )?;
+    fs::write(
+        Path::new(&out_dir).join("migrations.rs"),
+        format!("&[\n{}\n]", migrations()?.join("\n")),
+    )
+}
+
+fn statements() -> io::Result<Vec<String>> {
+    println!("cargo:rerun-if-changed={SQL_DIR}");
+    let mut statements = Vec::new();
+    for kind in fs::read_dir(SQL_DIR)? {
+        let kind = kind?.path();
+        println!("cargo:rerun-if-changed={}", kind.display());
+        for file in fs::read_dir(&kind)? {
+            let path = file?.path().canonicalize()?;
+            let name = path
+                .strip_prefix(Path::new(SQL_DIR).canonicalize()?)
+                .unwrap();
+            statements.push(format!(
+                "({:?}, include_str!({:?})),",
+                name.display().to_string(),
+                path.display().to_string()
+            ));
+        }
+    }
+    statements.sort();
+    Ok(statements)
+}
+
+fn migrations() -> io::Result<Vec<String>> {
+    println!("cargo:rerun-if-changed={MIGRATIONS_DIR}");
+    let mut migrations = Vec::new();
+    for file in fs::read_dir(MIGRATIONS_DIR)? {
+        let path = file?.path().canonicalize()?;
+        let stem = path.file_stem().unwrap().to_string_lossy().to_string();
+        let (version, name) = stem
+            .split_once('_')
+            .and_then(|(version, name)| Some((version.parse::<i32>().ok()?, name)))
+            .unwrap_or_else(|| panic!("migration {stem} isn't named as <version>_<name>.sql"));
+        migrations.push((
+            version,
+            format!(
+                "({version}, {name:?}, include_str!({:?})),",
+                path.display().to_string()
+            ),
+        ));
+    }
+    migrations.sort();
+    for pair in migrations.windows(2) {
+        assert!(
+            pair[0].0 != pair[1].0,
+            "there are several migrations with version {}",
+            pair[0].0
+        );
+    }
+    Ok(migrations
+        .into_iter()
+        .map(|(_, migration)| migration)
+        .collect())
+}
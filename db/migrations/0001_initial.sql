@@ -0,0 +1,217 @@
+-- Schema before migrations were introduced.
+
+CREATE TYPE "UserRole" AS ENUM
+(
+    'Customer',
+    'Rider',
+    'Manager'
+);
+
+CREATE TABLE public.users
+(
+    id serial NOT NULL,
+    username character varying(64) NOT NULL,
+    password character(64) NOT NULL,
+    first_name character varying(128),
+    last_name character varying(128),
+    birth_date date NOT NULL,
+    role "UserRole" NOT NULL DEFAULT 'Customer',
+    PRIMARY KEY (id),
+    CONSTRAINT username UNIQUE (username)
+);
+
+ALTER TABLE IF EXISTS public.users
+    OWNER to gogo;
+
+CREATE TABLE public.categories
+(
+    id serial NOT NULL,
+    title character varying(128) NOT NULL,
+    description text,
+    -- Image in JPEG format.
+    preview bytea,
+    PRIMARY KEY (id)
+);
+
+ALTER TABLE IF EXISTS public.categories
+    OWNER to gogo;
+
+CREATE TABLE public.food
+(
+    id serial NOT NULL,
+    title character varying(128) NOT NULL,
+    description text,
+    -- Image in JPEG format.
+    preview bytea,
+    category_id serial NOT NULL,
+    count integer NOT NULL DEFAULT 0,
+    is_alcohol boolean NOT NULL,
+    price numeric(7, 2) NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT category_id FOREIGN KEY (category_id)
+        REFERENCES public.categories (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE RESTRICT
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.food
+    OWNER to gogo;
+
+CREATE TABLE public.addresses
+(
+    id serial NOT NULL,
+    customer_id serial NOT NULL,
+    locality character varying(128) NOT NULL,
+    street character varying(128) NOT NULL,
+    house integer NOT NULL,
+    corps character varying(16),
+    apartment character varying(16),
+    PRIMARY KEY (id),
+    CONSTRAINT customer_id FOREIGN KEY (customer_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.addresses
+    OWNER to gogo;
+
+CREATE TABLE public.orders
+(
+    id serial NOT NULL,
+    customer_id serial NOT NULL,
+    address_id serial NOT NULL,
+    create_time timestamp without time zone NOT NULL,
+    rider_id integer,
+    completed_time timestamp without time zone,
+    PRIMARY KEY (id),
+    CONSTRAINT customer_id FOREIGN KEY (customer_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT address_id FOREIGN KEY (address_id)
+        REFERENCES public.addresses (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE RESTRICT
+        NOT VALID,
+    CONSTRAINT rider_id FOREIGN KEY (rider_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.orders
+    OWNER to gogo;
+
+CREATE TABLE public.orders_food
+(
+    id serial NOT NULL,
+    order_id serial NOT NULL,
+    food_id serial NOT NULL,
+    count integer NOT NULL DEFAULT 1,
+    PRIMARY KEY (id),
+    CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CHECK (count > 0) NOT VALID,
+    CONSTRAINT food_per_order UNIQUE (order_id, food_id)
+);
+
+ALTER TABLE IF EXISTS public.orders_food
+    OWNER to gogo;
+
+CREATE TABLE public.cart
+(
+    id serial NOT NULL,
+    customer_id serial NOT NULL,
+    food_id serial NOT NULL,
+    count integer NOT NULL DEFAULT 1,
+    add_time timestamp without time zone NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT customer_id FOREIGN KEY (customer_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT count CHECK (count > 0) NOT VALID,
+    CONSTRAINT food_per_customer UNIQUE (customer_id, food_id)
+);
+
+ALTER TABLE IF EXISTS public.cart
+    OWNER to gogo;
+
+CREATE TABLE public.favorites
+(
+    id serial NOT NULL,
+    user_id serial NOT NULL,
+    food_id serial NOT NULL,
+    add_time timestamp without time zone NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT food_per_user UNIQUE (user_id, food_id)
+);
+
+ALTER TABLE IF EXISTS public.favorites
+    OWNER to gogo;
+
+CREATE TABLE public.feedbacks
+(
+    id serial NOT NULL,
+    order_id serial NOT NULL,
+    rating smallint,
+    comment text,
+    PRIMARY KEY (id),
+    CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT rating CHECK (rating >= 0 AND rating <= 5) NOT VALID,
+    CONSTRAINT unique_order_id UNIQUE (order_id)
+);
+
+ALTER TABLE IF EXISTS public.feedbacks
+    OWNER to gogo;
+
+CREATE TABLE public.notifications
+(
+    id serial NOT NULL,
+    user_id serial NOT NULL,
+    sent_time timestamp without time zone NOT NULL,
+    title character varying(128) NOT NULL,
+    description text,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.notifications
+    OWNER to gogo;
@@ -0,0 +1,3 @@
+-- Items which went out of stock after ordering are excluded from the order total.
+ALTER TABLE public.orders_food
+    ADD COLUMN is_unavailable boolean NOT NULL DEFAULT false;
@@ -0,0 +1,21 @@
+CREATE TYPE "OrderStatus" AS ENUM
+(
+    'Created',
+    'Accepted',
+    'PickedUp',
+    'Delivered',
+    'Cancelled'
+);
+
+ALTER TABLE public.orders
+    ADD COLUMN status "OrderStatus" NOT NULL DEFAULT 'Created';
+
+-- Status was derived from the rider and completion time before. Cancelled orders were deleted.
+UPDATE
+    public.orders
+SET
+    status = CASE
+        WHEN completed_time IS NOT NULL THEN 'Delivered'
+        WHEN rider_id IS NOT NULL THEN 'Accepted'
+        ELSE 'Created'
+    END::"OrderStatus";
@@ -0,0 +1,28 @@
+CREATE TYPE "ActivityKind" AS ENUM
+(
+    'SignUp',
+    'NewDeviceLogin',
+    'FailedLogin',
+    'PasswordChange',
+    'AddressAdded',
+    'AddressDeleted'
+);
+
+CREATE TABLE public.activities
+(
+    id serial NOT NULL,
+    user_id serial NOT NULL,
+    "time" timestamp without time zone NOT NULL,
+    kind "ActivityKind" NOT NULL,
+    ip_address character varying(64),
+    user_agent text,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.activities
+    OWNER to gogo;
@@ -0,0 +1,20 @@
+CREATE TYPE "ApiKeyScope" AS ENUM
+(
+    'CatalogRead'
+);
+
+CREATE TABLE public.api_keys
+(
+    id serial NOT NULL,
+    title character varying(128) NOT NULL,
+    -- SHA256 hash of the key.
+    key_hash character(64) NOT NULL,
+    scope "ApiKeyScope" NOT NULL,
+    create_time timestamp without time zone NOT NULL,
+    last_used_time timestamp without time zone,
+    PRIMARY KEY (id),
+    CONSTRAINT key_hash UNIQUE (key_hash)
+);
+
+ALTER TABLE IF EXISTS public.api_keys
+    OWNER to gogo;
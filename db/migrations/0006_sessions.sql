@@ -0,0 +1,19 @@
+CREATE TABLE public.sessions
+(
+    id serial NOT NULL,
+    user_id serial NOT NULL,
+    ip_address character varying(64),
+    user_agent text,
+    created timestamp without time zone NOT NULL,
+    last_seen timestamp without time zone NOT NULL,
+    is_revoked boolean NOT NULL DEFAULT false,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.sessions
+    OWNER to gogo;
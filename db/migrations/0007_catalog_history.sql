@@ -0,0 +1,36 @@
+CREATE TYPE "CatalogEntity" AS ENUM
+(
+    'Category',
+    'Food'
+);
+
+CREATE TYPE "CatalogAction" AS ENUM
+(
+    'Created',
+    'Updated',
+    'Deleted',
+    'Reverted'
+);
+
+CREATE TABLE public.catalog_history
+(
+    id serial NOT NULL,
+    "time" timestamp without time zone NOT NULL,
+    -- NULL if the manager was deleted.
+    manager_id integer,
+    entity "CatalogEntity" NOT NULL,
+    entity_id integer NOT NULL,
+    action "CatalogAction" NOT NULL,
+    -- Row snapshots without previews. NULL if the entity didn't exist.
+    before jsonb,
+    after jsonb,
+    PRIMARY KEY (id),
+    CONSTRAINT manager_id FOREIGN KEY (manager_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.catalog_history
+    OWNER to gogo;
@@ -0,0 +1,41 @@
+CREATE TYPE "StockMovementKind" AS ENUM
+(
+    'OrderDecrement',
+    'OrderCancellation',
+    'Restock',
+    'Correction'
+);
+
+CREATE TABLE public.stock_movements
+(
+    id serial NOT NULL,
+    food_id serial NOT NULL,
+    "time" timestamp without time zone NOT NULL,
+    kind "StockMovementKind" NOT NULL,
+    -- Negative if the count was decreased.
+    delta integer NOT NULL,
+    count_after integer NOT NULL,
+    order_id integer,
+    -- NULL for automatic movements or if the manager was deleted.
+    manager_id integer,
+    comment text,
+    PRIMARY KEY (id),
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID,
+    CONSTRAINT manager_id FOREIGN KEY (manager_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.stock_movements
+    OWNER to gogo;
@@ -0,0 +1,6 @@
+ALTER TABLE public.addresses
+    ADD COLUMN is_default boolean NOT NULL DEFAULT false;
+
+CREATE UNIQUE INDEX default_address_per_customer
+    ON public.addresses (customer_id)
+    WHERE is_default;
@@ -0,0 +1,13 @@
+-- Contains at most one row. Default values are used if it doesn't exist.
+CREATE TABLE public.settings
+(
+    id boolean NOT NULL DEFAULT true,
+    -- Mutations are rejected for everyone except managers.
+    is_maintenance boolean NOT NULL DEFAULT false,
+    maintenance_message text,
+    PRIMARY KEY (id),
+    CONSTRAINT single_row CHECK (id)
+);
+
+ALTER TABLE IF EXISTS public.settings
+    OWNER to gogo;
@@ -0,0 +1,3 @@
+-- Set when personal data is erased on request.
+ALTER TABLE public.users
+    ADD COLUMN erased_time timestamp without time zone;
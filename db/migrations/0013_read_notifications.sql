@@ -0,0 +1,2 @@
+ALTER TABLE public.notifications
+    ADD COLUMN read_time timestamp without time zone;
@@ -0,0 +1,20 @@
+ALTER TYPE "OrderStatus" ADD VALUE 'ReadyForPickup' AFTER 'PickedUp';
+
+CREATE TYPE "FulfillmentType" AS ENUM
+(
+    'Delivery',
+    'Pickup'
+);
+
+-- NULL for pickup orders.
+ALTER TABLE public.orders
+    ALTER COLUMN address_id DROP DEFAULT,
+    ALTER COLUMN address_id DROP NOT NULL;
+DROP SEQUENCE public.orders_address_id_seq;
+
+ALTER TABLE public.orders
+    ADD COLUMN fulfillment "FulfillmentType" NOT NULL DEFAULT 'Delivery',
+    -- Shown by the customer at the counter to receive a pickup order.
+    ADD COLUMN pickup_code character(6),
+    ADD CONSTRAINT delivery_address
+        CHECK (fulfillment = 'Pickup' OR address_id IS NOT NULL);
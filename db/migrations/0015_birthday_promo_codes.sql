@@ -0,0 +1,47 @@
+-- Applied using a promo code.
+ALTER TABLE public.orders
+    ADD COLUMN discount_percent smallint NOT NULL DEFAULT 0;
+
+CREATE TYPE "PromoCodeReason" AS ENUM
+(
+    'Birthday'
+);
+
+CREATE TABLE public.promo_codes
+(
+    id serial NOT NULL,
+    customer_id integer NOT NULL,
+    code character varying(16) NOT NULL,
+    reason "PromoCodeReason" NOT NULL,
+    discount_percent smallint NOT NULL,
+    create_time timestamp without time zone NOT NULL,
+    expire_time timestamp without time zone NOT NULL,
+    -- Order for which the code was used.
+    order_id integer,
+    PRIMARY KEY (id),
+    CONSTRAINT code UNIQUE (code),
+    CONSTRAINT customer_id FOREIGN KEY (customer_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID,
+    CONSTRAINT discount_percent CHECK (discount_percent > 0 AND discount_percent <= 100)
+);
+
+ALTER TABLE IF EXISTS public.promo_codes
+    OWNER to gogo;
+
+ALTER TABLE public.settings
+    -- Customers get a promo code on their birthday.
+    ADD COLUMN is_birthday_promo_enabled boolean NOT NULL DEFAULT false,
+    ADD COLUMN birthday_discount_percent smallint NOT NULL DEFAULT 10,
+    -- Number of days the birthday promo code can be used.
+    ADD COLUMN birthday_promo_days integer NOT NULL DEFAULT 7,
+    ADD CONSTRAINT birthday_discount_percent
+        CHECK (birthday_discount_percent > 0 AND birthday_discount_percent <= 100),
+    ADD CONSTRAINT birthday_promo_days CHECK (birthday_promo_days > 0);
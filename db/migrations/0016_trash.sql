@@ -0,0 +1,5 @@
+-- Set when the address or the favorite is moved to the trash.
+ALTER TABLE public.addresses
+    ADD COLUMN delete_time timestamp without time zone;
+ALTER TABLE public.favorites
+    ADD COLUMN delete_time timestamp without time zone;
@@ -0,0 +1,41 @@
+CREATE TABLE public.locations
+(
+    id serial NOT NULL,
+    title character varying(128) NOT NULL,
+    -- Localities of the delivery addresses served by the location.
+    localities character varying(128)[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY (id)
+);
+
+ALTER TABLE IF EXISTS public.locations
+    OWNER to gogo;
+
+CREATE TABLE public.location_stock
+(
+    location_id integer NOT NULL,
+    food_id integer NOT NULL,
+    count integer NOT NULL DEFAULT 0,
+    PRIMARY KEY (location_id, food_id),
+    CONSTRAINT location_id FOREIGN KEY (location_id)
+        REFERENCES public.locations (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT non_negative_count CHECK (count >= 0) NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.location_stock
+    OWNER to gogo;
+
+ALTER TABLE public.orders
+    -- Location the order is fulfilled from, NULL if there are no locations.
+    ADD COLUMN location_id integer,
+    ADD CONSTRAINT location_id FOREIGN KEY (location_id)
+        REFERENCES public.locations (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+ALTER TYPE "OrderStatus" ADD VALUE 'Queued' BEFORE 'Created';
+
+ALTER TABLE public.settings
+    -- New orders are queued when the number of active orders reaches it. NULL means no limit.
+    ADD COLUMN order_capacity integer,
+    ADD CONSTRAINT order_capacity CHECK (order_capacity > 0);
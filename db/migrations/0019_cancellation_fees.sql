@@ -0,0 +1,13 @@
+-- Charged from the customer who cancelled the order.
+ALTER TABLE public.orders
+    ADD COLUMN cancellation_fee numeric(7, 2);
+
+ALTER TABLE public.settings
+    -- Percent of the order total charged when a customer cancels an accepted order.
+    ADD COLUMN accepted_cancellation_fee_percent smallint NOT NULL DEFAULT 0,
+    -- Same, but after the order is picked up. NULL means it can't be cancelled.
+    ADD COLUMN picked_up_cancellation_fee_percent smallint,
+    ADD CONSTRAINT accepted_cancellation_fee_percent
+        CHECK (accepted_cancellation_fee_percent >= 0 AND accepted_cancellation_fee_percent <= 100),
+    ADD CONSTRAINT picked_up_cancellation_fee_percent
+        CHECK (picked_up_cancellation_fee_percent >= 0 AND picked_up_cancellation_fee_percent <= 100);
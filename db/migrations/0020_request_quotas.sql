@@ -0,0 +1,11 @@
+ALTER TABLE public.users
+    ADD COLUMN request_count bigint NOT NULL DEFAULT 0,
+    ADD COLUMN last_request_time timestamp without time zone,
+    -- Beginning of the minute in which 'quota_window_requests' were sent.
+    ADD COLUMN quota_window_start timestamp without time zone,
+    ADD COLUMN quota_window_requests integer NOT NULL DEFAULT 0;
+
+ALTER TABLE public.api_keys
+    ADD COLUMN request_count bigint NOT NULL DEFAULT 0,
+    ADD COLUMN quota_window_start timestamp without time zone,
+    ADD COLUMN quota_window_requests integer NOT NULL DEFAULT 0;
@@ -0,0 +1,29 @@
+ALTER TYPE "PromoCodeReason" ADD VALUE 'LateDelivery';
+
+-- Delivery is promised by this time. NULL if the order isn't compensated when late.
+ALTER TABLE public.orders
+    ADD COLUMN promised_time timestamp without time zone;
+
+ALTER TABLE public.promo_codes
+    -- Order delivered late for which the code was granted.
+    ADD COLUMN compensated_order_id integer,
+    ADD CONSTRAINT compensated_order_id_unique UNIQUE (compensated_order_id),
+    ADD CONSTRAINT compensated_order_id FOREIGN KEY (compensated_order_id)
+        REFERENCES public.orders (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID;
+
+ALTER TABLE public.settings
+    -- Delivery time promised to customers. NULL disables late delivery compensation.
+    ADD COLUMN promised_delivery_minutes integer,
+    -- Orders delivered later than promised by more than this are compensated.
+    ADD COLUMN late_delivery_tolerance_minutes integer NOT NULL DEFAULT 0,
+    ADD COLUMN late_delivery_discount_percent smallint NOT NULL DEFAULT 10,
+    -- Number of days the compensation promo code can be used.
+    ADD COLUMN late_delivery_promo_days integer NOT NULL DEFAULT 14,
+    ADD CONSTRAINT promised_delivery_minutes CHECK (promised_delivery_minutes > 0),
+    ADD CONSTRAINT late_delivery_tolerance_minutes CHECK (late_delivery_tolerance_minutes >= 0),
+    ADD CONSTRAINT late_delivery_discount_percent
+        CHECK (late_delivery_discount_percent > 0 AND late_delivery_discount_percent <= 100),
+    ADD CONSTRAINT late_delivery_promo_days CHECK (late_delivery_promo_days > 0);
@@ -0,0 +1,24 @@
+-- Periodic reports of the locations (zones) served by online riders.
+CREATE TABLE public.rider_pings
+(
+    id serial NOT NULL,
+    rider_id integer NOT NULL,
+    location_id integer NOT NULL,
+    "time" timestamp without time zone NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT rider_id FOREIGN KEY (rider_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT location_id FOREIGN KEY (location_id)
+        REFERENCES public.locations (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+CREATE INDEX rider_pings_time ON public.rider_pings ("time");
+
+ALTER TABLE IF EXISTS public.rider_pings
+    OWNER to gogo;
@@ -0,0 +1,3 @@
+-- Stock could become negative before, so only the new changes are checked.
+ALTER TABLE public.food
+    ADD CONSTRAINT non_negative_count CHECK (count >= 0) NOT VALID;
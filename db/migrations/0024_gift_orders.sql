@@ -0,0 +1,8 @@
+ALTER TABLE public.orders
+    -- Set if the order is a gift delivered to another person.
+    ADD COLUMN gift_recipient_name character varying(128),
+    ADD COLUMN gift_recipient_phone character varying(32),
+    -- Printed on the receipt.
+    ADD COLUMN gift_message text,
+    ADD CONSTRAINT gift_recipient
+        CHECK ((gift_recipient_name IS NULL) = (gift_recipient_phone IS NULL));
@@ -0,0 +1 @@
+ALTER TYPE "StockMovementKind" ADD VALUE 'Adjustment';
@@ -0,0 +1,3 @@
+-- Delivery instructions for the rider.
+ALTER TABLE public.orders
+    ADD COLUMN comment text;
@@ -0,0 +1,12 @@
+ALTER TABLE public.orders
+    -- Time the order was taken by a rider. Unknown for orders taken before.
+    ADD COLUMN accept_time timestamp without time zone,
+    -- Set when managers were notified that the order breached the SLA.
+    ADD COLUMN sla_breach_time timestamp without time zone;
+
+ALTER TABLE public.settings
+    -- SLA targets for delivery orders counted from creation. NULL means no target.
+    ADD COLUMN sla_accept_minutes integer,
+    ADD COLUMN sla_delivery_minutes integer,
+    ADD CONSTRAINT sla_accept_minutes CHECK (sla_accept_minutes > 0),
+    ADD CONSTRAINT sla_delivery_minutes CHECK (sla_delivery_minutes > 0);
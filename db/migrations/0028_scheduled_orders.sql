@@ -0,0 +1,18 @@
+ALTER TYPE "OrderStatus" ADD VALUE 'Scheduled' BEFORE 'Queued';
+
+-- Order is dispatched shortly before this time. NULL for ASAP orders.
+ALTER TABLE public.orders
+    ADD COLUMN scheduled_for timestamp without time zone;
+
+ALTER TABLE public.settings
+    -- Orders can be scheduled only within opening hours. NULL means always open.
+    ADD COLUMN opening_time time without time zone,
+    -- Can be earlier than the opening time if the business works past midnight.
+    ADD COLUMN closing_time time without time zone,
+    -- Orders must be scheduled at least this far ahead.
+    ADD COLUMN min_schedule_minutes integer NOT NULL DEFAULT 60,
+    -- Scheduled orders are dispatched this long before the scheduled time.
+    ADD COLUMN dispatch_minutes integer NOT NULL DEFAULT 45,
+    ADD CONSTRAINT opening_hours CHECK ((opening_time IS NULL) = (closing_time IS NULL)),
+    ADD CONSTRAINT min_schedule_minutes CHECK (min_schedule_minutes >= 0),
+    ADD CONSTRAINT dispatch_minutes CHECK (dispatch_minutes >= 0);
@@ -0,0 +1,15 @@
+-- NULL for addresses entered by gift recipients, they aren't shown to the buyer.
+ALTER TABLE public.addresses
+    ALTER COLUMN customer_id DROP DEFAULT,
+    ALTER COLUMN customer_id DROP NOT NULL;
+DROP SEQUENCE public.addresses_customer_id_seq;
+
+ALTER TABLE public.orders
+    -- SHA-256 of the token of the link which the gift recipient uses to enter
+    -- the address. Kept after the address is entered to hide it from the buyer.
+    ADD COLUMN gift_address_token character(64),
+    ADD COLUMN gift_address_expire_time timestamp without time zone,
+    DROP CONSTRAINT delivery_address,
+    -- Gift recipient can enter the address later.
+    ADD CONSTRAINT delivery_address
+        CHECK (fulfillment = 'Pickup' OR address_id IS NOT NULL OR gift_recipient_name IS NOT NULL);
@@ -0,0 +1,3 @@
+-- Optionally given by the user who cancelled the order.
+ALTER TABLE public.orders
+    ADD COLUMN cancellation_reason text;
@@ -0,0 +1,14 @@
+-- Service disruptions announced by managers on the public status page.
+CREATE TABLE public.incidents
+(
+    id serial NOT NULL,
+    title character varying(128) NOT NULL,
+    description text,
+    start_time timestamp without time zone NOT NULL,
+    -- NULL while the incident is ongoing.
+    resolve_time timestamp without time zone,
+    PRIMARY KEY (id)
+);
+
+ALTER TABLE IF EXISTS public.incidents
+    OWNER to gogo;
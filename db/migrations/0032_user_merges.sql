@@ -0,0 +1,27 @@
+-- Audit of duplicate customer accounts merged by managers.
+CREATE TABLE public.user_merges
+(
+    id serial NOT NULL,
+    -- Merged account is deleted, so only its name is kept.
+    source_username character varying(64) NOT NULL,
+    target_user_id integer NOT NULL,
+    manager_id integer,
+    merge_time timestamp without time zone NOT NULL,
+    order_count integer NOT NULL,
+    address_count integer NOT NULL,
+    favorite_count integer NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT target_user_id FOREIGN KEY (target_user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT manager_id FOREIGN KEY (manager_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.user_merges
+    OWNER to gogo;
@@ -0,0 +1,4 @@
+ALTER TABLE public.feedbacks
+    -- Rating of the rider who delivered the order, separate from the order rating.
+    ADD COLUMN rider_rating smallint,
+    ADD CONSTRAINT rider_rating CHECK (rider_rating >= 0 AND rider_rating <= 5);
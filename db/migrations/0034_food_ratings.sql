@@ -0,0 +1,30 @@
+-- Aggregated from ratings of orders containing the food. Updated with feedbacks.
+ALTER TABLE public.food
+    ADD COLUMN average_rating double precision,
+    ADD COLUMN ratings_count integer NOT NULL DEFAULT 0;
+
+-- Items which were unavailable and excluded from the order don't get its rating.
+UPDATE
+    public.food
+SET
+    average_rating = stats.average_rating,
+    ratings_count = stats.ratings_count
+FROM
+    (
+        SELECT
+            orders_food.food_id,
+            avg(feedbacks.rating)::double precision AS average_rating,
+            count(feedbacks.rating)::integer AS ratings_count
+        FROM
+            public.orders_food
+        JOIN
+            public.feedbacks
+        ON
+            feedbacks.order_id = orders_food.order_id
+        WHERE
+            NOT orders_food.is_unavailable
+        GROUP BY
+            orders_food.food_id
+    ) AS stats
+WHERE
+    food.id = stats.food_id;
@@ -0,0 +1,61 @@
+-- Completed orders moved out of the hot tables after ORDER_ARCHIVE_DAYS.
+-- Rows are moved using SELECT *, so columns must match the hot tables.
+CREATE TABLE public.orders_archive
+(
+    LIKE public.orders INCLUDING DEFAULTS,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX orders_archive_customer_id ON public.orders_archive (customer_id);
+
+CREATE TABLE public.orders_food_archive
+(
+    LIKE public.orders_food INCLUDING DEFAULTS,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX orders_food_archive_order_id ON public.orders_food_archive (order_id);
+
+CREATE TABLE public.feedbacks_archive
+(
+    LIKE public.feedbacks INCLUDING DEFAULTS,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX feedbacks_archive_order_id ON public.feedbacks_archive (order_id);
+
+-- Combined views for history and reports. Must be recreated when columns are added.
+CREATE VIEW public.all_orders AS
+SELECT * FROM public.orders
+UNION ALL
+SELECT * FROM public.orders_archive;
+
+CREATE VIEW public.all_orders_food AS
+SELECT * FROM public.orders_food
+UNION ALL
+SELECT * FROM public.orders_food_archive;
+
+CREATE VIEW public.all_feedbacks AS
+SELECT * FROM public.feedbacks
+UNION ALL
+SELECT * FROM public.feedbacks_archive;
+
+ALTER TABLE IF EXISTS public.orders_archive
+    OWNER to gogo;
+ALTER TABLE IF EXISTS public.orders_food_archive
+    OWNER to gogo;
+ALTER TABLE IF EXISTS public.feedbacks_archive
+    OWNER to gogo;
+ALTER VIEW IF EXISTS public.all_orders
+    OWNER to gogo;
+ALTER VIEW IF EXISTS public.all_orders_food
+    OWNER to gogo;
+ALTER VIEW IF EXISTS public.all_feedbacks
+    OWNER to gogo;
+
+-- Order IDs aren't foreign keys as orders can be archived.
+ALTER TABLE public.promo_codes
+    DROP CONSTRAINT order_id,
+    DROP CONSTRAINT compensated_order_id;
+ALTER TABLE public.stock_movements
+    DROP CONSTRAINT order_id;
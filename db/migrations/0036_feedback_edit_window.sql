@@ -0,0 +1,35 @@
+ALTER TABLE public.feedbacks
+    ADD COLUMN create_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE public.feedbacks_archive
+    ADD COLUMN create_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- Feedbacks left before are considered left on completion of the order,
+-- so their edit window isn't reopened.
+UPDATE
+    public.feedbacks
+SET
+    create_time = orders.completed_time
+FROM
+    public.orders
+WHERE
+    orders.id = feedbacks.order_id
+    AND orders.completed_time IS NOT NULL;
+UPDATE
+    public.feedbacks_archive AS feedbacks
+SET
+    create_time = orders.completed_time
+FROM
+    public.all_orders AS orders
+WHERE
+    orders.id = feedbacks.order_id
+    AND orders.completed_time IS NOT NULL;
+
+CREATE OR REPLACE VIEW public.all_feedbacks AS
+SELECT * FROM public.feedbacks
+UNION ALL
+SELECT * FROM public.feedbacks_archive;
+
+ALTER TABLE public.settings
+    -- Customers can edit or delete their feedbacks within this time after leaving them.
+    ADD COLUMN feedback_edit_hours integer NOT NULL DEFAULT 24,
+    ADD CONSTRAINT feedback_edit_hours CHECK (feedback_edit_hours >= 0);
@@ -0,0 +1,12 @@
+-- Hidden by a manager. Such feedbacks aren't counted in ratings.
+ALTER TABLE public.feedbacks
+    ADD COLUMN is_hidden boolean NOT NULL DEFAULT false,
+    ADD COLUMN hidden_reason text;
+ALTER TABLE public.feedbacks_archive
+    ADD COLUMN is_hidden boolean NOT NULL DEFAULT false,
+    ADD COLUMN hidden_reason text;
+
+CREATE OR REPLACE VIEW public.all_feedbacks AS
+SELECT * FROM public.feedbacks
+UNION ALL
+SELECT * FROM public.feedbacks_archive;
@@ -0,0 +1,25 @@
+-- Named lists which users can organize their favorites into.
+CREATE TABLE public.favorite_collections
+(
+    id serial NOT NULL,
+    user_id integer NOT NULL,
+    title text NOT NULL,
+    create_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+ALTER TABLE IF EXISTS public.favorite_collections
+    OWNER to gogo;
+
+ALTER TABLE public.favorites
+    -- Favorites are left without a collection when it's deleted.
+    ADD COLUMN collection_id integer,
+    ADD CONSTRAINT collection_id FOREIGN KEY (collection_id)
+        REFERENCES public.favorite_collections (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE SET NULL;
@@ -0,0 +1,23 @@
+-- Resumable uploads assembled from chunks before they're used as previews.
+CREATE TABLE public.uploads
+(
+    id serial NOT NULL,
+    token text NOT NULL,
+    user_id integer NOT NULL,
+    -- Declared size of the complete upload in bytes.
+    size integer NOT NULL,
+    data bytea NOT NULL DEFAULT '',
+    create_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT user_id FOREIGN KEY (user_id)
+        REFERENCES public.users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID,
+    CONSTRAINT unique_token UNIQUE (token),
+    CONSTRAINT size CHECK (size > 0),
+    CONSTRAINT data CHECK (octet_length(data) <= size)
+);
+
+ALTER TABLE IF EXISTS public.uploads
+    OWNER to gogo;
@@ -0,0 +1,18 @@
+-- Daily totals for reconciliation with the payment provider. Days are settled once
+-- after they end, days without finished orders aren't recorded.
+CREATE TABLE public.settlements
+(
+    day date NOT NULL,
+    delivered_count integer NOT NULL,
+    cancelled_count integer NOT NULL,
+    -- Price of the delivered items before discounts.
+    gross numeric(12, 2) NOT NULL,
+    discounts numeric(12, 2) NOT NULL,
+    -- Cancellation fees.
+    fees numeric(12, 2) NOT NULL,
+    create_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (day)
+);
+
+ALTER TABLE IF EXISTS public.settlements
+    OWNER to gogo;
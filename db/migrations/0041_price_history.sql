@@ -0,0 +1,28 @@
+-- Prices of food since the time they were set. The latest row holds the current price.
+CREATE TABLE public.price_history
+(
+    id serial NOT NULL,
+    food_id integer NOT NULL,
+    price numeric(7, 2) NOT NULL,
+    change_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT food_id FOREIGN KEY (food_id)
+        REFERENCES public.food (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE
+        NOT VALID
+);
+
+CREATE INDEX price_history_food_id_idx
+    ON public.price_history (food_id, change_time);
+
+ALTER TABLE IF EXISTS public.price_history
+    OWNER to gogo;
+
+-- Current prices are the first known ones.
+INSERT INTO public.price_history (food_id, price)
+SELECT
+    id,
+    price
+FROM
+    public.food;
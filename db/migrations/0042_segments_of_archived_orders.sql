@@ -0,0 +1,32 @@
+-- Segments take archived orders into account, so long-standing customers
+-- aren't moved to the "New" segment once their orders are archived.
+CREATE OR REPLACE VIEW public.customer_segments AS
+SELECT
+    users.id AS user_id,
+    CASE
+        WHEN stats.last_order_time < CURRENT_TIMESTAMP - interval '60 days' THEN 'Lapsed'
+        WHEN stats.recent_orders >= 10 THEN 'Vip'
+        WHEN stats.total_orders >= 2 THEN 'Regular'
+        ELSE 'New'
+    END::"CustomerSegment" AS segment
+FROM
+    users
+LEFT JOIN
+(
+    SELECT
+        customer_id,
+        COUNT(*) AS total_orders,
+        COUNT(*) FILTER (WHERE create_time >= CURRENT_TIMESTAMP - interval '90 days')
+            AS recent_orders,
+        MAX(create_time) AS last_order_time
+    FROM
+        all_orders
+    WHERE
+        status = 'Delivered'
+    GROUP BY
+        customer_id
+) AS stats
+ON
+    stats.customer_id = users.id
+WHERE
+    users.role = 'Customer';
@@ -0,0 +1,7 @@
+-- Each impersonated request is recorded for both the customer and the manager.
+-- The new values aren't used in this transaction, so they can be added in it.
+ALTER TYPE "ActivityKind" ADD VALUE 'Impersonated';
+ALTER TYPE "ActivityKind" ADD VALUE 'Impersonating';
+
+-- Operation, access mode and the other party of the impersonation.
+ALTER TABLE public.activities ADD COLUMN details text;
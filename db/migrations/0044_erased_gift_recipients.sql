@@ -0,0 +1,11 @@
+-- Recipient of a gift is erased together with the buyer, so a gift which was
+-- cancelled before the recipient entered the address is left without both.
+ALTER TABLE public.orders
+    DROP CONSTRAINT delivery_address,
+    ADD CONSTRAINT delivery_address
+        CHECK (
+            fulfillment = 'Pickup'
+            OR status = 'Cancelled'
+            OR address_id IS NOT NULL
+            OR gift_recipient_name IS NOT NULL
+        );
@@ -0,0 +1,126 @@
+-- Columns are listed explicitly, so a column added to a hot table doesn't shift
+-- the others. Columns of the archive tables are compared with the hot ones on startup.
+CREATE OR REPLACE VIEW public.all_orders AS
+SELECT
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason
+FROM
+    public.orders
+UNION ALL
+SELECT
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason
+FROM
+    public.orders_archive;
+
+CREATE OR REPLACE VIEW public.all_orders_food AS
+SELECT id, order_id, food_id, count, is_unavailable FROM public.orders_food
+UNION ALL
+SELECT id, order_id, food_id, count, is_unavailable FROM public.orders_food_archive;
+
+CREATE OR REPLACE VIEW public.all_feedbacks AS
+SELECT
+    id, order_id, rating, comment, rider_rating, create_time, is_hidden, hidden_reason
+FROM
+    public.feedbacks
+UNION ALL
+SELECT
+    id, order_id, rating, comment, rider_rating, create_time, is_hidden, hidden_reason
+FROM
+    public.feedbacks_archive;
+
+-- Items and feedbacks are archived together with their orders.
+ALTER TABLE public.orders_food_archive
+    ADD CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders_archive (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE;
+ALTER TABLE public.feedbacks_archive
+    ADD CONSTRAINT order_id FOREIGN KEY (order_id)
+        REFERENCES public.orders_archive (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE CASCADE;
+
+-- Rows which lost their orders after the foreign keys were dropped.
+UPDATE public.promo_codes
+SET
+    order_id = NULL
+WHERE
+    order_id NOT IN (SELECT id FROM public.all_orders);
+UPDATE public.promo_codes
+SET
+    compensated_order_id = NULL
+WHERE
+    compensated_order_id NOT IN (SELECT id FROM public.all_orders);
+UPDATE public.stock_movements
+SET
+    order_id = NULL
+WHERE
+    order_id NOT IN (SELECT id FROM public.all_orders);
+
+-- Promo codes and stock movements reference orders in either table, which can't
+-- be expressed by foreign keys. Orders are moved to the archive in one statement,
+-- and the triggers are fired after it, so a moved order is found in the archive.
+CREATE FUNCTION public.check_order_references() RETURNS trigger
+    LANGUAGE plpgsql
+AS $$
+DECLARE
+    column_name text;
+    referenced_id integer;
+BEGIN
+    FOREACH column_name IN ARRAY TG_ARGV LOOP
+        referenced_id := (to_jsonb(NEW) ->> column_name)::integer;
+        IF referenced_id IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM public.orders WHERE id = referenced_id)
+            AND NOT EXISTS (SELECT 1 FROM public.orders_archive WHERE id = referenced_id)
+        THEN
+            RAISE foreign_key_violation USING MESSAGE = format(
+                '%s.%s references order %s which doesn''t exist',
+                TG_TABLE_NAME, column_name, referenced_id
+            );
+        END IF;
+    END LOOP;
+    RETURN NULL;
+END
+$$;
+
+-- Replaces ON DELETE SET NULL of the dropped foreign keys.
+CREATE FUNCTION public.release_order_references() RETURNS trigger
+    LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM public.orders WHERE id = OLD.id)
+        AND NOT EXISTS (SELECT 1 FROM public.orders_archive WHERE id = OLD.id)
+    THEN
+        UPDATE public.promo_codes SET order_id = NULL WHERE order_id = OLD.id;
+        UPDATE public.promo_codes
+            SET compensated_order_id = NULL WHERE compensated_order_id = OLD.id;
+        UPDATE public.stock_movements SET order_id = NULL WHERE order_id = OLD.id;
+    END IF;
+    RETURN NULL;
+END
+$$;
+
+CREATE CONSTRAINT TRIGGER order_id
+    AFTER INSERT OR UPDATE OF order_id, compensated_order_id ON public.promo_codes
+    FOR EACH ROW EXECUTE FUNCTION public.check_order_references('order_id', 'compensated_order_id');
+CREATE CONSTRAINT TRIGGER order_id
+    AFTER INSERT OR UPDATE OF order_id ON public.stock_movements
+    FOR EACH ROW EXECUTE FUNCTION public.check_order_references('order_id');
+CREATE TRIGGER release_order_references
+    AFTER DELETE ON public.orders
+    FOR EACH ROW EXECUTE FUNCTION public.release_order_references();
+CREATE TRIGGER release_order_references
+    AFTER DELETE ON public.orders_archive
+    FOR EACH ROW EXECUTE FUNCTION public.release_order_references();
+
+ALTER FUNCTION public.check_order_references()
+    OWNER TO gogo;
+ALTER FUNCTION public.release_order_references()
+    OWNER TO gogo;
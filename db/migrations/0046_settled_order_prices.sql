@@ -0,0 +1,102 @@
+-- Settlements are computed from the prices at the time of ordering
+-- and cancellations are dated by the time they were made.
+ALTER TABLE public.orders_food
+    ADD COLUMN price numeric(7, 2);
+ALTER TABLE public.orders_food_archive
+    ADD COLUMN price numeric(7, 2);
+ALTER TABLE public.orders
+    ADD COLUMN cancel_time timestamp without time zone;
+ALTER TABLE public.orders_archive
+    ADD COLUMN cancel_time timestamp without time zone;
+
+-- Prices of the existing items are looked up in the history as of the order time.
+-- Orders made before the history was recorded get the first known price.
+CREATE FUNCTION pg_temp.ordered_price(ordered_food_id integer, order_time timestamp)
+    RETURNS numeric
+    LANGUAGE sql
+AS $$
+    SELECT
+        COALESCE(
+            (
+                SELECT
+                    price
+                FROM
+                    public.price_history
+                WHERE
+                    food_id = ordered_food_id
+                AND
+                    change_time <= order_time
+                ORDER BY
+                    change_time DESC
+                LIMIT 1
+            ),
+            (
+                SELECT
+                    price
+                FROM
+                    public.price_history
+                WHERE
+                    food_id = ordered_food_id
+                ORDER BY
+                    change_time
+                LIMIT 1
+            ),
+            (SELECT price FROM public.food WHERE id = ordered_food_id)
+        )
+$$;
+
+UPDATE public.orders_food
+SET
+    price = pg_temp.ordered_price(orders_food.food_id, orders.create_time)
+FROM
+    public.orders
+WHERE
+    orders.id = orders_food.order_id;
+UPDATE public.orders_food_archive
+SET
+    price = pg_temp.ordered_price(orders_food_archive.food_id, orders_archive.create_time)
+FROM
+    public.orders_archive
+WHERE
+    orders_archive.id = orders_food_archive.order_id;
+
+ALTER TABLE public.orders_food
+    ALTER COLUMN price SET NOT NULL;
+ALTER TABLE public.orders_food_archive
+    ALTER COLUMN price SET NOT NULL;
+
+-- Time of the existing cancellations is unknown, they were settled by the order time.
+UPDATE public.orders
+SET
+    cancel_time = create_time
+WHERE
+    status = 'Cancelled';
+UPDATE public.orders_archive
+SET
+    cancel_time = create_time
+WHERE
+    status = 'Cancelled';
+
+CREATE OR REPLACE VIEW public.all_orders AS
+SELECT
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason, cancel_time
+FROM
+    public.orders
+UNION ALL
+SELECT
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason, cancel_time
+FROM
+    public.orders_archive;
+
+CREATE OR REPLACE VIEW public.all_orders_food AS
+SELECT id, order_id, food_id, count, is_unavailable, price FROM public.orders_food
+UNION ALL
+SELECT id, order_id, food_id, count, is_unavailable, price FROM public.orders_food_archive;
@@ -0,0 +1,6 @@
+-- Sessions aren't revoked one by one anymore: credentials are sent with each
+-- request, so only changing the password locks out the other devices.
+DELETE FROM public.sessions
+    WHERE is_revoked;
+ALTER TABLE public.sessions
+    DROP COLUMN is_revoked;
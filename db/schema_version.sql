@@ -0,0 +1,15 @@
+-- Applied migrations from db/migrations. Created by the server before applying them.
+-- Suppresses the notice that the table already exists.
+SET client_min_messages = warning;
+
+CREATE TABLE IF NOT EXISTS public.schema_version
+(
+    version integer NOT NULL,
+    name text NOT NULL,
+    -- SHA-256 of the migration content, so changes of applied migrations are detected.
+    checksum character(64) NOT NULL,
+    apply_time timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (version)
+);
+
+RESET client_min_messages;
@@ -0,0 +1,222 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Command line interface: serving (by default) and administrative commands,
+//! so a fresh database can be bootstrapped without `psql`.
+
+use std::{io, path::PathBuf};
+
+use anyhow::{anyhow, bail};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+use log::info;
+
+use crate::{
+    config::Config,
+    db, random_token, sha256,
+    types::{CatalogDocument, User, UserRole, Validate},
+};
+
+/// Usernames and roles of the users created by `seed-demo-data`.
+const DEMO_USERS: &[(&str, UserRole)] = &[
+    ("demo-manager", UserRole::Manager),
+    ("demo-rider", UserRole::Rider),
+    ("demo-customer", UserRole::Customer),
+];
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the server (default)
+    Serve,
+    /// Create a user, the password is read from the standard input
+    CreateUser {
+        /// Name to sign in with
+        #[arg(long, value_name = "NAME")]
+        username: String,
+        /// Required for every user, as for the ones who sign up
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        birth_date: NaiveDate,
+        /// One of customer, rider or manager
+        #[arg(long, default_value = "customer", value_parser = parse_role)]
+        role: UserRole,
+    },
+    /// Create demo users and import a demo catalog
+    SeedDemoData,
+    /// Apply the pending database migrations
+    Migrate {
+        /// Mark a database created before migrations as created by the initial one
+        #[arg(long)]
+        baseline: bool,
+    },
+    /// Print the hash of the password read from the standard input
+    HashPassword,
+}
+
+/// Options which override the configuration. They're accepted by every command.
+#[derive(Args)]
+struct ConfigOptions {
+    /// TOML configuration file [env: CONFIG_FILE]
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Address to listen on [env: SERVER_ADDRESS]
+    #[arg(long, global = true)]
+    address: Option<String>,
+    /// Port to listen on [env: SERVER_PORT]
+    #[arg(long, global = true)]
+    port: Option<u16>,
+    /// Also serve metrics there, e.g. 127.0.0.1:9100 [env: METRICS_ADDRESS]
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    metrics_address: Option<String>,
+}
+
+#[derive(Parser)]
+#[command(version, about = "Gogo Delivery server and administrative commands")]
+struct Arguments {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    options: ConfigOptions,
+}
+
+pub struct Cli {
+    pub command: Command,
+    pub config: Config,
+}
+
+impl Cli {
+    /// Parses the command line arguments of the process and loads the configuration.
+    /// Prints the usage and exits if help is requested or the arguments are invalid.
+    pub fn parse() -> anyhow::Result<Self> {
+        let Arguments { command, options } = Arguments::parse();
+        let mut config = Config::load(options.config)?;
+        if let Some(address) = options.address {
+            config.server.address = address;
+        }
+        if let Some(port) = options.port {
+            config.server.port = port;
+        }
+        if options.metrics_address.is_some() {
+            config.server.metrics_address = options.metrics_address;
+        }
+        Ok(Self {
+            command: command.unwrap_or(Command::Serve),
+            config,
+        })
+    }
+}
+
+fn parse_role(role: &str) -> Result<UserRole, String> {
+    match role {
+        "customer" => Ok(UserRole::Customer),
+        "rider" => Ok(UserRole::Rider),
+        "manager" => Ok(UserRole::Manager),
+        _ => Err("must be customer, rider or manager".to_string()),
+    }
+}
+
+/// Runs an administrative command, [Command::Serve] must be handled by the caller.
+pub async fn run(command: Command, config: &Config) -> anyhow::Result<()> {
+    if let Command::HashPassword = command {
+        println!("{}", sha256(&read_password()?));
+        return Ok(());
+    }
+
+    let db = db::Client::connect(config.connection_string()?, &config.database).await?;
+    let baseline = matches!(command, Command::Migrate { baseline: true });
+    if config.database.migrate || matches!(command, Command::Migrate { .. }) {
+        match db.migrate(baseline).await?.as_slice() {
+            [] => info!("There are no pending migrations"),
+            versions => info!("Applied migrations {versions:?}"),
+        }
+    }
+    match command {
+        Command::Serve | Command::HashPassword => unreachable!("handled above"),
+        Command::Migrate { .. } => {}
+        Command::CreateUser {
+            username,
+            birth_date,
+            role,
+        } => {
+            let id =
+                create_user(&db, username.clone(), birth_date, role, &read_password()?).await?;
+            info!("Created {role:?} \"{username}\" with ID {id}");
+        }
+        Command::SeedDemoData => {
+            for (username, role) in DEMO_USERS {
+                if db.find_user_by_name(username).await?.is_some() {
+                    info!("User \"{username}\" already exists");
+                    continue;
+                }
+                let password = random_token();
+                let birth_date = NaiveDate::from_ymd_opt(1990, 1, 1).unwrap();
+                create_user(&db, username.to_string(), birth_date, *role, &password).await?;
+                info!("Created {role:?} \"{username}\"");
+                // Not logged, so the password doesn't end up in collected logs.
+                println!("{username} {password}");
+            }
+            let document: CatalogDocument =
+                serde_json::from_str(include_str!("../db/demo_catalog.json"))?;
+            let summary = db.import_catalog(DEMO_USERS[0].0, &document).await?;
+            info!(
+                "Imported demo catalog: {} categories and {} food created, \
+                 {} categories and {} food updated",
+                summary.created_categories,
+                summary.created_food,
+                summary.updated_categories,
+                summary.updated_food
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn create_user(
+    db: &db::Client,
+    username: String,
+    birth_date: NaiveDate,
+    role: UserRole,
+    password: &str,
+) -> anyhow::Result<i32> {
+    let user = User {
+        id: 0,
+        username,
+        password: sha256(password),
+        first_name: None,
+        last_name: None,
+        birth_date,
+        role,
+        segment: None,
+    };
+    user.validate().map_err(|err| anyhow!(err.message()))?;
+    let id = db.add_user(user.clone()).await?;
+    // New users are customers.
+    if role != UserRole::Customer {
+        db.set_user_role(&user.username, role).await?;
+    }
+    Ok(id)
+}
+
+/// Reads the first line of the standard input, so the password doesn't get
+/// into the shell history.
+fn read_password() -> anyhow::Result<String> {
+    let mut password = String::new();
+    io::stdin().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        bail!("password must be passed using the standard input");
+    }
+    Ok(password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn arguments_are_consistent() {
+        Arguments::command().debug_assert();
+    }
+}
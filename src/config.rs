@@ -0,0 +1,281 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Server configuration. Values are read from an optional TOML file,
+//! then overridden by the environment variables and the command line options.
+
+use std::{env, fs, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::{
+    persisted::PersistedQueriesConfig,
+    rest::{AdminAccess, PayloadLimits, RequestQuotas, SchemaOptions},
+    scan::ScanConfig,
+    tls::TlsConfig,
+    TrustedProxies,
+};
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub limits: PayloadLimits,
+    pub schema: SchemaOptions,
+    pub admin: AdminAccess,
+    pub quotas: RequestQuotas,
+    pub persisted_queries: PersistedQueriesConfig,
+    pub scan: ScanConfig,
+    pub jobs: JobsConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    /// If set, metrics are also served there without the admin token.
+    pub metrics_address: Option<String>,
+    /// On shutdown, in-flight requests and then background jobs are waited for up to
+    /// this number of seconds each.
+    pub shutdown_timeout_secs: u64,
+    pub tls: TlsConfig,
+    /// Client addresses are taken from the forwarded headers of requests sent from these.
+    pub trusted_proxies: TrustedProxies,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "0.0.0.0".to_string(),
+            port: 5000,
+            metrics_address: None,
+            shutdown_timeout_secs: 30,
+            tls: TlsConfig::default(),
+            trusted_proxies: TrustedProxies::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// See the format in the `tokio_postgres::Config` documentation.
+    pub connection_string: Option<String>,
+    /// Maximum number of connections which are open at the same time.
+    pub pool_size: usize,
+    /// Apply the pending migrations on startup.
+    pub migrate: bool,
+    /// Maximum total size of the stored previews in bytes, `None` means unlimited.
+    pub preview_storage_quota: Option<i64>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            connection_string: None,
+            pool_size: 16,
+            migrate: true,
+            preview_storage_quota: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Any origin is allowed if the list is empty.
+    pub origins: Vec<String>,
+    pub max_age_secs: usize,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            max_age_secs: 3600,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    pub notification_retention_days: i32,
+    pub rider_ping_retention_days: i32,
+    /// Orders completed this number of days ago are archived, `None` disables archiving.
+    pub order_archive_days: Option<i32>,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            notification_retention_days: 90,
+            rider_ping_retention_days: 30,
+            order_archive_days: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the file (`CONFIG_FILE` if `path` isn't specified)
+    /// and applies the environment variables.
+    pub fn load(path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let mut config = match path.or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from)) {
+            Some(path) => {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("unable to read {}", path.display()))?;
+                toml::from_str(&content)
+                    .with_context(|| format!("invalid configuration file {}", path.display()))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        if config.database.pool_size == 0 {
+            return Err(anyhow!("database.pool_size must be positive"));
+        }
+        config.server.tls.validate()?;
+        Ok(config)
+    }
+
+    pub fn connection_string(&self) -> anyhow::Result<&str> {
+        self.database.connection_string.as_deref().ok_or_else(|| {
+            anyhow!(
+                "database connection string isn't specified \
+                 (set database.connection_string or DB_CONNECTION_STRING)"
+            )
+        })
+    }
+
+    /// Fails if a variable has an invalid value.
+    fn apply_env(&mut self) -> anyhow::Result<()> {
+        let server = &mut self.server;
+        server.address = env_or("SERVER_ADDRESS", server.address.clone())?;
+        server.port = env_or("SERVER_PORT", server.port)?;
+        if let Ok(address) = env::var("METRICS_ADDRESS") {
+            server.metrics_address = Some(address).filter(|address| !address.is_empty());
+        }
+        server.shutdown_timeout_secs = env_or("SHUTDOWN_TIMEOUT", server.shutdown_timeout_secs)?;
+        let tls = &mut server.tls;
+        if let Some(path) = env::var_os("TLS_CERT_FILE") {
+            tls.cert_file = Some(path.into());
+        }
+        if let Some(path) = env::var_os("TLS_KEY_FILE") {
+            tls.key_file = Some(path.into());
+        }
+        // Zero disables the redirect.
+        if let Some(port) = env_opt::<u16>("TLS_REDIRECT_PORT")? {
+            tls.redirect_port = Some(port).filter(|port| *port > 0);
+        }
+        if let Ok(ips) = env::var("TRUSTED_PROXIES") {
+            server.trusted_proxies.0 = split_list(&ips)
+                .map(|ip| {
+                    ip.parse()
+                        .with_context(|| format!("invalid IP address {ip:?} in TRUSTED_PROXIES"))
+                })
+                .collect::<anyhow::Result<_>>()?;
+        }
+
+        let database = &mut self.database;
+        if let Ok(connection_string) = env::var("DB_CONNECTION_STRING") {
+            database.connection_string = Some(connection_string);
+        }
+        database.pool_size = env_or("DB_POOL_SIZE", database.pool_size)?;
+        database.migrate = env_or("MIGRATE_ON_STARTUP", database.migrate)?;
+        // Zero means unlimited, as the variable can't be unset in some environments.
+        if let Some(quota) = env_opt::<i64>("PREVIEW_STORAGE_QUOTA")? {
+            database.preview_storage_quota = Some(quota).filter(|quota| *quota > 0);
+        }
+
+        let cors = &mut self.cors;
+        if let Ok(origins) = env::var("CORS_ORIGINS") {
+            cors.origins = split_list(&origins).map(ToString::to_string).collect();
+        }
+        cors.max_age_secs = env_or("CORS_MAX_AGE", cors.max_age_secs)?;
+
+        let limits = &mut self.limits;
+        limits.graphql = env_or("MAX_GRAPHQL_REQUEST_SIZE", limits.graphql)?;
+        limits.upload = env_or("MAX_UPLOAD_SIZE", limits.upload)?;
+        limits.rest = env_or("MAX_REST_REQUEST_SIZE", limits.rest)?;
+
+        let schema = &mut self.schema;
+        schema.is_public = env_or("PUBLIC_SCHEMA", schema.is_public)?;
+        schema.introspection = env_or("GRAPHQL_INTROSPECTION", schema.introspection)?;
+
+        let admin = &mut self.admin;
+        if let Ok(token) = env::var("ADMIN_TOKEN") {
+            admin.token = Some(token).filter(|token| !token.is_empty());
+        }
+        if let Ok(ips) = env::var("ADMIN_ALLOWED_IPS") {
+            admin.allowed_ips = split_list(&ips)
+                .map(|ip| {
+                    ip.parse()
+                        .with_context(|| format!("invalid IP address {ip:?} in ADMIN_ALLOWED_IPS"))
+                })
+                .collect::<anyhow::Result<_>>()?;
+        }
+
+        let quotas = &mut self.quotas;
+        if let Some(quota) = env_opt::<i32>("USER_REQUESTS_PER_MINUTE")? {
+            quotas.user = Some(quota).filter(|quota| *quota > 0);
+        }
+        if let Some(quota) = env_opt::<i32>("API_KEY_REQUESTS_PER_MINUTE")? {
+            quotas.api_key = Some(quota).filter(|quota| *quota > 0);
+        }
+
+        let persisted_queries = &mut self.persisted_queries;
+        if let Some(dir) = env::var_os("OPERATION_ALLOWLIST_DIR") {
+            persisted_queries.allowlist_dir = Some(dir.into());
+        }
+        persisted_queries.cache_size =
+            env_or("PERSISTED_QUERIES_CACHE_SIZE", persisted_queries.cache_size)?;
+
+        let scan = &mut self.scan;
+        if let Ok(address) = env::var("CLAMD_ADDRESS") {
+            scan.clamd_address = Some(address).filter(|address| !address.is_empty());
+        }
+        if let Some(dir) = env::var_os("UPLOAD_QUARANTINE_DIR") {
+            scan.quarantine_dir = Some(dir.into());
+        }
+
+        let jobs = &mut self.jobs;
+        jobs.notification_retention_days = env_or(
+            "NOTIFICATION_RETENTION_DAYS",
+            jobs.notification_retention_days,
+        )?;
+        jobs.rider_ping_retention_days =
+            env_or("RIDER_PING_RETENTION_DAYS", jobs.rider_ping_retention_days)?;
+        if let Some(days) = env_opt::<i32>("ORDER_ARCHIVE_DAYS")? {
+            jobs.order_archive_days = Some(days).filter(|days| *days > 0);
+        }
+        Ok(())
+    }
+}
+
+/// Parses the environment variable or returns `default` if it's unset.
+fn env_or<T: FromStr>(name: &str, default: T) -> anyhow::Result<T> {
+    Ok(env_opt(name)?.unwrap_or(default))
+}
+
+/// Returns `None` if the variable is unset.
+fn env_opt<T: FromStr>(name: &str) -> anyhow::Result<Option<T>> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("invalid value {value:?} of {name}")),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(anyhow!("{name} isn't valid Unicode")),
+    }
+}
+
+/// Items of a comma-separated list without the empty ones.
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
@@ -0,0 +1,436 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Storage used by the GraphQL resolvers. It's implemented by [db::Client],
+//! other implementations allow to execute the schema without PostgreSQL.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::{
+    db::{self, Result},
+    types::*,
+    Device,
+};
+
+/// Declares [Datastore] and implements it for [db::Client] by forwarding
+/// to the inherent methods of the same names.
+macro_rules! datastore {
+    ($($(#[$attr:meta])* async fn $name:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;)*) => {
+        #[async_trait]
+        pub trait Datastore: Send + Sync {
+            $(
+                $(#[$attr])*
+                async fn $name(&self $(, $arg: $ty)*) -> $ret;
+            )*
+        }
+
+        #[async_trait]
+        impl Datastore for db::Client {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> $ret {
+                    db::Client::$name(self $(, $arg)*).await
+                }
+            )*
+        }
+    };
+}
+
+datastore! {
+    async fn is_credentials_valid(&self, username: &str, password: &str) -> Result<bool>;
+    async fn user_by_name(&self, username: &str) -> Result<User>;
+    /// Returns `None` if there is no user with such name.
+    async fn find_user_by_name(&self, username: &str) -> Result<Option<User>>;
+    async fn user_by_id(&self, id: ID) -> Result<Option<User>>;
+    /// Pass `role` to get only users with the role.
+    async fn users(
+        &self,
+        role: Option<UserRole>,
+        sort_by: SortUsersBy,
+        sort_order: SortOrder,
+        pagination: Pagination,
+    ) -> Result<Vec<User>>;
+    /// Returns `false` if there is nothing to update.
+    async fn update_user(&self, username: &str, patch: &UserPatch) -> Result<bool>;
+    /// Revoked sessions are forgotten, as they only lock out holders of the old password.
+    async fn set_user_password(&self, username: &str, password: &str) -> Result<bool>;
+    /// Deletes personal data of the user and makes it impossible to log in.
+    /// Username and password are replaced by random values.
+    /// Fails if the user has orders in progress.
+    async fn erase_user(&self, username: &str) -> Result<()>;
+    async fn set_user_role(&self, username: &str, role: UserRole) -> Result<bool>;
+    async fn user_activities(&self, username: &str) -> Result<Vec<Activity>>;
+    /// Does nothing if there is no user with such name.
+    async fn add_user_activity(
+        &self,
+        username: &str,
+        kind: ActivityKind,
+        device: &Device,
+        details: Option<&str>,
+    ) -> Result<()>;
+    async fn user_sessions(&self, username: &str, current_device: &Device) -> Result<Vec<Session>>;
+    /// Changes the password and deletes all sessions except the one opened
+    /// from the current device.
+    async fn revoke_other_user_sessions(
+        &self,
+        username: &str,
+        current_device: &Device,
+        new_password: &str,
+    ) -> Result<bool>;
+    async fn user_notifications(
+        &self,
+        username: &str,
+        unread_only: bool,
+    ) -> Result<Vec<Notification>>;
+    async fn delete_user_notification(&self, username: &str, id: ID) -> Result<bool>;
+    async fn unread_user_notifications_count(&self, username: &str) -> Result<i64>;
+    /// Returns `false` if the notification doesn't exist or it's already read.
+    async fn read_user_notification(&self, username: &str, id: ID) -> Result<bool>;
+    /// Returns `false` if there are no unread notifications.
+    async fn read_user_notifications(&self, username: &str) -> Result<bool>;
+    async fn add_user_notification(&self, user_id: ID, notification: &Notification) -> Result<ID>;
+    /// Sends the notification to all users with the role (and the segment if it's specified).
+    async fn add_notifications(
+        &self,
+        target_users_role: UserRole,
+        target_segment: Option<CustomerSegment>,
+        notification: Notification,
+    ) -> Result<Vec<ID>>;
+    async fn maintenance(&self) -> Result<Maintenance>;
+    async fn set_maintenance(&self, maintenance: &Maintenance) -> Result<()>;
+    async fn birthday_promo_settings(&self) -> Result<BirthdayPromoSettings>;
+    async fn set_birthday_promo_settings(&self, settings: &BirthdayPromoSettings) -> Result<()>;
+    async fn late_delivery_policy(&self) -> Result<LateDeliveryPolicy>;
+    async fn set_late_delivery_policy(&self, policy: &LateDeliveryPolicy) -> Result<()>;
+    async fn sla_policy(&self) -> Result<SlaPolicy>;
+    async fn set_sla_policy(&self, policy: &SlaPolicy) -> Result<()>;
+    /// SLA attainment of delivery orders made during the last `days`.
+    async fn sla_report(&self, days: i32) -> Result<SlaReport>;
+    /// Ongoing incidents, the most recent first.
+    async fn incidents(&self) -> Result<Vec<Incident>>;
+    async fn add_incident(&self, incident: &Incident) -> Result<ID>;
+    async fn resolve_incident(&self, id: ID) -> Result<bool>;
+    async fn order_scheduling(&self) -> Result<OrderScheduling>;
+    async fn set_order_scheduling(&self, scheduling: &OrderScheduling) -> Result<()>;
+    /// Returns `None` if the number of active orders isn't limited.
+    async fn order_capacity(&self) -> Result<Option<i32>>;
+    /// Queued orders which fit into the new capacity are promoted immediately.
+    async fn set_order_capacity(&self, capacity: Option<i32>) -> Result<()>;
+    /// Returns `None` if the order isn't queued.
+    async fn order_queue_position(
+        &self,
+        username: &str,
+        order_id: ID,
+    ) -> Result<Option<QueuePosition>>;
+    /// Compensation of late deliveries for the last `days`.
+    async fn late_delivery_report(&self, days: i32) -> Result<LateDeliveryReport>;
+    /// Settlements of the days from `from` to `to` inclusive, the oldest first.
+    async fn settlements(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Settlement>>;
+    async fn user_promo_codes(&self, username: &str) -> Result<Vec<PromoCode>>;
+    async fn api_keys(&self) -> Result<Vec<ApiKey>>;
+    async fn add_api_key(&self, title: &str, key: &str, scope: ApiKeyScope) -> Result<ID>;
+    /// Users who have sent at least one request, the most active first.
+    async fn api_usage(&self, pagination: Pagination) -> Result<Vec<ApiUsage>>;
+    async fn user_api_usage(&self, username: &str) -> Result<ApiUsage>;
+    async fn delete_api_key(&self, id: ID) -> Result<bool>;
+    async fn user_addresses(&self, username: &str) -> Result<Vec<Address>>;
+    async fn add_user_address(&self, username: &str, address: Address) -> Result<ID>;
+    async fn update_user_address(&self, username: &str, id: ID, address: &Address) -> Result<bool>;
+    /// Returns `false` if the user has no address with such ID.
+    async fn set_default_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    /// Moves the address to the trash.
+    async fn delete_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    async fn trashed_user_addresses(&self, username: &str) -> Result<Vec<Address>>;
+    async fn restore_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    /// Returns all categories if `pagination` isn't specified.
+    async fn categories(&self, pagination: Option<Pagination>) -> Result<Vec<Category>>;
+    async fn category_by_id(&self, id: ID) -> Result<Option<Category>>;
+    async fn export_catalog(&self) -> Result<CatalogDocument>;
+    /// Creates categories and food which don't exist and updates the rest.
+    /// Previews are left unchanged. All changes are recorded in the catalog history.
+    async fn import_catalog(
+        &self,
+        manager_username: &str,
+        document: &CatalogDocument,
+    ) -> Result<CatalogImportSummary>;
+    /// Returns ID of a category which title differs only in case or surrounding whitespace.
+    async fn similar_category_id(&self, title: &str) -> Result<Option<ID>>;
+    async fn add_category(
+        &self,
+        manager_username: &str,
+        category: &Category,
+        preview: Option<Vec<u8>>,
+    ) -> Result<ID>;
+    /// Preview is left unchanged if `preview` is `None`.
+    async fn update_category(
+        &self,
+        manager_username: &str,
+        id: ID,
+        category: &Category,
+        preview: Option<Option<Vec<u8>>>,
+    ) -> Result<bool>;
+    async fn delete_category(&self, manager_username: &str, id: ID) -> Result<bool>;
+    /// Returns ID of food in the category which title differs only in case
+    /// or surrounding whitespace.
+    async fn similar_food_id(&self, category_id: ID, title: &str) -> Result<Option<ID>>;
+    async fn food_by_id(&self, id: ID) -> Result<Option<Food>>;
+    async fn food_in_category(
+        &self,
+        category_id: ID,
+        filter: FoodFilter,
+        sort_by: SortFoodBy,
+        sort_order: SortOrder,
+        pagination: Pagination,
+    ) -> Result<Vec<IndexedFood>>;
+    async fn food_connection(
+        &self,
+        category_id: ID,
+        sort_by: SortFoodBy,
+        sort_order: SortOrder,
+        first: i64,
+        after: Option<Cursor>,
+    ) -> Result<Connection<IndexedFood>>;
+    async fn add_food(
+        &self,
+        manager_username: &str,
+        food: &IndexedFood,
+        preview: Option<Vec<u8>>,
+    ) -> Result<ID>;
+    /// Returns `false` if there is no food with such ID or the patch is empty.
+    async fn update_food(&self, manager_username: &str, id: ID, patch: &FoodPatch) -> Result<bool>;
+    async fn locations(&self) -> Result<Vec<Location>>;
+    async fn add_location(&self, location: &Location) -> Result<ID>;
+    /// Sets stock of the food at the location. The total food count is changed
+    /// by the same difference. Returns the new total count.
+    async fn set_location_stock(
+        &self,
+        manager_username: &str,
+        location_id: ID,
+        food_id: ID,
+        count: i32,
+    ) -> Result<i32>;
+    /// Returns stock at all locations grouped by food ID.
+    async fn location_stock(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<LocationStock>>>;
+    /// Returns price changes grouped by food ID, the latest ones go first.
+    async fn price_history(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<PriceChange>>>;
+    /// Returns the new count or `None` if there is no food with such ID.
+    async fn restock_food(
+        &self,
+        manager_username: &str,
+        id: ID,
+        quantity: i32,
+        comment: Option<&str>,
+    ) -> Result<Option<i32>>;
+    /// Returns the new count or `None` if there is no food with such ID.
+    async fn adjust_food_stock(
+        &self,
+        manager_username: &str,
+        id: ID,
+        delta: i32,
+        reason: &str,
+    ) -> Result<Option<i32>>;
+    /// Returns food which runs out within `days` according to sales during the last
+    /// `lookback_days`. Suggested quantity brings the stock to `cover_days` of sales.
+    async fn reorder_suggestions(
+        &self,
+        days: i32,
+        lookback_days: i32,
+        cover_days: i32,
+    ) -> Result<Vec<ReorderSuggestion>>;
+    async fn stock_history(
+        &self,
+        food_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<StockMovement>>;
+    async fn delete_food(&self, manager_username: &str, id: ID) -> Result<bool>;
+    async fn catalog_history(
+        &self,
+        entity: Option<CatalogEntity>,
+        entity_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<CatalogChange>>;
+    /// Restores the state of the entity which preceded the change.
+    /// Previews aren't restored. Returns ID of the recorded reverting change.
+    async fn revert_catalog_change(&self, manager_username: &str, id: ID) -> Result<ID>;
+    async fn storage_usage(&self) -> Result<StorageUsage>;
+    /// Deletes the complete upload and returns its data.
+    async fn take_upload(&self, username: &str, token: &str) -> Result<Vec<u8>>;
+    async fn is_user_favorite(&self, username: &str, food_id: ID) -> Result<bool>;
+    /// Returns only favorites from the collection if `collection_id` is set.
+    async fn user_favorites(
+        &self,
+        username: &str,
+        collection_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<Favorite>>;
+    async fn add_user_favorite(&self, username: &str, favorite: &IndexedFavorite) -> Result<ID>;
+    /// Moves the favorite to the trash.
+    async fn delete_user_favorite(&self, username: &str, id: ID) -> Result<bool>;
+    /// Removes the favorite from its collection if `collection_id` is `None`.
+    async fn move_user_favorite(
+        &self,
+        username: &str,
+        id: ID,
+        collection_id: Option<ID>,
+    ) -> Result<bool>;
+    async fn user_favorite_collections(&self, username: &str) -> Result<Vec<FavoriteCollection>>;
+    async fn add_user_favorite_collection(
+        &self,
+        username: &str,
+        collection: &FavoriteCollection,
+    ) -> Result<ID>;
+    async fn update_user_favorite_collection(
+        &self,
+        username: &str,
+        id: ID,
+        collection: &FavoriteCollection,
+    ) -> Result<bool>;
+    /// Favorites from the collection are kept without a collection.
+    async fn delete_user_favorite_collection(&self, username: &str, id: ID) -> Result<bool>;
+    /// Moves the favorites to the trash. Returns the number of deleted favorites.
+    async fn delete_user_favorites(&self, username: &str, ids: &[ID]) -> Result<u64>;
+    /// Adds one item of each favorite food into the user cart.
+    /// Returns the number of added favorites.
+    async fn add_favorites_to_cart(&self, username: &str, ids: &[ID]) -> Result<u64>;
+    async fn trashed_user_favorites(&self, username: &str) -> Result<Vec<Favorite>>;
+    async fn restore_user_favorite(&self, username: &str, id: ID) -> Result<bool>;
+    async fn is_in_user_cart(&self, username: &str, food_id: ID) -> Result<bool>;
+    async fn user_cart(
+        &self,
+        username: &str,
+        sort_by: SortCartBy,
+        sort_order: SortOrder,
+    ) -> Result<Cart>;
+    /// If the food is already in the cart, increments count of the existing item.
+    /// Returns ID of the item and whether it was inserted.
+    async fn add_user_cart_item(
+        &self,
+        username: &str,
+        item: &IndexedCartItem,
+    ) -> Result<(ID, bool)>;
+    /// Adds items of the delivered order into the user cart.
+    /// Items which are out of stock are skipped.
+    async fn reorder(&self, username: &str, order_id: ID) -> Result<Reorder>;
+    /// Deletes the item if `count` is 0.
+    async fn update_user_cart_item(&self, username: &str, id: ID, count: i32) -> Result<bool>;
+    async fn delete_user_cart_item(&self, username: &str, id: ID) -> Result<bool>;
+    async fn orders(&self, filter: OrdersFilter, pagination: Pagination) -> Result<Vec<Order>>;
+    async fn user_orders(
+        &self,
+        username: &str,
+        filter: OrdersFilter,
+        pagination: Pagination,
+    ) -> Result<Vec<Order>>;
+    /// Delivery orders which aren't taken by any rider yet.
+    /// Pass `location_id` to get only orders fulfilled from the location.
+    async fn available_orders(
+        &self,
+        sort_order: SortOrder,
+        limit: i64,
+        location_id: Option<ID>,
+    ) -> Result<Vec<Order>>;
+    /// Orders assigned to the rider which aren't delivered yet.
+    async fn rider_active_orders(&self, username: &str) -> Result<Vec<Order>>;
+    /// Returns orders of the user if `username` is specified, otherwise all orders.
+    async fn orders_connection(
+        &self,
+        username: Option<&str>,
+        filter: OrdersFilter,
+        first: i64,
+        after: Option<Cursor>,
+    ) -> Result<Connection<Order>>;
+    async fn make_order_from_user_cart(
+        &self,
+        username: &str,
+        order: IndexedOrder,
+        promo_code: Option<&str>,
+    ) -> Result<ID>;
+    /// Returns a token which the gift recipient uses to enter the address.
+    /// A new token replaces the previous one.
+    async fn create_gift_address_link(&self, username: &str, order_id: ID) -> Result<String>;
+    /// Notifies the customer that the order is taken.
+    async fn take_order(&self, username: &str, id: ID) -> Result<bool>;
+    /// Records that the rider is online and serves the location.
+    async fn add_rider_ping(&self, username: &str, location_id: ID) -> Result<()>;
+    /// Coverage of the locations by hours for the last `days`, the latest first.
+    async fn rider_coverage(&self, days: i32) -> Result<Vec<RiderCoverage>>;
+    /// Pass `rider_username` to allow releasing only orders assigned to the rider.
+    /// Managers are notified about the released order.
+    async fn release_order(
+        &self,
+        id: ID,
+        rider_username: Option<&str>,
+        released_by: &str,
+    ) -> Result<bool>;
+    /// Notifies the customer that the order is delivered.
+    async fn complete_order(&self, username: &str, id: ID) -> Result<bool>;
+    /// Notifies the customer that the pickup order can be received.
+    async fn mark_order_ready_for_pickup(&self, id: ID) -> Result<bool>;
+    /// Completes the pickup order if the code matches.
+    async fn hand_over_order(&self, id: ID, pickup_code: &str) -> Result<bool>;
+    /// Moves the order to the next status on behalf of the rider.
+    async fn advance_order_status(&self, username: &str, id: ID) -> Result<OrderStatus>;
+    async fn cancellation_policy(&self) -> Result<CancellationPolicy>;
+    async fn set_cancellation_policy(&self, policy: &CancellationPolicy) -> Result<()>;
+    /// Returns the fee the customer will be charged for cancelling the order.
+    async fn cancellation_fee(&self, id: ID, customer_username: &str) -> Result<Decimal>;
+    /// Pass `customer_username` to allow cancelling only orders owned by the user
+    /// according to the cancellation policy. Non-zero fee must match `confirmed_fee`.
+    /// The assigned rider and the customer (if the order is cancelled by a manager)
+    /// are notified. Returns the charged fee.
+    async fn cancel_order(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+        confirmed_fee: Option<Decimal>,
+        reason: Option<&str>,
+    ) -> Result<Decimal>;
+    async fn mark_order_item_unavailable(&self, id: ID) -> Result<bool>;
+    async fn add_user_feedback(&self, username: &str, feedback: &Feedback) -> Result<ID>;
+    /// If `customer_username` is set, the feedback must be left by the customer within the edit
+    /// window. Otherwise, it's changed by a manager.
+    async fn update_user_feedback(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+        rating: Option<i16>,
+        comment: Option<&str>,
+    ) -> Result<()>;
+    /// Same restrictions as for [`Self::update_user_feedback`] are applied.
+    async fn delete_user_feedback(&self, id: ID, customer_username: Option<&str>) -> Result<()>;
+    async fn feedback_policy(&self) -> Result<FeedbackPolicy>;
+    async fn set_feedback_policy(&self, policy: &FeedbackPolicy) -> Result<()>;
+    /// Feedbacks including the archived ones, the newest first.
+    async fn feedbacks(
+        &self,
+        filter: FeedbacksFilter,
+        pagination: Pagination,
+    ) -> Result<Vec<Feedback>>;
+    async fn hide_feedback(&self, id: ID, reason: &str) -> Result<()>;
+    async fn rider_rating_summary(&self, username: &str) -> Result<RiderRatingSummary>;
+    async fn duplicate_users(&self) -> Result<Vec<DuplicateUsers>>;
+    /// Moves orders, addresses, favorites and promo codes of the source customer
+    /// to the target one and deletes the source account. The merge is recorded.
+    async fn merge_users(
+        &self,
+        source_id: ID,
+        target_id: ID,
+        manager_username: &str,
+    ) -> Result<UserMerge>;
+    async fn users_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, User>>;
+    async fn addresses_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Address>>;
+    async fn categories_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Category>>;
+    async fn food_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Food>>;
+    /// Analyzes feedbacks on orders completed during the last `days`. Keywords are
+    /// extracted only from comments with rating up to `max_rating` if it's specified.
+    async fn feedback_analytics(
+        &self,
+        days: i32,
+        max_rating: Option<i16>,
+        keyword_limit: usize,
+    ) -> Result<FeedbackAnalytics>;
+}
@@ -4,14 +4,16 @@
 
 use std::{collections::HashMap, env};
 
-use anyhow::anyhow;
-use log::error;
+use async_graphql::{connection::Edge, OutputType};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, PoolError, RecyclingMethod};
 use postgres_types::ToSql;
+use rand::Rng;
 use rust_decimal::Decimal;
 use serde::Deserialize;
-use tokio_postgres::{NoTls, Row};
+use tokio_postgres::{error::SqlState, NoTls, Row};
 
-use crate::{sha256, types::*};
+use crate::{config::DatabaseConfig, keywords, random_token, sha256, types::*, Device};
 
 #[derive(Clone, Copy, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -20,33 +22,242 @@ pub enum PreviewOf {
     Food,
 }
 
-type PostgresResult<T> = Result<T, tokio_postgres::Error>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Distinguishes failures of the database from the ones caused by the request,
+/// so the latter can be reported to the client.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Postgres(#[from] tokio_postgres::Error),
+    /// Connection can't be obtained from the pool.
+    #[error(transparent)]
+    Pool(#[from] PoolError),
+    /// Entity doesn't exist or isn't accessible by the user.
+    #[error("{0}")]
+    NotFound(String),
+    /// Operation isn't allowed in the current state of the entity.
+    #[error("{0}")]
+    Conflict(String),
+    /// Request data can't be processed.
+    #[error("{0}")]
+    Invalid(String),
+    /// Current user doesn't exist anymore, e.g. the account was deleted during the session.
+    #[error("there is no user \"{0}\"")]
+    UnknownUser(String),
+    /// Items can't be ordered as there isn't enough of them in stock.
+    #[error("not enough items in stock")]
+    OutOfStock(Vec<StockShortage>),
+}
+
+/// Paths relative to `src/sql` and contents of all embedded statements.
+const STATEMENTS: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/statements.rs"));
+
+/// Versions, names and contents of the migrations from `db/migrations` sorted by versions.
+const MIGRATIONS: &[(i32, &str, &str)] = include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// Used to estimate acceptance time of queued orders if no orders were delivered recently.
+pub(crate) const DEFAULT_FULFILLMENT_MINUTES: i32 = 30;
+/// Number of hours a gift recipient can enter the address using a link.
+const GIFT_ADDRESS_LINK_HOURS: i32 = 72;
+/// Number of hours a resumable upload can be completed and used.
+const UPLOAD_EXPIRE_HOURS: i32 = 24;
+/// Key of the advisory lock held while migrations are applied.
+const MIGRATIONS_LOCK_KEY: i64 = 0x676f_676f;
 
 pub struct Client {
-    client: tokio_postgres::Client,
+    pool: Pool,
+    /// Maximum total size of the stored previews, `None` means unlimited.
+    preview_storage_quota: Option<i64>,
 }
 
 impl Client {
-    pub async fn connect() -> PostgresResult<Self> {
-        let (client, connection) = tokio_postgres::connect(
-            &env::var("DB_CONNECTION_STRING")
-                .expect("environment variable DB_CONNECTION_STRING isn't defined"),
+    /// Connections are opened on demand, up to `pool_size` of the configuration
+    /// at the same time. Fails if the first one can't be established.
+    pub async fn connect(connection_string: &str, config: &DatabaseConfig) -> Result<Self> {
+        let manager = Manager::from_config(
+            connection_string.parse()?,
             NoTls,
-        )
-        .await?;
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                error!("Unable to establish connection to database: {e}");
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(config.pool_size)
+            .build()
+            .expect("pool without timeouts doesn't require a runtime");
+        let client = Self {
+            pool,
+            preview_storage_quota: config.preview_storage_quota,
+        };
+        // The connection is returned to the pool to be used by the first statement.
+        drop(client.client().await?);
+        Ok(client)
+    }
+
+    /// Closes the idle connections. Connections in use are closed when they're returned.
+    pub fn close(self) {
+        self.pool.close();
+    }
+
+    /// Returns `false` if a connection to the database can't be obtained.
+    pub async fn is_connected(&self) -> bool {
+        self.client().await.is_ok_and(|client| !client.is_closed())
+    }
+
+    /// Waits for a free connection if all of them are in use.
+    async fn client(&self) -> Result<Object> {
+        self.pool.get().await.map_err(Into::into)
+    }
+
+    /// Applies the pending migrations, each one in a transaction. Fails if an applied
+    /// migration isn't embedded or was changed. Returns versions of the applied ones.
+    ///
+    /// A schema created before migrations were introduced is marked as created
+    /// by the initial migration only if `baseline` is set, as it may be outdated.
+    /// Servers which are started at the same time apply migrations one by one.
+    pub async fn migrate(&self, baseline: bool) -> Result<Vec<i32>> {
+        // The advisory lock is held by the connection, so the migrations use the same one.
+        let mut client = self.client().await?;
+        client
+            .execute(
+                include_str!("sql/select/migrations_lock.sql"),
+                &[&MIGRATIONS_LOCK_KEY],
+            )
+            .await?;
+        let result = Self::apply_migrations(&mut client, baseline).await;
+        client
+            .execute(
+                include_str!("sql/select/migrations_unlock.sql"),
+                &[&MIGRATIONS_LOCK_KEY],
+            )
+            .await?;
+        result
+    }
+
+    /// Takes no `self`, so every statement runs on the connection which holds the lock.
+    async fn apply_migrations(client: &mut Object, baseline: bool) -> Result<Vec<i32>> {
+        client
+            .batch_execute(include_str!("../db/schema_version.sql"))
+            .await?;
+        let is_legacy: bool = client
+            .query_one(include_str!("sql/check/legacy_schema.sql"), &[])
+            .await?
+            .get(0);
+        if is_legacy {
+            let (version, name, migration) = MIGRATIONS[0];
+            if !baseline {
+                return Err(Error::Conflict(format!(
+                    "database was created before migrations were introduced, make sure \
+                     it matches migration {version:04}_{name} and run the migrate command \
+                     with --baseline"
+                )));
             }
-        });
-        Ok(Self { client })
+            let missing: Vec<String> = client
+                .query(
+                    include_str!("sql/select/missing_relations.sql"),
+                    &[&created_relations(migration)],
+                )
+                .await?
+                .into_iter()
+                .map(|row| row.get("name"))
+                .collect();
+            if !missing.is_empty() {
+                return Err(Error::Conflict(format!(
+                    "database doesn't match migration {version:04}_{name}, \
+                     it lacks {}",
+                    missing.join(", ")
+                )));
+            }
+            // Columns are verified by the statements check on startup.
+            client
+                .execute(
+                    include_str!("sql/insert/schema_version.sql"),
+                    &[&version, &name, &sha256(migration)],
+                )
+                .await?;
+        }
+
+        let applied: HashMap<i32, String> = client
+            .query(include_str!("sql/select/schema_versions.sql"), &[])
+            .await?
+            .into_iter()
+            .map(|row| (row.get("version"), row.get("checksum")))
+            .collect();
+        for (version, checksum) in &applied {
+            match MIGRATIONS.iter().find(|migration| migration.0 == *version) {
+                None => {
+                    return Err(Error::Conflict(format!(
+                        "migration {version} is applied, but isn't known to this version \
+                         of the server"
+                    )))
+                }
+                Some((_, name, migration)) if sha256(migration) != *checksum => {
+                    return Err(Error::Conflict(format!(
+                        "migration {version:04}_{name} was changed after it had been applied"
+                    )))
+                }
+                _ => {}
+            }
+        }
+
+        let mut versions = Vec::new();
+        for (version, name, migration) in MIGRATIONS {
+            if applied.contains_key(version) {
+                continue;
+            }
+            let transaction = client.transaction().await?;
+            transaction.batch_execute(migration).await?;
+            transaction
+                .execute(
+                    include_str!("sql/insert/schema_version.sql"),
+                    &[version, name, &sha256(migration)],
+                )
+                .await?;
+            transaction.commit().await?;
+            versions.push(*version);
+        }
+        Ok(versions)
     }
 
-    pub async fn is_credentials_valid(
-        &self,
-        username: &str,
-        password: &str,
-    ) -> PostgresResult<bool> {
+    /// Prepares all embedded statements to make sure the tables, columns and types
+    /// they reference exist. Returns descriptions of the failed statements.
+    /// Statements with placeholders are skipped, as they are completed on each request.
+    /// Also checks that the archive tables have the columns of the hot ones.
+    pub async fn check_statements(&self) -> Vec<String> {
+        let client = match self.client().await {
+            Ok(client) => client,
+            Err(e) => return vec![format!("unable to connect to database: {e}")],
+        };
+        let mut failures = Vec::new();
+        for (path, statement) in STATEMENTS {
+            if statement.contains('{') {
+                continue;
+            }
+            if let Err(e) = client.prepare(statement).await {
+                failures.push(match e.as_db_error() {
+                    Some(db_error) => format!("{path}: {}", db_error.message()),
+                    None => format!("{path}: {e}"),
+                });
+            }
+        }
+        // Failure of the statement itself is reported above.
+        if let Ok(rows) = client
+            .query(include_str!("sql/check/mismatched_archive_tables.sql"), &[])
+            .await
+        {
+            failures.extend(rows.iter().map(|row| {
+                format!(
+                    "columns of {} don't match columns of {}",
+                    row.get::<_, &str>("archive"),
+                    row.get::<_, &str>("hot")
+                )
+            }));
+        }
+        failures
+    }
+
+    pub async fn is_credentials_valid(&self, username: &str, password: &str) -> Result<bool> {
         self.is_true(
             include_str!("sql/check/credentials_valid.sql"),
             &[&username, &sha256(password)],
@@ -54,22 +265,53 @@ impl Client {
         .await
     }
 
-    pub async fn user_by_name(&self, username: &str) -> PostgresResult<User> {
-        self.client
-            .query_one(include_str!("sql/select/user_by_name.sql"), &[&username])
+    pub async fn user_by_name(&self, username: &str) -> Result<User> {
+        self.find_user_by_name(username)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("there is no user \"{username}\"")))
+    }
+
+    /// Returns `None` if there is no user with such name.
+    pub async fn find_user_by_name(&self, username: &str) -> Result<Option<User>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/user_by_name.sql"), &[&username])
             .await
-            .map(Into::into)
+            .map(|row| row.map(Into::into))
+            .map_err(Into::into)
+    }
+
+    pub async fn user_by_id(&self, id: ID) -> Result<Option<User>> {
+        self.users_by_ids(&[id])
+            .await
+            .map(|mut users| users.remove(&id))
     }
 
-    pub async fn users(&self) -> PostgresResult<Vec<User>> {
-        self.client
-            .query(include_str!("sql/select/users.sql"), &[])
+    /// Pass `role` to get only users with the role.
+    pub async fn users(
+        &self,
+        role: Option<UserRole>,
+        sort_by: SortUsersBy,
+        sort_order: SortOrder,
+        pagination: Pagination,
+    ) -> Result<Vec<User>> {
+        let statement = include_str!("sql/select/users.sql")
+            .replace("{sort_column}", sort_by.column())
+            .replace("{direction}", sort_order.sql());
+        self.client()
+            .await?
+            .query(
+                &statement,
+                &[&role, &pagination.limit(), &pagination.offset()],
+            )
             .await
             .map(from_rows)
+            .map_err(Into::into)
     }
 
-    pub async fn add_user(&self, user: User) -> PostgresResult<ID> {
-        self.client
+    pub async fn add_user(&self, user: User) -> Result<ID> {
+        self.client()
+            .await?
             .query_one(
                 include_str!("sql/insert/user.sql"),
                 &[
@@ -82,261 +324,2248 @@ impl Client {
             )
             .await
             .map(|row| row.get(0))
+            .map_err(Into::into)
     }
 
-    pub async fn set_user_role(&self, username: &str, role: UserRole) -> PostgresResult<bool> {
-        self.client
+    /// Returns `false` if there is nothing to update.
+    pub async fn update_user(&self, username: &str, patch: &UserPatch) -> Result<bool> {
+        let columns = patch.columns();
+        if columns.is_empty() {
+            return Ok(false);
+        }
+        let assignments = columns
+            .iter()
+            .enumerate()
+            // The first parameter is the username.
+            .map(|(index, (column, _))| format!("{column} = ${}", index + 2))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut params: Vec<&(dyn ToSql + Sync)> = vec![&username];
+        params.extend(columns.iter().map(|(_, value)| *value));
+
+        self.client()
+            .await?
             .execute(
-                include_str!("sql/update/user_role.sql"),
-                &[&role, &self.user_id_by_name(username).await?],
+                &include_str!("sql/update/user.sql").replace("{assignments}", &assignments),
+                &params,
             )
             .await
             .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn user_notifications(&self, username: &str) -> PostgresResult<Vec<Notification>> {
-        self.client
-            .query(
-                include_str!("sql/select/user_notifications.sql"),
-                &[&self.user_id_by_name(username).await?],
+    pub async fn set_user_password(&self, username: &str, password: &str) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/user_password.sql"),
+                &[&username, &sha256(password)],
             )
             .await
-            .map(from_rows)
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn add_user_notification(
-        &self,
-        user_id: ID,
-        notification: &Notification,
-    ) -> PostgresResult<ID> {
-        self.client
-            .query_one(
-                include_str!("sql/insert/user_notification.sql"),
-                &[&user_id, &notification.title, &notification.description],
+    /// Deletes personal data of the user and makes it impossible to log in.
+    /// Username and password are replaced by random values.
+    /// Fails if the user has orders in progress.
+    pub async fn erase_user(&self, username: &str) -> Result<()> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/erased_user.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &format!("deleted_{}", random_token()),
+                    &sha256(&random_token()),
+                ],
             )
-            .await
-            .map(|row| row.get(0))
+            .await?
+            .map(|_| ())
+            .ok_or_else(|| Error::Conflict("account has orders in progress".to_string()))
     }
 
-    pub async fn add_notifications(
-        &self,
-        target_users_role: UserRole,
-        notification: Notification,
-    ) -> PostgresResult<Vec<ID>> {
-        let mut notification_ids = Vec::new();
-        for user in self
-            .users()
+    pub async fn set_user_role(&self, username: &str, role: UserRole) -> Result<bool> {
+        self.client()
             .await?
-            .into_iter()
-            .filter(|user| user.role == target_users_role)
-        {
-            notification_ids.push(self.add_user_notification(user.id, &notification).await?)
-        }
-        Ok(notification_ids)
+            .execute(
+                include_str!("sql/update/user_role.sql"),
+                &[&role, &self.user_by_name(username).await?.id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn user_addresses(&self, username: &str) -> PostgresResult<Vec<Address>> {
-        self.client
+    pub async fn user_activities(&self, username: &str) -> Result<Vec<Activity>> {
+        self.client()
+            .await?
             .query(
-                include_str!("sql/select/user_addresses.sql"),
+                include_str!("sql/select/user_activities.sql"),
                 &[&self.user_id_by_name(username).await?],
             )
             .await
             .map(from_rows)
+            .map_err(Into::into)
     }
 
-    pub async fn add_user_address(&self, username: &str, address: Address) -> PostgresResult<ID> {
-        self.client
-            .query_one(
-                include_str!("sql/insert/user_address.sql"),
+    /// Does nothing if there is no user with such name.
+    pub async fn add_user_activity(
+        &self,
+        username: &str,
+        kind: ActivityKind,
+        device: &Device,
+        details: Option<&str>,
+    ) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/insert/user_activity.sql"),
                 &[
-                    &self.user_id_by_name(username).await?,
-                    &address.locality,
-                    &address.street,
-                    &address.house,
-                    &address.corps,
-                    &address.apartment,
+                    &username,
+                    &kind,
+                    &device.ip_address,
+                    &device.user_agent,
+                    &details,
                 ],
             )
             .await
-            .map(|row| row.get(0))
+            .map(|_| ())
+            .map_err(Into::into)
     }
 
-    pub async fn delete_user_address(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
+    /// Records the login only if it's performed from a new device.
+    pub async fn add_user_login(&self, username: &str, device: &Device) -> Result<()> {
+        self.client()
+            .await?
             .execute(
-                include_str!("sql/delete/user_address.sql"),
-                &[&self.user_id_by_name(username).await?, &id],
+                include_str!("sql/insert/user_login.sql"),
+                &[&username, &device.ip_address, &device.user_agent],
             )
             .await
-            .map(|modified_rows| modified_rows != 0)
-    }
-
-    pub async fn categories(&self) -> PostgresResult<Vec<Category>> {
-        self.client
-            .query(include_str!("sql/select/categories.sql"), &[])
-            .await
-            .map(from_rows)
+            .map(|_| ())
+            .map_err(Into::into)
     }
 
-    pub async fn add_category(
-        &self,
-        category: &Category,
-        preview: Option<Vec<u8>>,
-    ) -> PostgresResult<ID> {
-        self.client
-            .query_one(
-                include_str!("sql/insert/category.sql"),
-                &[&category.title, &category.description, &preview],
+    /// Updates the last seen time of the session opened from the device
+    /// (creating it on the first login).
+    pub async fn touch_user_session(&self, username: &str, device: &Device) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/insert/user_session.sql"),
+                &[&username, &device.ip_address, &device.user_agent],
             )
             .await
-            .map(|row| row.get(0))
-    }
-
-    pub async fn delete_category(&self, id: ID) -> PostgresResult<bool> {
-        self.client
-            .execute(include_str!("sql/delete/category.sql"), &[&id])
-            .await
-            .map(|modified_rows| modified_rows != 0)
+            .map(|_| ())
+            .map_err(Into::into)
     }
 
-    pub async fn food_in_category(
+    pub async fn user_sessions(
         &self,
-        category_id: ID,
-        sort_by: SortFoodBy,
-        sort_order: SortOrder,
-    ) -> PostgresResult<Vec<IndexedFood>> {
-        let mut food = self
-            .client
+        username: &str,
+        current_device: &Device,
+    ) -> Result<Vec<Session>> {
+        self.client()
+            .await?
             .query(
-                include_str!("sql/select/food_in_category.sql"),
-                &[&category_id],
+                include_str!("sql/select/user_sessions.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &current_device.ip_address,
+                    &current_device.user_agent,
+                ],
             )
             .await
-            .map(from_rows)?;
-        food.sort_by(|lhs, rhs| sort_by.cmp(lhs, rhs));
-        if let SortOrder::Descending = sort_order {
-            food.reverse();
-        }
-        Ok(food)
+            .map(from_rows)
+            .map_err(Into::into)
     }
 
-    pub async fn add_food(
+    /// Changes the password and deletes all sessions except the one opened from
+    /// the current device. Credentials are sent with each request, so other devices
+    /// can't authenticate only if they don't know the new password.
+    pub async fn revoke_other_user_sessions(
         &self,
-        food: &IndexedFood,
-        preview: Option<Vec<u8>>,
-    ) -> PostgresResult<ID> {
-        self.client
-            .query_one(
-                include_str!("sql/insert/food.sql"),
+        username: &str,
+        current_device: &Device,
+        new_password: &str,
+    ) -> Result<bool> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/user_password_and_sessions.sql"),
                 &[
-                    &food.title,
-                    &food.description,
-                    &preview,
-                    &food.category_id,
-                    &food.count,
-                    &food.is_alcohol,
-                    &food.price,
+                    &self.user_id_by_name(username).await?,
+                    &current_device.ip_address,
+                    &current_device.user_agent,
+                    &sha256(new_password),
                 ],
             )
             .await
-            .map(|row| row.get(0))
+            .map(|row| row.is_some())
+            .map_err(Into::into)
     }
 
-    pub async fn delete_food(&self, id: ID) -> PostgresResult<bool> {
-        self.client
-            .execute(include_str!("sql/delete/food.sql"), &[&id])
+    pub async fn user_notifications(
+        &self,
+        username: &str,
+        unread_only: bool,
+    ) -> Result<Vec<Notification>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/user_notifications.sql"),
+                &[&self.user_id_by_name(username).await?, &unread_only],
+            )
             .await
-            .map(|modified_rows| modified_rows != 0)
+            .map(from_rows)
+            .map_err(Into::into)
     }
 
-    pub async fn preview(&self, of: PreviewOf, id: ID) -> PostgresResult<Vec<u8>> {
-        self.client
-            .query_one(
-                match of {
-                    PreviewOf::Category => include_str!("sql/select/category_preview.sql"),
-                    PreviewOf::Food => include_str!("sql/select/food_preview.sql"),
-                },
-                &[&id],
+    pub async fn delete_user_notification(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/user_notification.sql"),
+                &[&self.user_id_by_name(username).await?, &id],
             )
             .await
-            .map(|row| row.get(0))
-    }
-
-    pub async fn is_user_favorite(&self, username: &str, food_id: ID) -> PostgresResult<bool> {
-        self.is_true(
-            include_str!("sql/check/user_favorite.sql"),
-            &[&self.user_id_by_name(username).await?, &food_id],
-        )
-        .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn user_favorites(&self, username: &str) -> anyhow::Result<Vec<Favorite>> {
-        let user_id = self.user_id_by_name(username).await?;
-        let mut food = self
-            .query_food(
-                include_str!("sql/select/user_favorite_food.sql"),
-                &[&user_id],
+    /// Deletes notifications of all users sent more than `retention_days` ago.
+    /// Returns the number of deleted notifications.
+    pub async fn delete_old_notifications(&self, retention_days: i32) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/old_notifications.sql"),
+                &[&retention_days],
             )
-            .await?;
-        let indexed_favorites: Vec<IndexedFavorite> = self
-            .client
-            .query(include_str!("sql/select/user_favorites.sql"), &[&user_id])
             .await
-            .map(from_rows)?;
-
-        let mut favorites = Vec::with_capacity(indexed_favorites.capacity());
-        for indexed_favorite in indexed_favorites {
-            favorites.push(Favorite {
-                food: food
-                    // We can move a food item because it's
-                    // unique per user (constraint 'food_per_user').
-                    .remove(&indexed_favorite.food_id)
-                    .ok_or(anyhow!("database was changed during data merging"))?,
-                indexed_favorite,
-            })
-        }
-        Ok(favorites)
+            .map_err(Into::into)
     }
 
-    pub async fn add_user_favorite(
-        &self,
-        username: &str,
-        favorite: &IndexedFavorite,
-    ) -> PostgresResult<ID> {
-        self.client
+    pub async fn unread_user_notifications_count(&self, username: &str) -> Result<i64> {
+        self.client()
+            .await?
             .query_one(
-                include_str!("sql/insert/user_favorite.sql"),
-                &[&self.user_id_by_name(username).await?, &favorite.food_id],
+                include_str!("sql/select/unread_notifications_count.sql"),
+                &[&self.user_id_by_name(username).await?],
             )
             .await
             .map(|row| row.get(0))
+            .map_err(Into::into)
     }
 
-    pub async fn delete_user_favorite(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
+    /// Returns `false` if the notification doesn't exist or it's already read.
+    pub async fn read_user_notification(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
             .execute(
-                include_str!("sql/delete/user_favorite.sql"),
+                include_str!("sql/update/read_notification.sql"),
                 &[&self.user_id_by_name(username).await?, &id],
             )
             .await
             .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn is_in_user_cart(&self, username: &str, food_id: ID) -> PostgresResult<bool> {
-        self.is_true(
-            include_str!("sql/check/in_user_cart.sql"),
-            &[&self.user_id_by_name(username).await?, &food_id],
-        )
-        .await
+    /// Returns `false` if there are no unread notifications.
+    pub async fn read_user_notifications(&self, username: &str) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/read_notifications.sql"),
+                &[&self.user_id_by_name(username).await?],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
     }
 
-    pub async fn user_cart(
+    pub async fn add_user_notification(
         &self,
-        username: &str,
-        sort_by: SortCartBy,
-        sort_order: SortOrder,
-    ) -> anyhow::Result<Cart> {
+        user_id: ID,
+        notification: &Notification,
+    ) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/user_notification.sql"),
+                &[&user_id, &notification.title, &notification.description],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Sends the notification to all users with the role (and the segment if it's specified).
+    pub async fn add_notifications(
+        &self,
+        target_users_role: UserRole,
+        target_segment: Option<CustomerSegment>,
+        notification: Notification,
+    ) -> Result<Vec<ID>> {
+        let users: Vec<User> = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/select/users_with_role.sql"),
+                &[&target_users_role, &target_segment],
+            )
+            .await
+            .map(from_rows)?;
+
+        let mut notification_ids = Vec::with_capacity(users.len());
+        for user in users {
+            notification_ids.push(self.add_user_notification(user.id, &notification).await?)
+        }
+        Ok(notification_ids)
+    }
+
+    pub async fn maintenance(&self) -> Result<Maintenance> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/maintenance.sql"), &[])
+            .await
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
+    }
+
+    pub async fn set_maintenance(&self, maintenance: &Maintenance) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/maintenance.sql"),
+                &[&maintenance.is_enabled, &maintenance.message],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub async fn birthday_promo_settings(&self) -> Result<BirthdayPromoSettings> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/birthday_promo_settings.sql"), &[])
+            .await
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
+    }
+
+    pub async fn set_birthday_promo_settings(
+        &self,
+        settings: &BirthdayPromoSettings,
+    ) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/birthday_promo_settings.sql"),
+                &[
+                    &settings.is_enabled,
+                    &settings.discount_percent,
+                    &settings.valid_days,
+                ],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub async fn late_delivery_policy(&self) -> Result<LateDeliveryPolicy> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/late_delivery_policy.sql"), &[])
+            .await
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
+    }
+
+    pub async fn set_late_delivery_policy(&self, policy: &LateDeliveryPolicy) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/late_delivery_policy.sql"),
+                &[
+                    &policy.promised_minutes,
+                    &policy.tolerance_minutes,
+                    &policy.discount_percent,
+                    &policy.valid_days,
+                ],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub async fn sla_policy(&self) -> Result<SlaPolicy> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/sla_policy.sql"), &[])
+            .await
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
+    }
+
+    pub async fn set_sla_policy(&self, policy: &SlaPolicy) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/sla_policy.sql"),
+                &[&policy.accept_minutes, &policy.delivery_minutes],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Flags orders breaching the SLA and notifies managers about them.
+    /// Each order is reported once. Returns the number of flagged orders.
+    pub async fn report_sla_breaches(&self) -> Result<usize> {
+        let policy = self.sla_policy().await?;
+        if policy.accept_minutes.is_none() && policy.delivery_minutes.is_none() {
+            return Ok(0);
+        }
+        let rows = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/update/sla_breached_orders.sql"),
+                &[&policy.accept_minutes, &policy.delivery_minutes],
+            )
+            .await?;
+        for row in &rows {
+            let id: ID = row.get("id");
+            let description = if row.get("is_accept_breached") {
+                format!(
+                    "Order #{id} wasn't taken by a rider within {} minutes.",
+                    policy.accept_minutes.unwrap_or_default()
+                )
+            } else {
+                format!(
+                    "Order #{id} wasn't delivered within {} minutes.",
+                    policy.delivery_minutes.unwrap_or_default()
+                )
+            };
+            let notification = Notification {
+                title: "Order breached the SLA".to_string(),
+                description: Some(description),
+                ..Default::default()
+            };
+            self.add_notifications(UserRole::Manager, None, notification)
+                .await?;
+        }
+        Ok(rows.len())
+    }
+
+    /// SLA attainment of delivery orders made during the last `days`.
+    pub async fn sla_report(&self, days: i32) -> Result<SlaReport> {
+        let policy = self.sla_policy().await?;
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/select/sla_report.sql"),
+                &[&days, &policy.accept_minutes, &policy.delivery_minutes],
+            )
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    /// Ongoing incidents, the most recent first.
+    pub async fn incidents(&self) -> Result<Vec<Incident>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/ongoing_incidents.sql"), &[])
+            .await
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+            .map_err(Into::into)
+    }
+
+    pub async fn add_incident(&self, incident: &Incident) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/incident.sql"),
+                &[&incident.title, &incident.description],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    pub async fn resolve_incident(&self, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/update/resolved_incident.sql"), &[&id])
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn service_status(&self) -> Result<ServiceStatus> {
+        let is_open = !self.maintenance().await?.is_enabled
+            && self
+                .order_scheduling()
+                .await?
+                .is_open_at(Utc::now().naive_utc().time());
+        let estimated_delivery_minutes = self
+            .client()
+            .await?
+            .query_one(
+                include_str!("sql/select/estimated_delivery_minutes.sql"),
+                &[],
+            )
+            .await?
+            .get(0);
+        Ok(ServiceStatus {
+            is_open,
+            estimated_delivery_minutes,
+            incidents: self.incidents().await?,
+        })
+    }
+
+    pub async fn order_scheduling(&self) -> Result<OrderScheduling> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/order_scheduling.sql"), &[])
+            .await
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
+    }
+
+    pub async fn set_order_scheduling(&self, scheduling: &OrderScheduling) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/order_scheduling.sql"),
+                &[
+                    &scheduling.opening_time,
+                    &scheduling.closing_time,
+                    &scheduling.min_schedule_minutes,
+                    &scheduling.dispatch_minutes,
+                ],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Queues scheduled orders whose dispatch window has opened and promotes
+    /// queued orders. Returns the number of dispatched orders.
+    pub async fn dispatch_scheduled_orders(&self) -> Result<u64> {
+        let count = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/update/scheduled_orders.sql"),
+                &[&self.order_scheduling().await?.dispatch_minutes],
+            )
+            .await?;
+        if count != 0 {
+            self.promote_queued_orders().await?;
+        }
+        Ok(count)
+    }
+
+    /// Returns `None` if the number of active orders isn't limited.
+    pub async fn order_capacity(&self) -> Result<Option<i32>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/order_capacity.sql"), &[])
+            .await
+            .map(|row| row.and_then(|row| row.get(0)))
+            .map_err(Into::into)
+    }
+
+    /// Queued orders which fit into the new capacity are promoted immediately.
+    pub async fn set_order_capacity(&self, capacity: Option<i32>) -> Result<()> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/update/order_capacity.sql"), &[&capacity])
+            .await?;
+        self.promote_queued_orders().await.map(|_| ())
+    }
+
+    /// Moves queued orders to the `Created` status while there are free slots
+    /// and notifies the customers. Returns the number of promoted orders.
+    pub async fn promote_queued_orders(&self) -> Result<usize> {
+        let rows = self
+            .client()
+            .await?
+            .query(include_str!("sql/update/queued_orders.sql"), &[])
+            .await?;
+        for row in &rows {
+            let notification = Notification {
+                title: "Order left the queue".to_string(),
+                description: Some(format!(
+                    "Your order #{} will be processed soon.",
+                    row.get::<_, ID>("id")
+                )),
+                ..Default::default()
+            };
+            self.add_user_notification(row.get("customer_id"), &notification)
+                .await?;
+        }
+        Ok(rows.len())
+    }
+
+    /// Returns `None` if the order isn't queued.
+    pub async fn order_queue_position(
+        &self,
+        username: &str,
+        order_id: ID,
+    ) -> Result<Option<QueuePosition>> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/select/order_queue_position.sql"),
+                &[
+                    &order_id,
+                    &self.user_id_by_name(username).await?,
+                    &DEFAULT_FULFILLMENT_MINUTES,
+                ],
+            )
+            .await
+            .map(|row| row.map(Into::into))
+            .map_err(Into::into)
+    }
+
+    /// Grants promo codes to customers whose birthday is today and notifies them.
+    /// Each customer gets one code a year. Returns the number of granted codes.
+    pub async fn grant_birthday_promo_codes(&self) -> Result<usize> {
+        let settings = self.birthday_promo_settings().await?;
+        if !settings.is_enabled {
+            return Ok(0);
+        }
+        let rows = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/insert/birthday_promo_codes.sql"),
+                &[&settings.discount_percent, &settings.valid_days],
+            )
+            .await?;
+        for row in &rows {
+            let notification = Notification {
+                title: "Happy birthday!".to_string(),
+                description: Some(format!(
+                    "Use promo code {} to get {}% off until {}.",
+                    row.get::<_, String>("code"),
+                    row.get::<_, i16>("discount_percent"),
+                    row.get::<_, NaiveDateTime>("expire_time")
+                        .format("%Y-%m-%d %H:%M")
+                )),
+                ..Default::default()
+            };
+            self.add_user_notification(row.get("customer_id"), &notification)
+                .await?;
+        }
+        Ok(rows.len())
+    }
+
+    /// Grants promo codes for orders delivered later than promised and notifies
+    /// the customers. Each order is compensated once. Returns the number of granted codes.
+    pub async fn grant_late_delivery_promo_codes(&self) -> Result<usize> {
+        let policy = self.late_delivery_policy().await?;
+        let rows = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/insert/late_delivery_promo_codes.sql"),
+                &[
+                    &policy.tolerance_minutes,
+                    &policy.discount_percent,
+                    &policy.valid_days,
+                ],
+            )
+            .await?;
+        for row in &rows {
+            let notification = Notification {
+                title: "Sorry for the late delivery".to_string(),
+                description: Some(format!(
+                    "Your order #{} was delivered late. Use promo code {} to get {}% off until {}.",
+                    row.get::<_, ID>("compensated_order_id"),
+                    row.get::<_, String>("code"),
+                    row.get::<_, i16>("discount_percent"),
+                    row.get::<_, NaiveDateTime>("expire_time")
+                        .format("%Y-%m-%d %H:%M")
+                )),
+                ..Default::default()
+            };
+            self.add_user_notification(row.get("customer_id"), &notification)
+                .await?;
+        }
+        Ok(rows.len())
+    }
+
+    /// Compensation of late deliveries for the last `days`.
+    pub async fn late_delivery_report(&self, days: i32) -> Result<LateDeliveryReport> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/select/late_delivery_report.sql"),
+                &[&days],
+            )
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    /// Records totals of the finished days which aren't settled yet.
+    /// Returns the number of settled days.
+    pub async fn settle_days(&self) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/insert/settlements.sql"), &[])
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Settlements of the days from `from` to `to` inclusive, the oldest first.
+    pub async fn settlements(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Settlement>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/settlements.sql"), &[&from, &to])
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn user_promo_codes(&self, username: &str) -> Result<Vec<PromoCode>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/user_promo_codes.sql"),
+                &[&self.user_id_by_name(username).await?],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn api_keys(&self) -> Result<Vec<ApiKey>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/api_keys.sql"), &[])
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn add_api_key(&self, title: &str, key: &str, scope: ApiKeyScope) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/api_key.sql"),
+                &[&title, &sha256(key), &scope],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Returns scope of the key and number of requests sent
+    /// using it during the current minute if the key is valid.
+    pub async fn use_api_key(&self, key: &str) -> Result<Option<(ApiKeyScope, i32)>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/update/used_api_key.sql"), &[&sha256(key)])
+            .await
+            .map(|row| row.map(|row| (row.get(0), row.get(1))))
+            .map_err(Into::into)
+    }
+
+    /// Counts the request and returns number of requests
+    /// sent by the user during the current minute.
+    pub async fn record_user_request(&self, username: &str) -> Result<i32> {
+        self.client()
+            .await?
+            .query_one(include_str!("sql/update/user_request.sql"), &[&username])
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Users who have sent at least one request, the most active first.
+    pub async fn api_usage(&self, pagination: Pagination) -> Result<Vec<ApiUsage>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/api_usage.sql"),
+                &[&pagination.limit(), &pagination.offset()],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn user_api_usage(&self, username: &str) -> Result<ApiUsage> {
+        self.client()
+            .await?
+            .query_one(include_str!("sql/select/user_api_usage.sql"), &[&username])
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_api_key(&self, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/delete/api_key.sql"), &[&id])
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn user_addresses(&self, username: &str) -> Result<Vec<Address>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/user_addresses.sql"),
+                &[&self.user_id_by_name(username).await?],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn add_user_address(&self, username: &str, address: Address) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/user_address.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &address.locality,
+                    &address.street,
+                    &address.house,
+                    &address.corps,
+                    &address.apartment,
+                ],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    pub async fn update_user_address(
+        &self,
+        username: &str,
+        id: ID,
+        address: &Address,
+    ) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/user_address.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &id,
+                    &address.locality,
+                    &address.street,
+                    &address.house,
+                    &address.corps,
+                    &address.apartment,
+                ],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Returns `false` if the user has no address with such ID.
+    pub async fn set_default_user_address(&self, username: &str, id: ID) -> Result<bool> {
+        let user_id = self.user_id_by_name(username).await?;
+        if !self
+            .is_true(include_str!("sql/check/user_address.sql"), &[&user_id, &id])
+            .await?
+        {
+            return Ok(false);
+        }
+        // The previous default address must be reset first to satisfy the unique index.
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/non_default_addresses.sql"),
+                &[&user_id, &id],
+            )
+            .await?;
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/default_address.sql"),
+                &[&user_id, &id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Moves the address to the trash.
+    pub async fn delete_user_address(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/trashed_address.sql"),
+                &[&self.user_id_by_name(username).await?, &id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn trashed_user_addresses(&self, username: &str) -> Result<Vec<Address>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/trashed_user_addresses.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &TRASH_RETENTION_DAYS,
+                ],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn restore_user_address(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/restored_address.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &id,
+                    &TRASH_RETENTION_DAYS,
+                ],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Returns all categories if `pagination` isn't specified.
+    pub async fn categories(&self, pagination: Option<Pagination>) -> Result<Vec<Category>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/categories.sql"),
+                &[
+                    &pagination.map(|pagination| pagination.limit()),
+                    &pagination
+                        .map(|pagination| pagination.offset())
+                        .unwrap_or(0),
+                ],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn category_by_id(&self, id: ID) -> Result<Option<Category>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/category_by_id.sql"), &[&id])
+            .await
+            .map(|row| row.map(Into::into))
+            .map_err(Into::into)
+    }
+
+    pub async fn export_catalog(&self) -> Result<CatalogDocument> {
+        let preview_url = |of: &str, row: &Row| {
+            row.get::<_, bool>("has_preview")
+                .then(|| format!("/preview?of={of}&id={}", row.get::<_, ID>("id")))
+        };
+        let mut categories: Vec<(ID, CatalogDocumentCategory)> = self
+            .client()
+            .await?
+            .query(include_str!("sql/select/exported_categories.sql"), &[])
+            .await?
+            .iter()
+            .map(|row| {
+                let category = CatalogDocumentCategory {
+                    title: row.get("title"),
+                    description: row.get("description"),
+                    preview_url: preview_url("category", row),
+                    food: Vec::new(),
+                };
+                (row.get("id"), category)
+            })
+            .collect();
+        for row in self
+            .client()
+            .await?
+            .query(include_str!("sql/select/exported_food.sql"), &[])
+            .await?
+        {
+            let category_id: ID = row.get("category_id");
+            if let Some((_, category)) = categories.iter_mut().find(|(id, _)| *id == category_id) {
+                category.food.push(CatalogDocumentFood {
+                    title: row.get("title"),
+                    description: row.get("description"),
+                    count: row.get("count"),
+                    is_alcohol: row.get("is_alcohol"),
+                    price: row.get("price"),
+                    preview_url: preview_url("food", &row),
+                });
+            }
+        }
+        Ok(CatalogDocument {
+            export_time: Utc::now().naive_utc(),
+            categories: categories
+                .into_iter()
+                .map(|(_, category)| category)
+                .collect(),
+        })
+    }
+
+    /// Creates categories and food which don't exist and updates the rest.
+    /// Previews are left unchanged. All changes are recorded in the catalog history.
+    pub async fn import_catalog(
+        &self,
+        manager_username: &str,
+        document: &CatalogDocument,
+    ) -> Result<CatalogImportSummary> {
+        let mut summary = CatalogImportSummary::default();
+        for imported_category in &document.categories {
+            let category = Category {
+                id: 0,
+                title: imported_category.title.clone(),
+                description: imported_category.description.clone(),
+            };
+            let category_id = match self.similar_category_id(&category.title).await? {
+                Some(id) => {
+                    self.update_category(manager_username, id, &category, None)
+                        .await?;
+                    summary.updated_categories += 1;
+                    id
+                }
+                None => {
+                    summary.created_categories += 1;
+                    self.add_category(manager_username, &category, None).await?
+                }
+            };
+
+            for imported_food in &imported_category.food {
+                match self
+                    .similar_food_id(category_id, &imported_food.title)
+                    .await?
+                {
+                    Some(id) => {
+                        let patch = FoodPatch {
+                            title: Some(imported_food.title.clone()),
+                            description: Some(imported_food.description.clone()).into(),
+                            category_id: None,
+                            count: Some(imported_food.count),
+                            is_alcohol: Some(imported_food.is_alcohol),
+                            price: Some(imported_food.price),
+                        };
+                        self.update_food(manager_username, id, &patch).await?;
+                        summary.updated_food += 1;
+                    }
+                    None => {
+                        let food = IndexedFood {
+                            id: 0,
+                            title: imported_food.title.clone(),
+                            description: imported_food.description.clone(),
+                            category_id,
+                            count: imported_food.count,
+                            is_alcohol: imported_food.is_alcohol,
+                            price: imported_food.price,
+                            average_rating: None,
+                            ratings_count: 0,
+                        };
+                        self.add_food(manager_username, &food, None).await?;
+                        summary.created_food += 1;
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Returns ID of a category which title differs only in case or surrounding whitespace.
+    pub async fn similar_category_id(&self, title: &str) -> Result<Option<ID>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/similar_category.sql"), &[&title])
+            .await
+            .map(|row| row.map(|row| row.get(0)))
+            .map_err(Into::into)
+    }
+
+    pub async fn add_category(
+        &self,
+        manager_username: &str,
+        category: &Category,
+        preview: Option<Vec<u8>>,
+    ) -> Result<ID> {
+        self.check_preview_quota(preview.as_deref(), PreviewOf::Category, None)
+            .await?;
+        let id = self
+            .client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/category.sql"),
+                &[&category.title, &category.description, &preview],
+            )
+            .await?
+            .get(0);
+        self.record_catalog_change(
+            manager_username,
+            CatalogEntity::Category,
+            id,
+            CatalogAction::Created,
+            None,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    /// Preview is left unchanged if `preview` is `None`.
+    pub async fn update_category(
+        &self,
+        manager_username: &str,
+        id: ID,
+        category: &Category,
+        preview: Option<Option<Vec<u8>>>,
+    ) -> Result<bool> {
+        if let Some(preview) = &preview {
+            self.check_preview_quota(preview.as_deref(), PreviewOf::Category, Some(id))
+                .await?;
+        }
+        let before = self.catalog_snapshot(CatalogEntity::Category, id).await?;
+        let updated = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/update/category.sql"),
+                &[
+                    &id,
+                    &category.title,
+                    &category.description,
+                    &preview.is_some(),
+                    &preview.flatten(),
+                ],
+            )
+            .await?
+            != 0;
+        if updated {
+            self.record_catalog_change(
+                manager_username,
+                CatalogEntity::Category,
+                id,
+                CatalogAction::Updated,
+                before,
+            )
+            .await?;
+        }
+        Ok(updated)
+    }
+
+    pub async fn delete_category(&self, manager_username: &str, id: ID) -> Result<bool> {
+        let before = self.catalog_snapshot(CatalogEntity::Category, id).await?;
+        let deleted = self
+            .client()
+            .await?
+            .execute(include_str!("sql/delete/category.sql"), &[&id])
+            .await?
+            != 0;
+        if deleted {
+            self.record_catalog_change(
+                manager_username,
+                CatalogEntity::Category,
+                id,
+                CatalogAction::Deleted,
+                before,
+            )
+            .await?;
+        }
+        Ok(deleted)
+    }
+
+    /// Returns ID of food in the category which title differs only in case
+    /// or surrounding whitespace.
+    pub async fn similar_food_id(&self, category_id: ID, title: &str) -> Result<Option<ID>> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/select/similar_food.sql"),
+                &[&category_id, &title],
+            )
+            .await
+            .map(|row| row.map(|row| row.get(0)))
+            .map_err(Into::into)
+    }
+
+    pub async fn food_by_id(&self, id: ID) -> Result<Option<Food>> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/food_by_id.sql"), &[&id])
+            .await
+            .map(|row| {
+                row.map(|row| Food {
+                    indexed_food: row.into(),
+                })
+            })
+            .map_err(Into::into)
+    }
+
+    pub async fn food_in_category(
+        &self,
+        category_id: ID,
+        filter: FoodFilter,
+        sort_by: SortFoodBy,
+        sort_order: SortOrder,
+        pagination: Pagination,
+    ) -> Result<Vec<IndexedFood>> {
+        let statement = include_str!("sql/select/food_in_category.sql")
+            .replace("{sort_column}", sort_by.column())
+            .replace("{direction}", sort_order.sql());
+        self.client()
+            .await?
+            .query(
+                &statement,
+                &[
+                    &category_id,
+                    &pagination.limit(),
+                    &pagination.offset(),
+                    &filter.min_price,
+                    &filter.max_price,
+                    &filter.exclude_alcohol,
+                    &filter.in_stock_only,
+                ],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn food_connection(
+        &self,
+        category_id: ID,
+        sort_by: SortFoodBy,
+        sort_order: SortOrder,
+        first: i64,
+        after: Option<Cursor>,
+    ) -> Result<Connection<IndexedFood>> {
+        check_cursor(after.as_ref(), sort_by.sql_type())?;
+        let statement = include_str!("sql/select/food_in_category_page.sql")
+            .replace("{sort_column}", sort_by.column())
+            .replace("{sort_type}", sort_by.sql_type())
+            .replace("{direction}", sort_order.sql())
+            .replace(
+                "{comparison}",
+                match sort_order {
+                    SortOrder::Ascending => ">",
+                    SortOrder::Descending => "<",
+                },
+            );
+        let first = first.clamp(0, MAX_PAGE_SIZE);
+        let food = self
+            .client()
+            .await?
+            .query(
+                &statement,
+                &[
+                    &category_id,
+                    &after.as_ref().map(|cursor| cursor.id),
+                    &after.as_ref().map(|cursor| cursor.key.to_string()),
+                    // Query one more item to know whether there is a next page.
+                    &(first + 1),
+                ],
+            )
+            .await
+            .map(from_rows)?;
+        Ok(into_connection(food, first, after.is_some(), |food| {
+            sort_by.cursor(food)
+        }))
+    }
+
+    pub async fn add_food(
+        &self,
+        manager_username: &str,
+        food: &IndexedFood,
+        preview: Option<Vec<u8>>,
+    ) -> Result<ID> {
+        self.check_preview_quota(preview.as_deref(), PreviewOf::Food, None)
+            .await?;
+        let id = self
+            .client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/food.sql"),
+                &[
+                    &food.title,
+                    &food.description,
+                    &preview,
+                    &food.category_id,
+                    &food.count,
+                    &food.is_alcohol,
+                    &food.price,
+                ],
+            )
+            .await?
+            .get(0);
+        self.record_price(id).await?;
+        self.record_catalog_change(
+            manager_username,
+            CatalogEntity::Food,
+            id,
+            CatalogAction::Created,
+            None,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    /// Returns `false` if there is no food with such ID or the patch is empty.
+    pub async fn update_food(
+        &self,
+        manager_username: &str,
+        id: ID,
+        patch: &FoodPatch,
+    ) -> Result<bool> {
+        let mut columns = patch.columns();
+        // Set as is instead of adding the difference, which could be outdated.
+        if let Some(count) = &patch.count {
+            columns.push(("count", count));
+        }
+        if columns.is_empty() {
+            return Ok(false);
+        }
+        let assignments = columns
+            .iter()
+            .enumerate()
+            // The first parameters are the food ID and the manager username.
+            .map(|(index, (column, _))| format!("{column} = ${}", index + 3))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut params: Vec<&(dyn ToSql + Sync)> = vec![&id, &manager_username];
+        params.extend(columns.iter().map(|(_, value)| *value));
+
+        let before = self.catalog_snapshot(CatalogEntity::Food, id).await?;
+        let is_updated = self
+            .client()
+            .await?
+            .query_opt(
+                &include_str!("sql/update/food.sql").replace("{assignments}", &assignments),
+                &params,
+            )
+            .await?
+            .is_some();
+        if !is_updated {
+            return Ok(false);
+        }
+        self.record_catalog_change(
+            manager_username,
+            CatalogEntity::Food,
+            id,
+            CatalogAction::Updated,
+            before,
+        )
+        .await?;
+        Ok(true)
+    }
+
+    pub async fn locations(&self) -> Result<Vec<Location>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/locations.sql"), &[])
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn add_location(&self, location: &Location) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/location.sql"),
+                &[&location.title, &location.localities],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Sets stock of the food at the location. The total food count is changed
+    /// by the same difference. Returns the new total count.
+    pub async fn set_location_stock(
+        &self,
+        manager_username: &str,
+        location_id: ID,
+        food_id: ID,
+        count: i32,
+    ) -> Result<i32> {
+        if count < 0 {
+            return Err(Error::Invalid("count can't be negative".to_string()));
+        }
+        let delta: i32 = self
+            .client()
+            .await?
+            .query_one(
+                include_str!("sql/update/location_stock.sql"),
+                &[&location_id, &food_id, &count],
+            )
+            .await?
+            .get(0);
+        self.move_stock(
+            food_id,
+            StockMovementKind::Correction,
+            delta,
+            None,
+            Some(manager_username),
+            Some(&format!("Stock at location with ID {location_id}")),
+        )
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("there is no food with ID {food_id}")))
+    }
+
+    /// Returns stock at all locations grouped by food ID.
+    pub async fn location_stock(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<LocationStock>>> {
+        let mut stock = HashMap::<ID, Vec<LocationStock>>::new();
+        for location_stock in from_rows::<LocationStock>(
+            self.client()
+                .await?
+                .query(include_str!("sql/select/location_stock.sql"), &[&food_ids])
+                .await?,
+        ) {
+            stock
+                .entry(location_stock.food_id)
+                .or_default()
+                .push(location_stock);
+        }
+        Ok(stock)
+    }
+
+    /// Returns price changes grouped by food ID, the latest ones go first.
+    pub async fn price_history(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<PriceChange>>> {
+        let mut history = HashMap::<ID, Vec<PriceChange>>::new();
+        for row in self
+            .client()
+            .await?
+            .query(include_str!("sql/select/price_history.sql"), &[&food_ids])
+            .await?
+        {
+            history
+                .entry(row.get("food_id"))
+                .or_default()
+                .push(row.into());
+        }
+        Ok(history)
+    }
+
+    async fn record_price(&self, food_id: ID) -> Result<()> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/insert/price_history.sql"), &[&food_id])
+            .await?;
+        Ok(())
+    }
+
+    /// Chooses the first location which has all the cart items in stock.
+    /// Returns `None` if there are no locations at all.
+    async fn order_location(
+        &self,
+        order: &IndexedOrder,
+        cart_items: &[CartItem],
+    ) -> Result<Option<ID>> {
+        let candidates: Vec<ID> = match order.fulfillment {
+            // Gift recipient will enter the address later.
+            FulfillmentType::Delivery if order.address_id.is_none() => match order.location_id {
+                Some(location_id) => vec![location_id],
+                None => self
+                    .locations()
+                    .await?
+                    .into_iter()
+                    .map(|location| location.id)
+                    .collect(),
+            },
+            FulfillmentType::Delivery => self
+                .client()
+                .await?
+                .query(
+                    include_str!("sql/select/address_locations.sql"),
+                    &[&order.address_id],
+                )
+                .await?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect(),
+            FulfillmentType::Pickup => order.location_id.into_iter().collect(),
+        };
+        if candidates.is_empty() {
+            if self.locations().await?.is_empty() {
+                return Ok(None);
+            }
+            return Err(match order.fulfillment {
+                FulfillmentType::Delivery => {
+                    Error::Invalid("address isn't served by any location".to_string())
+                }
+                FulfillmentType::Pickup => {
+                    Error::Invalid("location must be specified for pickup".to_string())
+                }
+            });
+        }
+
+        let food_ids: Vec<ID> = cart_items
+            .iter()
+            .map(|item| item.indexed_cart_item.food_id)
+            .collect();
+        for location_id in candidates {
+            let stock: HashMap<ID, i32> = self
+                .client()
+                .await?
+                .query(
+                    include_str!("sql/select/location_food_stock.sql"),
+                    &[&location_id, &food_ids],
+                )
+                .await?
+                .into_iter()
+                .map(|row| (row.get("food_id"), row.get("count")))
+                .collect();
+            if cart_items.iter().all(|item| {
+                stock
+                    .get(&item.indexed_cart_item.food_id)
+                    .is_some_and(|&count| count >= item.indexed_cart_item.count)
+            }) {
+                return Ok(Some(location_id));
+            }
+        }
+        Err(Error::Conflict(
+            "not enough items in stock at the location".to_string(),
+        ))
+    }
+
+    /// Changes stock of the food at the location by `delta`.
+    /// Returns the new count or `None` if there is no food with such ID.
+    pub async fn restock_food(
+        &self,
+        manager_username: &str,
+        id: ID,
+        quantity: i32,
+        comment: Option<&str>,
+    ) -> Result<Option<i32>> {
+        self.move_stock(
+            id,
+            StockMovementKind::Restock,
+            quantity,
+            None,
+            Some(manager_username),
+            comment,
+        )
+        .await
+    }
+
+    /// Returns the new count or `None` if there is no food with such ID.
+    pub async fn adjust_food_stock(
+        &self,
+        manager_username: &str,
+        id: ID,
+        delta: i32,
+        reason: &str,
+    ) -> Result<Option<i32>> {
+        self.move_stock(
+            id,
+            StockMovementKind::Adjustment,
+            delta,
+            None,
+            Some(manager_username),
+            Some(reason),
+        )
+        .await
+        .map_err(|e| match &e {
+            Error::Postgres(err) if err.code() == Some(&SqlState::CHECK_VIOLATION) => {
+                Error::Conflict("stock can't become negative".to_string())
+            }
+            _ => e,
+        })
+    }
+
+    /// Returns food which runs out within `days` according to sales during the last
+    /// `lookback_days`. Suggested quantity brings the stock to `cover_days` of sales.
+    pub async fn reorder_suggestions(
+        &self,
+        days: i32,
+        lookback_days: i32,
+        cover_days: i32,
+    ) -> Result<Vec<ReorderSuggestion>> {
+        let rows = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/select/reorder_suggestions.sql"),
+                &[&lookback_days, &days],
+            )
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let daily_sales: f64 = row.get("daily_sales");
+                let indexed_food = IndexedFood::from(row);
+                let count = f64::from(indexed_food.count);
+                ReorderSuggestion {
+                    days_left: count / daily_sales,
+                    suggested_quantity: ((daily_sales * f64::from(cover_days) - count).ceil()
+                        as i32)
+                        .max(1),
+                    daily_sales,
+                    food: Food { indexed_food },
+                }
+            })
+            .collect())
+    }
+
+    pub async fn stock_history(
+        &self,
+        food_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<StockMovement>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/stock_history.sql"),
+                &[&food_id, &pagination.limit(), &pagination.offset()],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_food(&self, manager_username: &str, id: ID) -> Result<bool> {
+        let before = self.catalog_snapshot(CatalogEntity::Food, id).await?;
+        let deleted = self
+            .client()
+            .await?
+            .execute(include_str!("sql/delete/food.sql"), &[&id])
+            .await?
+            != 0;
+        if deleted {
+            self.record_catalog_change(
+                manager_username,
+                CatalogEntity::Food,
+                id,
+                CatalogAction::Deleted,
+                before,
+            )
+            .await?;
+        }
+        Ok(deleted)
+    }
+
+    pub async fn catalog_history(
+        &self,
+        entity: Option<CatalogEntity>,
+        entity_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<CatalogChange>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/catalog_history.sql"),
+                &[
+                    &entity,
+                    &entity_id,
+                    &pagination.limit(),
+                    &pagination.offset(),
+                ],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    /// Restores the state of the entity which preceded the change.
+    /// Previews aren't restored. Returns ID of the recorded reverting change.
+    pub async fn revert_catalog_change(&self, manager_username: &str, id: ID) -> Result<ID> {
+        let change: CatalogChange = self
+            .client()
+            .await?
+            .query_opt(include_str!("sql/select/catalog_change.sql"), &[&id])
+            .await?
+            .ok_or_else(|| Error::NotFound("there is no catalog change with such ID".to_string()))?
+            .into();
+        let (entity, entity_id) = (change.entity, change.entity_id);
+        let current = self.catalog_snapshot(entity, entity_id).await?;
+
+        match (change.before, &current) {
+            (None, None) => {
+                return Err(Error::Conflict("the entity is already deleted".to_string()))
+            }
+            (None, Some(_)) => {
+                let statement = match entity {
+                    CatalogEntity::Category => include_str!("sql/delete/category.sql"),
+                    CatalogEntity::Food => include_str!("sql/delete/food.sql"),
+                };
+                self.client()
+                    .await?
+                    .execute(statement, &[&entity_id])
+                    .await?;
+            }
+            (Some(before), Some(_)) => {
+                self.client()
+                    .await?
+                    .execute(
+                        &entity.fill_placeholders(include_str!("sql/update/catalog_entity.sql")),
+                        &[&entity_id, &before.0],
+                    )
+                    .await?;
+            }
+            (Some(before), None) => {
+                self.client()
+                    .await?
+                    .execute(
+                        &entity.fill_placeholders(include_str!("sql/insert/catalog_entity.sql")),
+                        &[&before.0],
+                    )
+                    .await?;
+            }
+        }
+        self.record_catalog_change(
+            manager_username,
+            entity,
+            entity_id,
+            CatalogAction::Reverted,
+            current,
+        )
+        .await
+    }
+
+    /// Changes the food count by `delta` and records the movement.
+    /// Returns the new count or `None` if there is no food with such ID.
+    async fn move_stock(
+        &self,
+        food_id: ID,
+        kind: StockMovementKind,
+        delta: i32,
+        order_id: Option<ID>,
+        manager_username: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<Option<i32>> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/food_stock.sql"),
+                &[
+                    &food_id,
+                    &kind,
+                    &delta,
+                    &order_id,
+                    &manager_username,
+                    &comment,
+                ],
+            )
+            .await
+            .map(|row| row.map(|row| row.get(0)))
+            .map_err(Into::into)
+    }
+
+    /// Returns items whose requested counts exceed the stock.
+    async fn stock_shortages(&self, food_ids: &[ID], counts: &[i32]) -> Result<Vec<StockShortage>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/stock_shortages.sql"),
+                &[&food_ids, &counts],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    /// Takes a snapshot of the entity after the change and records it.
+    async fn record_catalog_change(
+        &self,
+        manager_username: &str,
+        entity: CatalogEntity,
+        entity_id: ID,
+        action: CatalogAction,
+        before: Option<serde_json::Value>,
+    ) -> Result<ID> {
+        let after = self.catalog_snapshot(entity, entity_id).await?;
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/catalog_change.sql"),
+                &[
+                    &manager_username,
+                    &entity,
+                    &entity_id,
+                    &action,
+                    &before,
+                    &after,
+                ],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Returns `None` if the entity doesn't exist.
+    async fn catalog_snapshot(
+        &self,
+        entity: CatalogEntity,
+        id: ID,
+    ) -> Result<Option<serde_json::Value>> {
+        self.client()
+            .await?
+            .query_opt(
+                &entity.fill_placeholders(include_str!("sql/select/catalog_snapshot.sql")),
+                &[&id],
+            )
+            .await
+            .map(|row| row.map(|row| row.get(0)))
+            .map_err(Into::into)
+    }
+
+    pub async fn storage_usage(&self) -> Result<StorageUsage> {
+        self.previews_size(None, None)
+            .await
+            .map(
+                |(category_preview_bytes, food_preview_bytes)| StorageUsage {
+                    category_preview_bytes,
+                    food_preview_bytes,
+                    total_bytes: category_preview_bytes + food_preview_bytes,
+                    quota_bytes: self.preview_storage_quota,
+                },
+            )
+    }
+
+    /// Makes sure storing `preview` of the entity (replacing the current one if `id` is set)
+    /// doesn't exceed the preview storage quota.
+    async fn check_preview_quota(
+        &self,
+        preview: Option<&[u8]>,
+        of: PreviewOf,
+        id: Option<ID>,
+    ) -> Result<()> {
+        let (Some(quota), Some(preview)) = (self.preview_storage_quota, preview) else {
+            return Ok(());
+        };
+        let (category_id, food_id) = match of {
+            PreviewOf::Category => (id, None),
+            PreviewOf::Food => (None, id),
+        };
+        let (category_bytes, food_bytes) = self.previews_size(category_id, food_id).await?;
+        if category_bytes + food_bytes + preview.len() as i64 > quota {
+            return Err(Error::Conflict(format!(
+                "preview storage quota of {quota} bytes would be exceeded"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns total sizes of category and food previews,
+    /// excluding previews of the category and food with the given IDs.
+    async fn previews_size(
+        &self,
+        category_id: Option<ID>,
+        food_id: Option<ID>,
+    ) -> Result<(i64, i64)> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/select/storage_usage.sql"),
+                &[&category_id, &food_id],
+            )
+            .await
+            .map(|row| {
+                (
+                    row.get("category_preview_bytes"),
+                    row.get("food_preview_bytes"),
+                )
+            })
+            .map_err(Into::into)
+    }
+
+    /// Starts a resumable upload of `size` bytes. Returns its token.
+    pub async fn create_upload(&self, username: &str, size: i32) -> Result<String> {
+        let token = random_token();
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/insert/upload.sql"),
+                &[&token, &self.user_id_by_name(username).await?, &size],
+            )
+            .await?;
+        Ok(token)
+    }
+
+    /// Returns the number of received bytes and the declared size,
+    /// or `None` if there is no such upload.
+    pub async fn upload_offset(&self, username: &str, token: &str) -> Result<Option<(i32, i32)>> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/select/upload_offset.sql"),
+                &[&token, &self.user_id_by_name(username).await?],
+            )
+            .await
+            .map(|row| row.map(|row| (row.get("offset"), row.get("size"))))
+            .map_err(Into::into)
+    }
+
+    /// Appends the chunk if `offset` equals the number of received bytes.
+    /// Returns the new offset.
+    pub async fn append_upload_chunk(
+        &self,
+        username: &str,
+        token: &str,
+        offset: i32,
+        chunk: &[u8],
+    ) -> Result<i32> {
+        let Some((received, _)) = self.upload_offset(username, token).await? else {
+            return Err(Error::NotFound(
+                "there is no upload with such token".to_string(),
+            ));
+        };
+        if received != offset {
+            return Err(Error::Conflict(format!(
+                "offset doesn't match {received} received bytes"
+            )));
+        }
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/upload_chunk.sql"),
+                &[
+                    &token,
+                    &self.user_id_by_name(username).await?,
+                    &offset,
+                    &chunk,
+                ],
+            )
+            .await
+            .map_err(|err| match err.code() {
+                Some(&SqlState::CHECK_VIOLATION) => {
+                    Error::Invalid("chunk exceeds the declared size".to_string())
+                }
+                _ => err.into(),
+            })?
+            .map(|row| row.get("offset"))
+            .ok_or_else(|| Error::Conflict("upload was changed concurrently".to_string()))
+    }
+
+    /// Deletes the complete upload and returns its data.
+    pub async fn take_upload(&self, username: &str, token: &str) -> Result<Vec<u8>> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/delete/completed_upload.sql"),
+                &[&token, &self.user_id_by_name(username).await?],
+            )
+            .await?
+            .map(|row| row.get("data"))
+            .ok_or_else(|| {
+                Error::NotFound("there is no complete upload with such token".to_string())
+            })
+    }
+
+    /// Deletes uploads which weren't completed or used in time.
+    /// Returns the number of deleted uploads.
+    pub async fn delete_stale_uploads(&self) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/stale_uploads.sql"),
+                &[&UPLOAD_EXPIRE_HOURS],
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns `None` if there is no preview.
+    pub async fn preview(&self, of: PreviewOf, id: ID) -> Result<Option<Vec<u8>>> {
+        self.client()
+            .await?
+            .query_one(
+                match of {
+                    PreviewOf::Category => include_str!("sql/select/category_preview.sql"),
+                    PreviewOf::Food => include_str!("sql/select/food_preview.sql"),
+                },
+                &[&id],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    pub async fn is_user_favorite(&self, username: &str, food_id: ID) -> Result<bool> {
+        self.is_true(
+            include_str!("sql/check/user_favorite.sql"),
+            &[&self.user_id_by_name(username).await?, &food_id],
+        )
+        .await
+    }
+
+    /// Returns only favorites from the collection if `collection_id` is set.
+    pub async fn user_favorites(
+        &self,
+        username: &str,
+        collection_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<Favorite>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/user_favorites.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &pagination.limit(),
+                    &pagination.offset(),
+                    &collection_id,
+                ],
+            )
+            .await
+            .map(|rows| {
+                from_rows(rows)
+                    .into_iter()
+                    .map(|indexed_favorite| Favorite { indexed_favorite })
+                    .collect()
+            })
+            .map_err(Into::into)
+    }
+
+    pub async fn add_user_favorite(
+        &self,
+        username: &str,
+        favorite: &IndexedFavorite,
+    ) -> Result<ID> {
+        let user_id = self.user_id_by_name(username).await?;
+        if let Some(collection_id) = favorite.collection_id {
+            self.check_user_favorite_collection(user_id, collection_id)
+                .await?;
+        }
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/insert/user_favorite.sql"),
+                &[&user_id, &favorite.food_id, &favorite.collection_id],
+            )
+            .await?
+            .map(|row| row.get(0))
+            .ok_or_else(|| Error::Conflict("food is already in favorites".to_string()))
+    }
+
+    /// Moves the favorite to the trash.
+    pub async fn delete_user_favorite(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/trashed_favorite.sql"),
+                &[&self.user_id_by_name(username).await?, &id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Removes the favorite from its collection if `collection_id` is `None`.
+    pub async fn move_user_favorite(
+        &self,
+        username: &str,
+        id: ID,
+        collection_id: Option<ID>,
+    ) -> Result<bool> {
+        let user_id = self.user_id_by_name(username).await?;
+        if let Some(collection_id) = collection_id {
+            self.check_user_favorite_collection(user_id, collection_id)
+                .await?;
+        }
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/favorite_collection.sql"),
+                &[&user_id, &id, &collection_id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn user_favorite_collections(
+        &self,
+        username: &str,
+    ) -> Result<Vec<FavoriteCollection>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/user_favorite_collections.sql"),
+                &[&self.user_id_by_name(username).await?],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    pub async fn add_user_favorite_collection(
+        &self,
+        username: &str,
+        collection: &FavoriteCollection,
+    ) -> Result<ID> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/user_favorite_collection.sql"),
+                &[&self.user_id_by_name(username).await?, &collection.title],
+            )
+            .await
+            .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    pub async fn update_user_favorite_collection(
+        &self,
+        username: &str,
+        id: ID,
+        collection: &FavoriteCollection,
+    ) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/user_favorite_collection.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &id,
+                    &collection.title,
+                ],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Favorites from the collection are kept without a collection.
+    pub async fn delete_user_favorite_collection(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/user_favorite_collection.sql"),
+                &[&self.user_id_by_name(username).await?, &id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    async fn check_user_favorite_collection(&self, user_id: ID, collection_id: ID) -> Result<()> {
+        if self
+            .is_true(
+                include_str!("sql/check/user_favorite_collection.sql"),
+                &[&user_id, &collection_id],
+            )
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(Error::NotFound(
+                "there is no favorite collection with such ID".to_string(),
+            ))
+        }
+    }
+
+    /// Moves the favorites to the trash. Returns the number of deleted favorites.
+    pub async fn delete_user_favorites(&self, username: &str, ids: &[ID]) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/trashed_user_favorites.sql"),
+                &[&self.user_id_by_name(username).await?, &ids],
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Adds one item of each favorite food into the user cart.
+    /// Returns the number of added favorites.
+    pub async fn add_favorites_to_cart(&self, username: &str, ids: &[ID]) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/insert/favorites_cart.sql"),
+                &[&self.user_id_by_name(username).await?, &ids],
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn trashed_user_favorites(&self, username: &str) -> Result<Vec<Favorite>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/trashed_user_favorites.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &TRASH_RETENTION_DAYS,
+                ],
+            )
+            .await
+            .map(|rows| {
+                from_rows(rows)
+                    .into_iter()
+                    .map(|indexed_favorite| Favorite { indexed_favorite })
+                    .collect()
+            })
+            .map_err(Into::into)
+    }
+
+    pub async fn restore_user_favorite(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/restored_favorite.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &id,
+                    &TRASH_RETENTION_DAYS,
+                ],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    /// Permanently deletes addresses and favorites which were in the trash
+    /// for more than `TRASH_RETENTION_DAYS`. Returns the number of deleted rows.
+    pub async fn empty_trash(&self) -> Result<u64> {
+        let addresses = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/delete/trashed_addresses.sql"),
+                &[&TRASH_RETENTION_DAYS],
+            )
+            .await?;
+        let favorites = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/delete/trashed_favorites.sql"),
+                &[&TRASH_RETENTION_DAYS],
+            )
+            .await?;
+        Ok(addresses + favorites)
+    }
+
+    pub async fn is_in_user_cart(&self, username: &str, food_id: ID) -> Result<bool> {
+        self.is_true(
+            include_str!("sql/check/in_user_cart.sql"),
+            &[&self.user_id_by_name(username).await?, &food_id],
+        )
+        .await
+    }
+
+    pub async fn user_cart(
+        &self,
+        username: &str,
+        sort_by: SortCartBy,
+        sort_order: SortOrder,
+    ) -> Result<Cart> {
         let user_id = self.user_id_by_name(username).await?;
         let mut food = self
             .query_food(
@@ -344,290 +2573,1177 @@ impl Client {
                 &[&user_id],
             )
             .await?;
-        let mut indexed_cart: Vec<IndexedCartItem> = self
-            .client
-            .query(include_str!("sql/select/user_cart.sql"), &[&user_id])
+        let statement = include_str!("sql/select/user_cart.sql")
+            .replace("{sort_column}", sort_by.column())
+            .replace("{direction}", sort_order.sql());
+        let indexed_cart: Vec<IndexedCartItem> = self
+            .client()
+            .await?
+            .query(&statement, &[&user_id])
             .await
             .map(from_rows)?;
 
-        indexed_cart.sort_by(|lhs, rhs| sort_by.cmp(lhs, rhs));
-        if let SortOrder::Descending = sort_order {
-            indexed_cart.reverse();
-        }
-
         let mut items = Vec::with_capacity(indexed_cart.capacity());
         for indexed_cart_item in indexed_cart {
             let food = food
                 // We can move a food item because it's
                 // unique per user (constraint 'food_per_customer').
                 .remove(&indexed_cart_item.food_id)
-                .ok_or(anyhow!("database was changed during data merging"))?;
+                .ok_or_else(|| {
+                    Error::Conflict("database was changed during data merging".to_string())
+                })?;
             items.push(CartItem {
                 total_price: food.indexed_food.price * Decimal::from(indexed_cart_item.count),
                 food,
                 indexed_cart_item,
             })
         }
-        Ok(Cart {
-            total_price: items.iter().map(|item| item.total_price).sum(),
-            items,
-        })
+        Ok(Cart {
+            total_price: items.iter().map(|item| item.total_price).sum(),
+            items,
+        })
+    }
+
+    /// If the food is already in the cart, increments count of the existing item.
+    /// Returns ID of the item and whether it was inserted.
+    pub async fn add_user_cart_item(
+        &self,
+        username: &str,
+        item: &IndexedCartItem,
+    ) -> Result<(ID, bool)> {
+        self.client()
+            .await?
+            .query_one(
+                include_str!("sql/insert/user_cart.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &item.food_id,
+                    &item.count,
+                ],
+            )
+            .await
+            .map(|row| (row.get("id"), row.get("inserted")))
+            .map_err(Into::into)
+    }
+
+    /// Adds items of the delivered order into the user cart.
+    /// Items which are out of stock are skipped.
+    pub async fn reorder(&self, username: &str, order_id: ID) -> Result<Reorder> {
+        let order = self.customer_order(order_id, username).await?;
+        if order.indexed_order.status != OrderStatus::Delivered {
+            return Err(Error::Conflict(
+                "only delivered orders can be repeated".to_string(),
+            ));
+        }
+        let user_id = order.indexed_order.customer_id;
+        let (items, skipped): (Vec<_>, Vec<_>) = self
+            .client()
+            .await?
+            .query(include_str!("sql/select/reorder_items.sql"), &[&order_id])
+            .await?
+            .into_iter()
+            .map(StockShortage::from)
+            .partition(|item| item.available >= item.requested);
+        for item in &items {
+            self.client()
+                .await?
+                .execute(
+                    include_str!("sql/insert/user_cart.sql"),
+                    &[&user_id, &item.food_id, &item.requested],
+                )
+                .await?;
+        }
+        Ok(Reorder {
+            added_count: items.len() as i32,
+            skipped,
+        })
+    }
+
+    /// Deletes the item if `count` is 0.
+    pub async fn update_user_cart_item(&self, username: &str, id: ID, count: i32) -> Result<bool> {
+        if count == 0 {
+            return self.delete_user_cart_item(username, id).await;
+        }
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/user_cart.sql"),
+                &[&self.user_id_by_name(username).await?, &id, &count],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_user_cart_item(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/user_cart.sql"),
+                &[&self.user_id_by_name(username).await?, &id],
+            )
+            .await
+            .map(|modified_rows| modified_rows != 0)
+            .map_err(Into::into)
+    }
+
+    pub async fn orders(&self, filter: OrdersFilter, pagination: Pagination) -> Result<Vec<Order>> {
+        self.query_orders(
+            include_str!("sql/select/orders.sql"),
+            &[
+                &filter.statuses(),
+                &pagination.limit(),
+                &pagination.offset(),
+            ],
+        )
+        .await
+    }
+
+    pub async fn user_orders(
+        &self,
+        username: &str,
+        filter: OrdersFilter,
+        pagination: Pagination,
+    ) -> Result<Vec<Order>> {
+        self.query_orders(
+            include_str!("sql/select/user_orders.sql"),
+            &[
+                &self.user_id_by_name(username).await?,
+                &filter.statuses(),
+                &pagination.limit(),
+                &pagination.offset(),
+            ],
+        )
+        .await
+    }
+
+    /// Orders of the year the user was charged for: delivered ones and cancelled with a fee.
+    pub async fn user_receipt_orders(&self, username: &str, year: i32) -> Result<Vec<Order>> {
+        self.query_orders(
+            include_str!("sql/select/user_receipt_orders.sql"),
+            &[&self.user_id_by_name(username).await?, &year],
+        )
+        .await
+    }
+
+    /// Delivery orders which aren't taken by any rider yet.
+    /// Pass `location_id` to get only orders fulfilled from the location.
+    pub async fn available_orders(
+        &self,
+        sort_order: SortOrder,
+        limit: i64,
+        location_id: Option<ID>,
+    ) -> Result<Vec<Order>> {
+        let statement = include_str!("sql/select/available_orders.sql")
+            .replace("{direction}", sort_order.sql());
+        self.query_orders(&statement, &[&limit.clamp(0, MAX_PAGE_SIZE), &location_id])
+            .await
+    }
+
+    /// Orders assigned to the rider which aren't delivered yet.
+    pub async fn rider_active_orders(&self, username: &str) -> Result<Vec<Order>> {
+        self.query_orders(
+            include_str!("sql/select/rider_orders.sql"),
+            &[
+                &self.user_id_by_name(username).await?,
+                &OrdersFilter::InProgress.statuses(),
+            ],
+        )
+        .await
+    }
+
+    /// Returns orders of the user if `username` is specified, otherwise all orders.
+    pub async fn orders_connection(
+        &self,
+        username: Option<&str>,
+        filter: OrdersFilter,
+        first: i64,
+        after: Option<Cursor>,
+    ) -> Result<Connection<Order>> {
+        check_cursor(after.as_ref(), "timestamp")?;
+        let first = first.clamp(0, MAX_PAGE_SIZE);
+        let statuses = filter.statuses();
+        let after_id = after.as_ref().map(|cursor| cursor.id);
+        let after_key = after.as_ref().map(|cursor| cursor.key.to_string());
+        // Query one more order to know whether there is a next page.
+        let limit = first + 1;
+        let orders = match username {
+            Some(username) => {
+                self.query_orders(
+                    include_str!("sql/select/user_orders_page.sql"),
+                    &[
+                        &statuses,
+                        &after_id,
+                        &after_key,
+                        &limit,
+                        &self.user_id_by_name(username).await?,
+                    ],
+                )
+                .await?
+            }
+            None => {
+                self.query_orders(
+                    include_str!("sql/select/orders_page.sql"),
+                    &[&statuses, &after_id, &after_key, &limit],
+                )
+                .await?
+            }
+        };
+        Ok(into_connection(orders, first, after.is_some(), |order| {
+            Cursor {
+                key: CursorKey::Timestamp(order.indexed_order.create_time),
+                id: order.indexed_order.id,
+            }
+        }))
+    }
+
+    pub async fn make_order_from_user_cart(
+        &self,
+        username: &str,
+        order: IndexedOrder,
+        promo_code: Option<&str>,
+    ) -> Result<ID> {
+        let user_id = self.user_id_by_name(username).await?;
+        let pickup_code = match order.fulfillment {
+            FulfillmentType::Delivery if order.address_id.is_none() && order.gift.is_none() => {
+                return Err(Error::Invalid(
+                    "address must be specified for delivery".to_string(),
+                ));
+            }
+            FulfillmentType::Delivery => None,
+            FulfillmentType::Pickup if order.gift.is_some() => {
+                return Err(Error::Invalid("gifts can only be delivered".to_string()));
+            }
+            FulfillmentType::Pickup => {
+                Some(format!("{:06}", rand::thread_rng().gen_range(0..1_000_000)))
+            }
+        };
+        let address_id = match order.fulfillment {
+            FulfillmentType::Delivery => order.address_id,
+            FulfillmentType::Pickup => None,
+        };
+        if let Some(address_id) = address_id {
+            if !self
+                .is_true(
+                    include_str!("sql/check/user_address.sql"),
+                    &[&user_id, &address_id],
+                )
+                .await?
+            {
+                return Err(Error::Invalid(
+                    "user has no address with such ID".to_string(),
+                ));
+            }
+        }
+        if let Some(scheduled_for) = order.scheduled_for {
+            let scheduling = self.order_scheduling().await?;
+            let earliest =
+                Utc::now().naive_utc() + Duration::minutes(scheduling.min_schedule_minutes.into());
+            if scheduled_for < earliest {
+                return Err(Error::Invalid(format!(
+                    "order must be scheduled at least {} minutes ahead",
+                    scheduling.min_schedule_minutes
+                )));
+            }
+            if !scheduling.is_open_at(scheduled_for.time()) {
+                return Err(Error::Invalid(
+                    "order must be scheduled within opening hours".to_string(),
+                ));
+            }
+        }
+        let cart_items = self
+            .user_cart(username, SortCartBy::AddTime, SortOrder::Ascending)
+            .await?
+            .items;
+        if cart_items.is_empty() {
+            return Err(Error::Invalid("user cart is empty".to_string()));
+        }
+        let (food_ids, counts): (Vec<ID>, Vec<i32>) = cart_items
+            .iter()
+            .map(|item| (item.indexed_cart_item.food_id, item.indexed_cart_item.count))
+            .unzip();
+        let shortages = self.stock_shortages(&food_ids, &counts).await?;
+        if !shortages.is_empty() {
+            return Err(Error::OutOfStock(shortages));
+        }
+
+        let location_id = self.order_location(&order, &cart_items).await?;
+        // Other orders are queued or created by the statement itself.
+        let status = order
+            .scheduled_for
+            .is_some()
+            .then_some(OrderStatus::Scheduled);
+
+        let promo_code = match promo_code {
+            Some(code) => Some(
+                self.client()
+                    .await?
+                    .query_opt(
+                        include_str!("sql/select/user_promo_code.sql"),
+                        &[&user_id, &code],
+                    )
+                    .await?
+                    .map(PromoCode::from)
+                    .ok_or_else(|| {
+                        Error::Invalid("promo code is invalid or expired".to_string())
+                    })?,
+            ),
+            None => None,
+        };
+
+        // Stock could be taken by concurrent orders after it was checked,
+        // then the statement fails and nothing is changed.
+        let mut client = self.client().await?;
+        let transaction = client.transaction().await?;
+        // Concurrent orders wait for the lock, so the order capacity
+        // isn't exceeded by orders created at the same time.
+        transaction
+            .query(include_str!("sql/select/locked_settings.sql"), &[])
+            .await?;
+        let result = transaction
+            .query_one(
+                include_str!("sql/insert/user_order.sql"),
+                &[
+                    &user_id,
+                    &address_id,
+                    &order.fulfillment,
+                    &pickup_code,
+                    &location_id,
+                    &status,
+                    &order.gift.as_ref().map(|gift| &gift.recipient_name),
+                    &order.gift.as_ref().map(|gift| &gift.recipient_phone),
+                    &order.gift.as_ref().and_then(|gift| gift.message.as_ref()),
+                    &order.comment,
+                    &order.scheduled_for,
+                    &food_ids,
+                    &counts,
+                    &promo_code.as_ref().map(|promo_code| promo_code.id),
+                ],
+            )
+            .await;
+        let e = match result {
+            Ok(row) => {
+                transaction.commit().await?;
+                return Ok(row.get(0));
+            }
+            Err(e) => e,
+        };
+        let db_error = e.as_db_error();
+        if db_error.and_then(|e| e.column()) == Some("discount_percent") {
+            // The promo code was used by a concurrent order.
+            return Err(Error::Invalid(
+                "promo code is invalid or expired".to_string(),
+            ));
+        }
+        match db_error.and_then(|e| e.constraint()) {
+            Some("non_negative_count") => {
+                let shortages = self.stock_shortages(&food_ids, &counts).await?;
+                Err(if shortages.is_empty() {
+                    Error::Conflict("not enough items in stock at the location".to_string())
+                } else {
+                    Error::OutOfStock(shortages)
+                })
+            }
+            // The address was deleted after it was checked.
+            Some("delivery_address") => Err(Error::Invalid(
+                "user has no address with such ID".to_string(),
+            )),
+            _ => Err(e.into()),
+        }
+    }
+
+    /// Returns a token which the gift recipient uses to enter the address.
+    /// A new token replaces the previous one.
+    pub async fn create_gift_address_link(&self, username: &str, order_id: ID) -> Result<String> {
+        let token = random_token();
+        let modified_rows = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/update/gift_address_token.sql"),
+                &[
+                    &order_id,
+                    &self.user_id_by_name(username).await?,
+                    &sha256(&token),
+                    &GIFT_ADDRESS_LINK_HOURS,
+                ],
+            )
+            .await?;
+        if modified_rows == 0 {
+            return Err(Error::NotFound(
+                "there is no gift order waiting for the recipient address with such ID".to_string(),
+            ));
+        }
+        Ok(token)
+    }
+
+    /// Attaches the address entered by the gift recipient and notifies the customer.
+    /// Returns `false` if the link is invalid, expired or already used.
+    pub async fn add_gift_address(&self, token: &str, address: &Address) -> Result<bool> {
+        let Some(row) = self
+            .client()
+            .await?
+            .query_opt(
+                include_str!("sql/insert/gift_order_address.sql"),
+                &[
+                    &sha256(token),
+                    &address.locality,
+                    &address.street,
+                    &address.house,
+                    &address.corps,
+                    &address.apartment,
+                ],
+            )
+            .await?
+        else {
+            return Ok(false);
+        };
+        let notification = Notification {
+            title: "Gift recipient entered the address".to_string(),
+            description: Some(format!(
+                "Your order #{} will be delivered to the recipient.",
+                row.get::<_, ID>("id")
+            )),
+            ..Default::default()
+        };
+        self.add_user_notification(row.get("customer_id"), &notification)
+            .await?;
+        Ok(true)
+    }
+
+    /// Notifies the customer that the order is taken. Delivery of gifts
+    /// concerns the recipient, so the customer isn't notified about it.
+    pub async fn take_order(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/untaken_order.sql"),
+                &[
+                    &self.user_id_by_name(username).await?,
+                    &id,
+                    &"Order accepted",
+                    &format!("Rider took your order #{id}."),
+                ],
+            )
+            .await
+            .map(|row| row.is_some())
+            .map_err(Into::into)
+    }
+
+    /// Records that the rider is online and serves the location.
+    pub async fn add_rider_ping(&self, username: &str, location_id: ID) -> Result<()> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/insert/rider_ping.sql"),
+                &[&self.user_id_by_name(username).await?, &location_id],
+            )
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Coverage of the locations by hours for the last `days`, the latest first.
+    pub async fn rider_coverage(&self, days: i32) -> Result<Vec<RiderCoverage>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/rider_coverage.sql"), &[&days])
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
+
+    /// Moves orders completed more than `days` ago to the archive tables.
+    /// Returns the number of archived orders.
+    pub async fn archive_orders(&self, days: i32) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/insert/archived_orders.sql"), &[&days])
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_old_rider_pings(&self, retention_days: i32) -> Result<u64> {
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/delete/old_rider_pings.sql"),
+                &[&retention_days],
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Pass `rider_username` to allow releasing only orders assigned to the rider.
+    /// Managers are notified about the released order.
+    pub async fn release_order(
+        &self,
+        id: ID,
+        rider_username: Option<&str>,
+        released_by: &str,
+    ) -> Result<bool> {
+        let rider_id = match rider_username {
+            Some(username) => Some(self.user_id_by_name(username).await?),
+            None => None,
+        };
+        let modified_rows = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/update/released_order.sql"),
+                &[&id, &rider_id],
+            )
+            .await?;
+        if modified_rows == 0 {
+            return Ok(false);
+        }
+        let notification = Notification {
+            title: "Order returned to the pool".to_string(),
+            description: Some(format!(
+                "Order #{id} was released by \"{released_by}\" and waits for another rider."
+            )),
+            ..Default::default()
+        };
+        self.add_notifications(UserRole::Manager, None, notification)
+            .await?;
+        Ok(true)
+    }
+
+    /// Notifies the customer that the order is on the way, except for gifts.
+    pub async fn pick_up_order(&self, username: &str, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/accepted_order.sql"),
+                &[
+                    &id,
+                    &self.user_id_by_name(username).await?,
+                    &"Order is on the way",
+                    &format!("Rider picked up your order #{id}."),
+                ],
+            )
+            .await
+            .map(|row| row.is_some())
+            .map_err(Into::into)
+    }
+
+    /// Notifies the customer that the order is delivered, except for gifts.
+    pub async fn complete_order(&self, username: &str, id: ID) -> Result<bool> {
+        let completed = self
+            .client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/taken_order.sql"),
+                &[
+                    &id,
+                    &self.user_id_by_name(username).await?,
+                    &"Order delivered",
+                    &format!("Your order #{id} was delivered. Enjoy your meal!"),
+                ],
+            )
+            .await?
+            .is_some();
+        if completed {
+            self.promote_queued_orders().await?;
+        }
+        Ok(completed)
+    }
+
+    /// Notifies the customer that the pickup order can be received.
+    pub async fn mark_order_ready_for_pickup(&self, id: ID) -> Result<bool> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(include_str!("sql/update/ready_order.sql"), &[&id])
+            .await?;
+        if let Some(row) = row {
+            let notification = Notification {
+                title: "Order is ready for pickup".to_string(),
+                description: Some(format!(
+                    "Show code {} at the counter to receive your order #{id}.",
+                    row.get::<_, String>("pickup_code")
+                )),
+                ..Default::default()
+            };
+            self.add_user_notification(row.get("customer_id"), &notification)
+                .await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Completes the pickup order if the code matches.
+    pub async fn hand_over_order(&self, id: ID, pickup_code: &str) -> Result<bool> {
+        let completed = self
+            .client()
+            .await?
+            .execute(
+                include_str!("sql/update/handed_over_order.sql"),
+                &[&id, &pickup_code],
+            )
+            .await?
+            != 0;
+        if completed {
+            self.promote_queued_orders().await?;
+        }
+        Ok(completed)
+    }
+
+    /// Moves the order to the next status on behalf of the rider.
+    pub async fn advance_order_status(&self, username: &str, id: ID) -> Result<OrderStatus> {
+        let order = self.order_by_id(id).await?;
+        if order.fulfillment == FulfillmentType::Pickup {
+            return Err(Error::Invalid(
+                "pickup orders aren't delivered by riders".to_string(),
+            ));
+        }
+        let status = order.status;
+        let next_status = status.next(order.fulfillment).ok_or_else(|| {
+            Error::Conflict(format!("order with status {status:?} can't be advanced"))
+        })?;
+        let is_advanced = match next_status {
+            OrderStatus::Accepted => self.take_order(username, id).await?,
+            OrderStatus::PickedUp => self.pick_up_order(username, id).await?,
+            OrderStatus::Delivered => self.complete_order(username, id).await?,
+            OrderStatus::Scheduled
+            | OrderStatus::Queued
+            | OrderStatus::Created
+            | OrderStatus::ReadyForPickup
+            | OrderStatus::Cancelled => false,
+        };
+        if !is_advanced {
+            return Err(Error::Conflict(
+                "order isn't assigned to the rider or its status was changed".to_string(),
+            ));
+        }
+        Ok(next_status)
     }
 
-    pub async fn add_user_cart_item(
-        &self,
-        username: &str,
-        item: &IndexedCartItem,
-    ) -> PostgresResult<ID> {
-        self.client
-            .query_one(
-                include_str!("sql/insert/user_cart.sql"),
-                &[
-                    &self.user_id_by_name(username).await?,
-                    &item.food_id,
-                    &item.count,
-                ],
-            )
+    pub async fn cancellation_policy(&self) -> Result<CancellationPolicy> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/cancellation_policy.sql"), &[])
             .await
-            .map(|row| row.get(0))
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
     }
 
-    pub async fn delete_user_cart_item(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
+    pub async fn set_cancellation_policy(&self, policy: &CancellationPolicy) -> Result<()> {
+        self.client()
+            .await?
             .execute(
-                include_str!("sql/delete/user_cart.sql"),
-                &[&self.user_id_by_name(username).await?, &id],
+                include_str!("sql/update/cancellation_policy.sql"),
+                &[&policy.accepted_fee_percent, &policy.picked_up_fee_percent],
             )
             .await
-            .map(|modified_rows| modified_rows != 0)
+            .map(|_| ())
+            .map_err(Into::into)
     }
 
-    pub async fn orders(&self, filter: OrdersFilter) -> anyhow::Result<Vec<Order>> {
-        self.query_orders(include_str!("sql/select/orders.sql"), &[], filter)
-            .await
+    /// Returns the fee the customer will be charged for cancelling the order.
+    pub async fn cancellation_fee(&self, id: ID, customer_username: &str) -> Result<Decimal> {
+        let order = self.customer_order(id, customer_username).await?;
+        self.customer_cancellation_fee(&order).await
     }
 
-    pub async fn user_orders(
+    /// Pass `customer_username` to allow cancelling only orders owned by the user
+    /// according to the cancellation policy. Non-zero fee must match `confirmed_fee`.
+    /// The assigned rider and the customer (if the order is cancelled by a manager)
+    /// are notified. Returns the charged fee.
+    pub async fn cancel_order(
         &self,
-        username: &str,
-        filter: OrdersFilter,
-    ) -> anyhow::Result<Vec<Order>> {
-        self.query_orders(
-            include_str!("sql/select/user_orders.sql"),
-            &[&self.user_id_by_name(username).await?],
-            filter,
-        )
-        .await
+        id: ID,
+        customer_username: Option<&str>,
+        confirmed_fee: Option<Decimal>,
+        reason: Option<&str>,
+    ) -> Result<Decimal> {
+        let (order, fee) = match customer_username {
+            Some(username) => {
+                let order = self.customer_order(id, username).await?;
+                let fee = self.customer_cancellation_fee(&order).await?;
+                if !fee.is_zero() && confirmed_fee != Some(fee) {
+                    return Err(Error::Invalid(format!(
+                        "cancellation fee {fee} must be confirmed"
+                    )));
+                }
+                (order.indexed_order, fee)
+            }
+            None => {
+                let order = self.order_by_id(id).await?;
+                if !order
+                    .status
+                    .can_transition_to(OrderStatus::Cancelled, order.fulfillment)
+                {
+                    return Err(Error::Conflict(format!(
+                        "order with status {:?} can't be cancelled",
+                        order.status
+                    )));
+                }
+                (order, Decimal::ZERO)
+            }
+        };
+
+        let rider_description = match reason {
+            Some(reason) => format!("Order #{id} was cancelled: {reason}"),
+            None => format!("Order #{id} was cancelled."),
+        };
+        // The customer knows about the cancellation if it's made by the customer.
+        let customer_description = customer_username.is_none().then(|| match reason {
+            Some(reason) => format!("Your order #{id} was cancelled: {reason}"),
+            None => format!("Your order #{id} was cancelled."),
+        });
+        let cancelled = self
+            .client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/cancelled_order.sql"),
+                &[
+                    &id,
+                    &order.status,
+                    &(!fee.is_zero()).then_some(fee),
+                    &reason,
+                    &"Order cancelled",
+                    &rider_description,
+                    &customer_description,
+                ],
+            )
+            .await?
+            .is_some();
+        if !cancelled {
+            return Err(Error::Conflict(
+                "order status was changed during cancellation".to_string(),
+            ));
+        }
+        self.promote_queued_orders().await?;
+        Ok(fee)
     }
 
-    pub async fn make_order_from_user_cart(
-        &self,
-        username: &str,
-        order: IndexedOrder,
-    ) -> anyhow::Result<ID> {
+    /// Returns the order only if it's owned by the user.
+    async fn customer_order(&self, id: ID, username: &str) -> Result<Order> {
         let user_id = self.user_id_by_name(username).await?;
-        let cart_items = self
-            .user_cart(username, SortCartBy::AddTime, SortOrder::Ascending)
+        self.query_orders(include_str!("sql/select/order_by_id.sql"), &[&id])
             .await?
-            .items;
-        if cart_items.is_empty() {
-            return Err(anyhow!("user cart is empty"));
+            .into_iter()
+            .find(|order| order.indexed_order.customer_id == user_id)
+            .ok_or_else(|| {
+                Error::NotFound("there is no order with such ID that owned by the user".to_string())
+            })
+    }
+
+    async fn customer_cancellation_fee(&self, order: &Order) -> Result<Decimal> {
+        let status = order.indexed_order.status;
+        let percent = self
+            .cancellation_policy()
+            .await?
+            .fee_percent(status)
+            .ok_or_else(|| {
+                Error::Conflict(format!("order with status {status:?} can't be cancelled"))
+            })?;
+        Ok((order.total_price * Decimal::from(percent) / Decimal::ONE_HUNDRED).round_dp(2))
+    }
+
+    /// Notifies the customer that the item was excluded from the order.
+    pub async fn mark_order_item_unavailable(&self, id: ID) -> Result<bool> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/unavailable_order_item.sql"),
+                &[
+                    &id,
+                    &"Order item is unavailable",
+                    // Placeholders are the title of the food and the order ID.
+                    &"\"%s\" from your order #%s went out of stock. \
+                      It was excluded from the order total.",
+                ],
+            )
+            .await
+            .map(|row| row.is_some())
+            .map_err(Into::into)
+    }
+
+    pub async fn add_user_feedback(&self, username: &str, feedback: &Feedback) -> Result<ID> {
+        if feedback.rating.is_none()
+            && feedback.comment.is_none()
+            && feedback.rider_rating.is_none()
+        {
+            return Err(Error::Invalid(
+                "either rating or comment must be provided".to_string(),
+            ));
+        }
+
+        let user_id = self.user_id_by_name(username).await?;
+        let order = self
+            .query_orders(
+                include_str!("sql/select/user_order.sql"),
+                &[
+                    &user_id,
+                    &feedback.order_id,
+                    &OrdersFilter::Completed.statuses(),
+                ],
+            )
+            .await?
+            .into_iter()
+            .next();
+        let Some(order) = order else {
+            return Err(Error::NotFound(
+                "there is no completed order with such ID that owned by the user".to_string(),
+            ));
+        };
+        if feedback.rider_rating.is_some() && order.indexed_order.rider_id.is_none() {
+            return Err(Error::Invalid(
+                "only orders delivered by a rider can have the rider rating".to_string(),
+            ));
         }
 
-        let order_id = self
-            .client
+        let id = self
+            .client()
+            .await?
             .query_one(
-                include_str!("sql/insert/user_order.sql"),
-                &[&user_id, &order.address_id, &user_id],
+                include_str!("sql/insert/feedback.sql"),
+                &[
+                    &feedback.order_id,
+                    &feedback.rating,
+                    &feedback.comment,
+                    &feedback.rider_rating,
+                ],
             )
             .await?
             .get(0);
-        for cart_item in cart_items {
-            self.client
-                .execute(
-                    include_str!("sql/insert/order_food.sql"),
-                    &[
-                        &order_id,
-                        &cart_item.indexed_cart_item.food_id,
-                        &cart_item.indexed_cart_item.count,
-                    ],
-                )
-                .await?;
+        self.update_food_ratings(feedback.order_id).await?;
+        Ok(id)
+    }
+
+    /// If `customer_username` is set, the feedback must be left by the customer within the edit
+    /// window. Otherwise, it's changed by a manager.
+    pub async fn update_user_feedback(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+        rating: Option<i16>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        if rating.is_none() && comment.is_none() {
+            return Err(Error::Invalid(
+                "either rating or comment must be provided".to_string(),
+            ));
         }
+        let order_id = self.editable_feedback_order(id, customer_username).await?;
+        self.client()
+            .await?
+            .execute(
+                include_str!("sql/update/feedback.sql"),
+                &[&id, &rating, &comment],
+            )
+            .await?;
+        self.update_food_ratings(order_id).await
+    }
 
-        self.client
-            .execute(include_str!("sql/delete/user_cart_all.sql"), &[&user_id])
+    /// Same restrictions as for [`Self::update_user_feedback`] are applied.
+    pub async fn delete_user_feedback(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+    ) -> Result<()> {
+        let order_id = self.editable_feedback_order(id, customer_username).await?;
+        self.client()
+            .await?
+            .execute(include_str!("sql/delete/feedback.sql"), &[&id])
             .await?;
-        Ok(order_id)
+        self.update_food_ratings(order_id).await
     }
 
-    pub async fn take_order(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
-            .execute(
-                include_str!("sql/update/untaken_order.sql"),
-                &[&self.user_id_by_name(username).await?, &id],
-            )
+    pub async fn feedback_policy(&self) -> Result<FeedbackPolicy> {
+        self.client()
+            .await?
+            .query_opt(include_str!("sql/select/feedback_policy.sql"), &[])
             .await
-            .map(|modified_rows| modified_rows != 0)
+            .map(|row| row.map(Into::into).unwrap_or_default())
+            .map_err(Into::into)
     }
 
-    pub async fn complete_order(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
+    pub async fn set_feedback_policy(&self, policy: &FeedbackPolicy) -> Result<()> {
+        self.client()
+            .await?
             .execute(
-                include_str!("sql/update/taken_order.sql"),
-                &[&id, &self.user_id_by_name(username).await?],
+                include_str!("sql/update/feedback_policy.sql"),
+                &[&policy.edit_hours],
             )
             .await
-            .map(|modified_rows| modified_rows != 0)
+            .map(|_| ())
+            .map_err(Into::into)
     }
 
-    pub async fn delete_untaken_user_order(&self, username: &str, id: ID) -> PostgresResult<bool> {
-        self.client
-            .execute(
-                include_str!("sql/delete/untaken_user_order.sql"),
-                &[&self.user_id_by_name(username).await?, &id],
+    /// Returns ID of the order the feedback is left for.
+    async fn editable_feedback_order(&self, id: ID, customer_username: Option<&str>) -> Result<ID> {
+        let edit_hours = self.feedback_policy().await?.edit_hours;
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                include_str!("sql/select/feedback_author.sql"),
+                &[&id, &edit_hours],
             )
-            .await
-            .map(|modified_rows| modified_rows != 0)
+            .await?;
+        let Some(row) = row else {
+            return Err(Error::NotFound(
+                "there is no feedback with such ID".to_string(),
+            ));
+        };
+        if let Some(username) = customer_username {
+            if row.get::<_, ID>("customer_id") != self.user_id_by_name(username).await? {
+                return Err(Error::NotFound(
+                    "there is no feedback with such ID left by the user".to_string(),
+                ));
+            }
+            if !row.get::<_, bool>("is_editable") {
+                return Err(Error::Conflict(format!(
+                    "feedback can only be changed within {edit_hours} hours"
+                )));
+            }
+        }
+        Ok(row.get("order_id"))
     }
 
-    pub async fn add_user_feedback(
+    /// Feedbacks including the archived ones, the newest first.
+    pub async fn feedbacks(
         &self,
-        username: &str,
-        feedback: &Feedback,
-    ) -> anyhow::Result<ID> {
-        if feedback.rating.is_none() && feedback.comment.is_none() {
-            return Err(anyhow!("either rating or comment must be provided"));
-        }
+        filter: FeedbacksFilter,
+        pagination: Pagination,
+    ) -> Result<Vec<Feedback>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/feedbacks.sql"),
+                &[
+                    &filter.is_hidden(),
+                    &pagination.limit(),
+                    &pagination.offset(),
+                ],
+            )
+            .await
+            .map(from_rows)
+            .map_err(Into::into)
+    }
 
-        let user_id = self.user_id_by_name(username).await?;
-        let order = self
-            .query_orders(
-                include_str!("sql/select/user_order.sql"),
-                &[&user_id, &feedback.order_id],
-                OrdersFilter::Completed,
+    pub async fn hide_feedback(&self, id: ID, reason: &str) -> Result<()> {
+        let order_id: ID = self
+            .client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/hidden_feedback.sql"),
+                &[&id, &reason],
             )
             .await?
-            .into_iter()
-            .next();
-        if order.is_none() {
-            return Err(anyhow!(
-                "there is no completed order with such ID that owned by the user"
-            ));
-        }
+            .ok_or_else(|| Error::NotFound("there is no feedback with such ID".to_string()))?
+            .get(0);
+        self.update_food_ratings(order_id).await
+    }
+
+    async fn update_food_ratings(&self, order_id: ID) -> Result<()> {
+        self.client()
+            .await?
+            .execute(include_str!("sql/update/food_ratings.sql"), &[&order_id])
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
 
-        self.client
+    pub async fn rider_rating_summary(&self, username: &str) -> Result<RiderRatingSummary> {
+        self.client()
+            .await?
             .query_one(
-                include_str!("sql/insert/feedback.sql"),
-                &[&feedback.order_id, &feedback.rating, &feedback.comment],
+                include_str!("sql/select/rider_rating_summary.sql"),
+                &[&self.user_by_name(username).await?.id],
             )
             .await
-            .map(|row| row.get(0))
+            .map(Into::into)
             .map_err(Into::into)
     }
 
-    async fn user_by_id(&self, id: ID) -> PostgresResult<User> {
-        self.client
-            .query_one(include_str!("sql/select/user_by_id.sql"), &[&id])
-            .await
+    pub async fn duplicate_users(&self) -> Result<Vec<DuplicateUsers>> {
+        let groups: Vec<Vec<ID>> = self
+            .client()
+            .await?
+            .query(include_str!("sql/select/duplicate_users.sql"), &[])
+            .await?
+            .into_iter()
+            .map(|row| row.get("user_ids"))
+            .collect();
+        let ids: Vec<ID> = groups.iter().flatten().copied().collect();
+        let mut users = self.users_by_ids(&ids).await?;
+        Ok(groups
+            .into_iter()
+            .map(|ids| DuplicateUsers {
+                users: ids.iter().filter_map(|id| users.remove(id)).collect(),
+            })
+            .collect())
+    }
+
+    /// Moves orders, addresses, favorites and promo codes of the source customer
+    /// to the target one and deletes the source account. The merge is recorded.
+    pub async fn merge_users(
+        &self,
+        source_id: ID,
+        target_id: ID,
+        manager_username: &str,
+    ) -> Result<UserMerge> {
+        self.client()
+            .await?
+            .query_opt(
+                include_str!("sql/update/merged_user.sql"),
+                &[
+                    &source_id,
+                    &target_id,
+                    &self.user_id_by_name(manager_username).await?,
+                ],
+            )
+            .await?
             .map(Into::into)
+            .ok_or_else(|| Error::NotFound("users must be two different customers".to_string()))
+    }
+
+    pub async fn users_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, User>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/users_by_ids.sql"), &[&ids])
+            .await
+            .map(|rows| {
+                from_rows::<User>(rows)
+                    .into_iter()
+                    .map(|user| (user.id, user))
+                    .collect()
+            })
+            .map_err(Into::into)
+    }
+
+    /// Fails with [Error::UnknownUser], so it's used only for the current user.
+    /// Users specified in the input are looked up using [Self::user_by_name].
+    async fn user_id_by_name(&self, username: &str) -> Result<ID> {
+        self.find_user_by_name(username)
+            .await?
+            .map(|user| user.id)
+            .ok_or_else(|| Error::UnknownUser(username.to_string()))
+    }
+
+    pub async fn addresses_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Address>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/addresses_by_ids.sql"), &[&ids])
+            .await
+            .map(|rows| {
+                from_rows::<Address>(rows)
+                    .into_iter()
+                    .map(|address| (address.id, address))
+                    .collect()
+            })
+            .map_err(Into::into)
+    }
+
+    pub async fn categories_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Category>> {
+        self.client()
+            .await?
+            .query(include_str!("sql/select/categories_by_ids.sql"), &[&ids])
+            .await
+            .map(|rows| {
+                from_rows::<Category>(rows)
+                    .into_iter()
+                    .map(|category| (category.id, category))
+                    .collect()
+            })
+            .map_err(Into::into)
     }
 
-    async fn user_id_by_name(&self, username: &str) -> PostgresResult<ID> {
-        self.user_by_name(username).await.map(|user| user.id)
+    pub async fn food_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Food>> {
+        self.query_food(include_str!("sql/select/food_by_ids.sql"), &[&ids])
+            .await
     }
 
-    async fn address_by_id(&self, id: ID) -> PostgresResult<Address> {
-        self.client
-            .query_one(include_str!("sql/select/address_by_id.sql"), &[&id])
+    async fn order_by_id(&self, id: ID) -> Result<IndexedOrder> {
+        self.client()
+            .await?
+            .query_one(include_str!("sql/select/order_by_id.sql"), &[&id])
             .await
             .map(Into::into)
+            .map_err(Into::into)
     }
 
     async fn query_food(
         &self,
         statement: &str,
         params: &[&(dyn ToSql + Sync)],
-    ) -> anyhow::Result<HashMap<ID, Food>> {
-        let categories: HashMap<_, _> = self
-            .categories()
+    ) -> Result<HashMap<ID, Food>> {
+        self.client()
             .await?
-            .into_iter()
-            .map(|category| (category.id, category))
-            .collect();
-        let indexed_food: Vec<IndexedFood> =
-            self.client.query(statement, params).await.map(from_rows)?;
-
-        let mut food = HashMap::with_capacity(indexed_food.capacity());
-        // Using loop instead of closure because we must be able to propage an error.
-        for indexed_food in indexed_food {
-            let category = categories
-                .get(&indexed_food.category_id)
-                .ok_or(anyhow!("database was changed during data merging"))?
-                .clone();
-            food.insert(
-                indexed_food.id,
-                Food {
-                    category,
-                    indexed_food,
-                },
-            );
-        }
-        Ok(food)
+            .query(statement, params)
+            .await
+            .map(|rows| {
+                from_rows::<IndexedFood>(rows)
+                    .into_iter()
+                    .map(|indexed_food| (indexed_food.id, Food { indexed_food }))
+                    .collect()
+            })
+            .map_err(Into::into)
     }
 
     async fn query_orders(
         &self,
         statement: &str,
         params: &[&(dyn ToSql + Sync)],
-        filter: OrdersFilter,
-    ) -> anyhow::Result<Vec<Order>> {
+    ) -> Result<Vec<Order>> {
         let indexed_orders: Vec<IndexedOrder> = self
-            .client
+            .client()
+            .await?
             .query(statement, params)
             .await
-            .map(from_rows)?
-            .into_iter()
-            .filter(|order| filter.fits(order))
-            .collect();
+            .map(from_rows)?;
+        if indexed_orders.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Related data is loaded for all orders at once to avoid a query per order.
+        // Customers, riders and addresses are resolved lazily using data loaders.
+        let order_ids: Vec<_> = indexed_orders.iter().map(|order| order.id).collect();
+        let mut items = self.orders_items(&order_ids).await?;
+        let mut feedbacks = self.orders_feedbacks(&order_ids).await?;
 
         let mut orders = Vec::with_capacity(indexed_orders.capacity());
         for indexed_order in indexed_orders {
-            let items = self.order_items(indexed_order.id).await?;
+            let items = items.remove(&indexed_order.id).unwrap_or_default();
+            let subtotal: Decimal = items
+                .iter()
+                .filter(|item| !item.indexed_item.is_unavailable)
+                .map(|item| item.total_price)
+                .sum();
+            let discount = Decimal::from(indexed_order.discount_percent) / Decimal::from(100);
             orders.push(Order {
-                customer: self.user_by_id(indexed_order.customer_id).await?,
-                address: self.address_by_id(indexed_order.address_id).await?,
-                rider: match indexed_order.rider_id {
-                    Some(id) => Some(self.user_by_id(id).await?),
-                    None => None,
-                },
-                total_price: items.iter().map(|item| item.total_price).sum(),
+                total_price: (subtotal * (Decimal::ONE - discount)).round_dp(2),
                 items,
-                feedback: self.order_feedback(indexed_order.id).await?,
+                feedback: feedbacks.remove(&indexed_order.id),
                 indexed_order,
             })
         }
         Ok(orders)
     }
 
-    async fn order_items(&self, order_id: ID) -> anyhow::Result<Vec<OrderItem>> {
-        let mut food = self
-            .query_food(include_str!("sql/select/order_food.sql"), &[&order_id])
+    /// Returns items grouped by order ID.
+    async fn orders_items(&self, order_ids: &[ID]) -> Result<HashMap<ID, Vec<OrderItem>>> {
+        let food = self
+            .query_food(include_str!("sql/select/orders_food.sql"), &[&order_ids])
+            .await?;
+        let rows = self
+            .client()
+            .await?
+            .query(include_str!("sql/select/orders_items.sql"), &[&order_ids])
             .await?;
-        let indexed_items: Vec<IndexedOrderItem> = self
-            .client
-            .query(include_str!("sql/select/order_items.sql"), &[&order_id])
-            .await
-            .map(from_rows)?;
 
-        let mut items = Vec::with_capacity(indexed_items.capacity());
-        for indexed_item in indexed_items {
-            let food = food
-                // We can move a food item because it's
-                // unique per order (constraint 'food_per_order').
-                .remove(&indexed_item.food_id)
-                .ok_or(anyhow!("database was changed during data merging"))?;
-            items.push(OrderItem {
+        let mut items: HashMap<ID, Vec<OrderItem>> = HashMap::with_capacity(order_ids.len());
+        for row in rows {
+            let order_id = row.get("order_id");
+            let indexed_item = IndexedOrderItem::from(row);
+            let food = food.get(&indexed_item.food_id).cloned().ok_or_else(|| {
+                Error::Conflict("database was changed during data merging".to_string())
+            })?;
+            items.entry(order_id).or_default().push(OrderItem {
                 total_price: food.indexed_food.price * Decimal::from(indexed_item.count),
                 food,
                 indexed_item,
@@ -636,25 +3752,105 @@ impl Client {
         Ok(items)
     }
 
-    async fn order_feedback(&self, order_id: ID) -> PostgresResult<Option<Feedback>> {
-        self.client
-            .query_opt(include_str!("sql/select/order_feedback.sql"), &[&order_id])
+    /// Analyzes feedbacks on orders completed during the last `days`. Keywords are
+    /// extracted only from comments with rating up to `max_rating` if it's specified.
+    pub async fn feedback_analytics(
+        &self,
+        days: i32,
+        max_rating: Option<i16>,
+        keyword_limit: usize,
+    ) -> Result<FeedbackAnalytics> {
+        let ratings = self
+            .client()
+            .await?
+            .query(include_str!("sql/select/feedback_ratings.sql"), &[&days])
+            .await
+            .map(from_rows)?;
+        let comments: Vec<String> = self
+            .client()
+            .await?
+            .query(
+                include_str!("sql/select/feedback_comments.sql"),
+                &[&days, &max_rating],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        Ok(FeedbackAnalytics {
+            ratings,
+            keywords: keywords::top_keywords(comments.iter().map(String::as_str), keyword_limit),
+        })
+    }
+
+    async fn orders_feedbacks(&self, order_ids: &[ID]) -> Result<HashMap<ID, Feedback>> {
+        self.client()
+            .await?
+            .query(
+                include_str!("sql/select/orders_feedbacks.sql"),
+                &[&order_ids],
+            )
             .await
-            .map(|row| row.map(Into::into))
+            .map(|rows| {
+                from_rows::<Feedback>(rows)
+                    .into_iter()
+                    .map(|feedback| (feedback.order_id, feedback))
+                    .collect()
+            })
+            .map_err(Into::into)
     }
 
-    async fn is_true(
-        &self,
-        statement: &str,
-        params: &[&(dyn ToSql + Sync)],
-    ) -> PostgresResult<bool> {
-        self.client
+    async fn is_true(&self, statement: &str, params: &[&(dyn ToSql + Sync)]) -> Result<bool> {
+        self.client()
+            .await?
             .query_one(statement, params)
             .await
             .map(|row| row.get(0))
+            .map_err(Into::into)
+    }
+}
+
+/// Cursor made for a list sorted by another key is rejected.
+pub(crate) fn check_cursor(cursor: Option<&Cursor>, sql_type: &str) -> Result<()> {
+    match cursor {
+        Some(cursor) if cursor.key.sql_type() != sql_type => {
+            Err(Error::Invalid("invalid cursor".to_string()))
+        }
+        _ => Ok(()),
     }
 }
 
+/// Names of the tables and views created by the migration.
+fn created_relations(migration: &str) -> Vec<&str> {
+    migration
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("CREATE TABLE public.")
+                .or_else(|| line.strip_prefix("CREATE VIEW public."))
+        })
+        .filter_map(|rest| {
+            rest.split(|char: char| !char.is_ascii_alphanumeric() && char != '_')
+                .next()
+        })
+        .collect()
+}
+
 fn from_rows<T: From<Row>>(rows: Vec<Row>) -> Vec<T> {
     rows.into_iter().map(Into::into).collect()
 }
+
+/// `nodes` can contain one extra item signifying that there is a next page.
+pub(crate) fn into_connection<T: OutputType>(
+    mut nodes: Vec<T>,
+    first: i64,
+    has_previous_page: bool,
+    cursor: impl Fn(&T) -> Cursor,
+) -> Connection<T> {
+    let has_next_page = nodes.len() as i64 > first;
+    nodes.truncate(first as usize);
+    let mut connection = Connection::new(has_previous_page, has_next_page);
+    connection
+        .edges
+        .extend(nodes.into_iter().map(|node| Edge::new(cursor(&node), node)));
+    connection
+}
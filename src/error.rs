@@ -0,0 +1,172 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Errors returned by resolvers. Each kind has a stable `code` in the GraphQL
+//! error extensions, so clients can branch on it instead of parsing messages.
+
+use async_graphql::{to_value, Error, ErrorExtensionValues, ServerError};
+use log::error;
+use rust_decimal::Decimal;
+use tokio_postgres::error::SqlState;
+
+use crate::{
+    db,
+    types::{StockShortage, ID},
+};
+
+pub type Result<T, E = AppError> = std::result::Result<T, E>;
+
+pub enum AppError {
+    /// `NOT_FOUND`: the entity doesn't exist or isn't accessible by the user.
+    NotFound(String),
+    /// `UNAUTHENTICATED`: the account of the current user doesn't exist anymore.
+    Unauthenticated(String),
+    /// `FORBIDDEN`: the user isn't allowed to perform the operation.
+    Forbidden(String),
+    /// `INVALID_INPUT`: `field` is specified if the error is caused by a single input field.
+    Validation {
+        field: Option<String>,
+        message: String,
+    },
+    /// `CONFLICT`: the operation conflicts with the current state,
+    /// `existingId` is provided if it's caused by an existing entity.
+    Conflict {
+        message: String,
+        existing_id: Option<ID>,
+    },
+    /// `FEE_CONFIRMATION_REQUIRED`: the operation is charged with the `fee`.
+    FeeConfirmationRequired(Decimal),
+    /// `OUT_OF_STOCK`: `items` lists food with the requested and available counts.
+    OutOfStock(Vec<StockShortage>),
+    /// `INTERNAL`: details are logged, but not exposed to the client.
+    Internal,
+}
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    /// Error which isn't related to a specific input field.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::Validation {
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict {
+            message: message.into(),
+            existing_id: None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Unauthenticated(_) => "UNAUTHENTICATED",
+            Self::Forbidden(_) => "FORBIDDEN",
+            Self::Validation { .. } => "INVALID_INPUT",
+            Self::Conflict { .. } => "CONFLICT",
+            Self::FeeConfirmationRequired(_) => "FEE_CONFIRMATION_REQUIRED",
+            Self::OutOfStock(_) => "OUT_OF_STOCK",
+            Self::Internal => "INTERNAL",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::NotFound(message)
+            | Self::Unauthenticated(message)
+            | Self::Forbidden(message)
+            | Self::Validation { message, .. }
+            | Self::Conflict { message, .. } => message.clone(),
+            Self::FeeConfirmationRequired(_) => "cancellation fee must be confirmed".to_string(),
+            Self::OutOfStock(_) => "not enough items in stock".to_string(),
+            Self::Internal => "internal server error".to_string(),
+        }
+    }
+}
+
+// Display isn't implemented to not fall under the blanket conversion into [Error],
+// which would drop the extensions.
+impl From<AppError> for Error {
+    fn from(err: AppError) -> Self {
+        let mut extensions = ErrorExtensionValues::default();
+        extensions.set("code", err.code());
+        match &err {
+            AppError::Validation {
+                field: Some(field), ..
+            } => extensions.set("field", field.as_str()),
+            AppError::Conflict {
+                existing_id: Some(existing_id),
+                ..
+            } => extensions.set("existingId", *existing_id),
+            AppError::FeeConfirmationRequired(fee) => extensions.set("fee", fee.to_string()),
+            AppError::OutOfStock(items) => {
+                extensions.set("items", to_value(items).unwrap_or_default())
+            }
+            _ => {}
+        }
+        Self {
+            message: err.message(),
+            source: None,
+            extensions: Some(extensions),
+        }
+    }
+}
+
+impl From<AppError> for ServerError {
+    fn from(err: AppError) -> Self {
+        let err = Error::from(err);
+        Self {
+            extensions: err.extensions,
+            ..ServerError::new(err.message, None)
+        }
+    }
+}
+
+impl From<&db::Error> for AppError {
+    /// Constraint violations are caused by the input, other database errors are internal.
+    fn from(err: &db::Error) -> Self {
+        let err = match err {
+            db::Error::NotFound(message) => return Self::NotFound(message.clone()),
+            db::Error::UnknownUser(username) => {
+                return Self::Unauthenticated(format!("account \"{username}\" doesn't exist"))
+            }
+            db::Error::Conflict(message) => return Self::conflict(message),
+            db::Error::Invalid(message) => return Self::invalid(message),
+            db::Error::OutOfStock(items) => return Self::OutOfStock(items.clone()),
+            db::Error::Postgres(err) => err,
+            db::Error::Pool(err) => {
+                error!("Unable to get connection to database: {err}");
+                return Self::Internal;
+            }
+        };
+        match err.code() {
+            Some(&SqlState::FOREIGN_KEY_VIOLATION) => {
+                Self::not_found("referenced entity doesn't exist")
+            }
+            Some(&SqlState::UNIQUE_VIOLATION) => Self::conflict("entity already exists"),
+            Some(&SqlState::CHECK_VIOLATION | &SqlState::NOT_NULL_VIOLATION) => {
+                Self::invalid("input violates a constraint")
+            }
+            _ => {
+                error!("Database query failed: {err}");
+                Self::Internal
+            }
+        }
+    }
+}
+
+impl From<db::Error> for AppError {
+    fn from(err: db::Error) -> Self {
+        Self::from(&err)
+    }
+}
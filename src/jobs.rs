@@ -0,0 +1,277 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Background tasks which are run periodically.
+
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use tokio::{
+    sync::Notify,
+    time::{self, Interval},
+};
+
+use crate::db;
+
+const BIRTHDAY_PROMOS_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const NOTIFICATIONS_CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const TRASH_CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const ORDER_QUEUE_INTERVAL: Duration = Duration::from_secs(60);
+const LATE_DELIVERY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SLA_MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+const RIDER_PINGS_CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const ORDER_ARCHIVE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const SETTLEMENT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const UPLOADS_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How often [drain] checks whether the running jobs are finished.
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+static IS_STOPPING: AtomicBool = AtomicBool::new(false);
+static STOP: Notify = Notify::const_new();
+/// Number of jobs which aren't finished.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Grants birthday promo codes every hour. A customer gets only one code a year,
+/// so the job can run many times a day and catches up after restarts.
+pub fn spawn_birthday_promos(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(BIRTHDAY_PROMOS_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.grant_birthday_promo_codes().await {
+                Ok(0) => {}
+                Ok(count) => info!("Granted {count} birthday promo codes"),
+                Err(e) => error!("Unable to grant birthday promo codes: {e}"),
+            }
+        }
+    });
+}
+
+/// Settles finished days every hour. Each day is settled once,
+/// so the job catches up after restarts.
+pub fn spawn_daily_settlement(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(SETTLEMENT_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.settle_days().await {
+                Ok(0) => {}
+                Ok(count) => info!("Settled {count} days"),
+                Err(e) => error!("Unable to settle days: {e}"),
+            }
+        }
+    });
+}
+
+/// Compensates orders delivered later than promised every 5 minutes.
+/// Orders which are already compensated are skipped.
+pub fn spawn_late_delivery_compensation(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(LATE_DELIVERY_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.grant_late_delivery_promo_codes().await {
+                Ok(0) => {}
+                Ok(count) => info!("Granted {count} late delivery promo codes"),
+                Err(e) => error!("Unable to grant late delivery promo codes: {e}"),
+            }
+        }
+    });
+}
+
+/// Notifies managers about orders breaching the SLA every minute.
+pub fn spawn_sla_monitor(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(SLA_MONITOR_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.report_sla_breaches().await {
+                Ok(0) => {}
+                Ok(count) => info!("Reported {count} orders breaching the SLA"),
+                Err(e) => error!("Unable to report orders breaching the SLA: {e}"),
+            }
+        }
+    });
+}
+
+/// Deletes notifications older than `retention_days` once a day.
+pub fn spawn_notifications_cleanup(db: Arc<db::Client>, retention_days: i32) {
+    spawn(async move {
+        let mut interval = time::interval(NOTIFICATIONS_CLEANUP_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.delete_old_notifications(retention_days).await {
+                Ok(0) => {}
+                Ok(count) => {
+                    info!("Deleted {count} notifications older than {retention_days} days")
+                }
+                Err(e) => error!("Unable to delete old notifications: {e}"),
+            }
+        }
+    });
+}
+
+/// Archives orders completed more than `days` ago once a day.
+pub fn spawn_order_archiver(db: Arc<db::Client>, days: i32) {
+    spawn(async move {
+        let mut interval = time::interval(ORDER_ARCHIVE_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.archive_orders(days).await {
+                Ok(0) => {}
+                Ok(count) => info!("Archived {count} orders completed more than {days} days ago"),
+                Err(e) => error!("Unable to archive orders: {e}"),
+            }
+        }
+    });
+}
+
+/// Deletes rider pings older than `retention_days` once a day.
+pub fn spawn_rider_pings_cleanup(db: Arc<db::Client>, retention_days: i32) {
+    spawn(async move {
+        let mut interval = time::interval(RIDER_PINGS_CLEANUP_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.delete_old_rider_pings(retention_days).await {
+                Ok(0) => {}
+                Ok(count) => {
+                    info!("Deleted {count} rider pings older than {retention_days} days")
+                }
+                Err(e) => error!("Unable to delete old rider pings: {e}"),
+            }
+        }
+    });
+}
+
+/// Permanently deletes expired addresses and favorites from the trash once a day.
+pub fn spawn_trash_cleanup(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(TRASH_CLEANUP_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.empty_trash().await {
+                Ok(0) => {}
+                Ok(count) => info!("Deleted {count} addresses and favorites from the trash"),
+                Err(e) => error!("Unable to empty the trash: {e}"),
+            }
+        }
+    });
+}
+
+/// Deletes resumable uploads which weren't completed or used in time every hour.
+pub fn spawn_uploads_cleanup(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(UPLOADS_CLEANUP_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.delete_stale_uploads().await {
+                Ok(0) => {}
+                Ok(count) => info!("Deleted {count} stale uploads"),
+                Err(e) => error!("Unable to delete stale uploads: {e}"),
+            }
+        }
+    });
+}
+
+/// Dispatches scheduled orders and promotes queued orders every minute. Orders are
+/// also promoted right after other orders are completed or cancelled, so this only
+/// catches up missed slots.
+pub fn spawn_order_queue(db: Arc<db::Client>) {
+    spawn(async move {
+        let mut interval = time::interval(ORDER_QUEUE_INTERVAL);
+        loop {
+            if !next_tick(&mut interval).await {
+                break;
+            }
+            match db.dispatch_scheduled_orders().await {
+                Ok(0) => {}
+                Ok(count) => info!("Dispatched {count} scheduled orders"),
+                Err(e) => error!("Unable to dispatch scheduled orders: {e}"),
+            }
+            match db.promote_queued_orders().await {
+                Ok(0) => {}
+                Ok(count) => info!("Promoted {count} queued orders"),
+                Err(e) => error!("Unable to promote queued orders: {e}"),
+            }
+        }
+    });
+}
+
+/// Stops the jobs and waits up to `timeout` for them to finish the current runs.
+/// Returns `false` if some of them are still running.
+pub async fn drain(timeout: Duration) -> bool {
+    IS_STOPPING.store(true, Ordering::SeqCst);
+    STOP.notify_waiters();
+    let deadline = Instant::now() + timeout;
+    while ACTIVE.load(Ordering::SeqCst) != 0 {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        time::sleep(DRAIN_CHECK_INTERVAL).await;
+    }
+    true
+}
+
+/// Spawns the job, so [drain] waits for it.
+fn spawn(job: impl Future<Output = ()> + Send + 'static) {
+    ACTIVE.fetch_add(1, Ordering::SeqCst);
+    tokio::spawn(async move {
+        let _active = Active;
+        job.await;
+    });
+}
+
+/// Marks a job as active until it's dropped, even if the job panics.
+struct Active;
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Waits for the next tick of the interval. Returns `false` if the jobs are stopped.
+async fn next_tick(interval: &mut Interval) -> bool {
+    // Created before the check to not miss the notification.
+    let mut stop = pin!(STOP.notified());
+    if IS_STOPPING.load(Ordering::SeqCst) {
+        return false;
+    }
+    let mut tick = pin!(interval.tick());
+    poll_fn(|cx| {
+        if stop.as_mut().poll(cx).is_ready() {
+            Poll::Ready(false)
+        } else {
+            tick.as_mut().poll(cx).map(|_| true)
+        }
+    })
+    .await
+}
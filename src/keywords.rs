@@ -0,0 +1,103 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Extraction of frequent keywords from free-form texts such as feedback comments.
+
+use std::collections::HashMap;
+
+use crate::types::KeywordCount;
+
+/// Shorter words are skipped as they rarely carry meaning.
+const MIN_WORD_LENGTH: usize = 3;
+/// Negations aren't included, as "not fresh" means the opposite of "fresh".
+const STOP_WORDS: &[&str] = &[
+    "and", "are", "but", "for", "from", "had", "has", "have", "her", "his", "its", "our", "she",
+    "that", "the", "their", "them", "then", "there", "they", "this", "too", "very", "was", "were",
+    "with", "you", "your",
+];
+
+/// Counts words and pairs of adjacent words (like "cold food"). A keyword is counted
+/// once per text. Returns up to `limit` keywords, the most frequent first.
+pub fn top_keywords<'a>(
+    texts: impl IntoIterator<Item = &'a str>,
+    limit: usize,
+) -> Vec<KeywordCount> {
+    let mut counts = HashMap::<String, i64>::new();
+    for text in texts {
+        let words = tokenize(text);
+        let mut keywords: Vec<_> = words
+            .windows(2)
+            .map(|pair| pair.join(" "))
+            .chain(words.iter().cloned())
+            .collect();
+        keywords.sort_unstable();
+        keywords.dedup();
+        for keyword in keywords {
+            *counts.entry(keyword).or_default() += 1;
+        }
+    }
+
+    let mut keywords: Vec<_> = counts
+        .into_iter()
+        .map(|(keyword, count)| KeywordCount { keyword, count })
+        .collect();
+    keywords.sort_unstable_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.keyword.cmp(&b.keyword))
+    });
+    keywords.truncate(limit);
+    keywords
+}
+
+/// Splits the text into lowercase words without stop words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| {
+            word.chars().count() >= MIN_WORD_LENGTH && !STOP_WORDS.contains(&word.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_skips_short_and_stop_words() {
+        assert_eq!(
+            tokenize("The PIZZA was not hot, but OK!"),
+            ["pizza", "not", "hot"]
+        );
+    }
+
+    #[test]
+    fn top_keywords_counts_once_per_text() {
+        let keywords = top_keywords(
+            [
+                "Cold food, cold food!",
+                "Food was not fresh",
+                "Cold food again",
+            ],
+            4,
+        );
+        let keywords: Vec<_> = keywords
+            .iter()
+            .map(|keyword| (keyword.keyword.as_str(), keyword.count))
+            .collect();
+        assert_eq!(
+            keywords,
+            [("food", 3), ("cold", 2), ("cold food", 2), ("again", 1)]
+        );
+    }
+
+    #[test]
+    fn top_keywords_keeps_negations() {
+        let keywords = top_keywords(["Not fresh", "Fresh and tasty"], 10);
+        assert!(keywords
+            .iter()
+            .any(|keyword| keyword.keyword == "not fresh" && keyword.count == 1));
+    }
+}
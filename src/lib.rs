@@ -2,26 +2,142 @@
 // Contacts: <nikita.dudko.95@gmail.com>
 // Licensed under the MIT License.
 
+pub mod cli;
+pub mod config;
+pub mod datastore;
 pub mod db;
+pub mod error;
+pub mod jobs;
+pub mod keywords;
+pub mod loader;
 pub mod mutation;
+pub mod persisted;
 pub mod query;
+pub mod receipt;
+pub mod request_id;
 pub mod rest;
+pub mod scan;
+pub mod stats;
+pub mod tls;
 pub mod types;
 
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
-use actix_web::{dev::ServiceRequest, web::Data};
+use actix_web::{
+    dev::ServiceRequest, error::ErrorTooManyRequests, http::header, web::Data, HttpMessage,
+    HttpRequest,
+};
 use actix_web_httpauth::extractors::{
     basic::{BasicAuth, Config},
     AuthenticationError,
 };
-use async_graphql::{Context, EmptySubscription, Schema};
-use log::warn;
+use async_graphql::{
+    async_trait::async_trait, dataloader::DataLoader, Context, EmptySubscription, Guard, Schema,
+};
+use base64::Engine;
+use datastore::Datastore;
+use error::AppError;
+use loader::{
+    AddressLoader, CategoryLoader, FoodLoader, LocationStockLoader, PriceHistoryLoader, UserLoader,
+};
+use log::{error, warn};
 use mutation::MutationRoot;
 use query::QueryRoot;
+use rand::RngCore;
+use request_id::RequestIdExtension;
+use rest::{RequestQuotas, SchemaOptions};
+use scan::UploadScanner;
+use serde::Deserialize;
 use sha2::{Digest, Sha256};
+use stats::ExecutionStats;
+use types::{ActivityKind, User, UserRole};
 
-type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Client from which a request is sent.
+#[derive(Clone, Default)]
+pub struct Device {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl From<&HttpRequest> for Device {
+    /// Forwarded headers can be spoofed, so they are used only if the request is sent
+    /// from one of the [TrustedProxies], otherwise the address of the connection is used.
+    fn from(req: &HttpRequest) -> Self {
+        let peer_ip = req.peer_addr().map(|addr| addr.ip());
+        let is_proxy_trusted = peer_ip.is_some_and(|ip| {
+            req.app_data::<Data<TrustedProxies>>()
+                .is_some_and(|proxies| proxies.0.contains(&ip))
+        });
+        let ip_address = if is_proxy_trusted {
+            req.connection_info()
+                .realip_remote_addr()
+                .map(ToString::to_string)
+        } else {
+            peer_ip.map(|ip| ip.to_string())
+        };
+        Self {
+            ip_address,
+            user_agent: req
+                .headers()
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(ToString::to_string),
+        }
+    }
+}
+
+/// Reverse proxies which are trusted to set the `Forwarded` and `X-Forwarded-For` headers.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct TrustedProxies(pub Vec<IpAddr>);
+
+/// Builds the GraphQL schema which resolves fields using the datastore.
+pub fn build_schema(
+    datastore: Arc<dyn Datastore>,
+    options: SchemaOptions,
+    scanner: UploadScanner,
+    execution_stats: ExecutionStats,
+) -> AppSchema {
+    let mut builder = Schema::build(
+        QueryRoot::new(Arc::clone(&datastore)),
+        MutationRoot::new(Arc::clone(&datastore)),
+        EmptySubscription,
+    )
+    .data(DataLoader::new(
+        UserLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        AddressLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        CategoryLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        FoodLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        LocationStockLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        PriceHistoryLoader(Arc::clone(&datastore)),
+        tokio::spawn,
+    ))
+    .data(scanner)
+    .data(execution_stats.clone())
+    .extension(execution_stats)
+    .extension(RequestIdExtension);
+    if !options.introspection {
+        builder = builder.disable_introspection();
+    }
+    builder.finish()
+}
 
 pub async fn auth_validator(
     req: ServiceRequest,
@@ -29,12 +145,48 @@ pub async fn auth_validator(
 ) -> Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
     let user = auth.user_id();
     if let Some(db) = req.app_data::<Data<Arc<db::Client>>>() {
-        let result = db
-            .is_credentials_valid(user, auth.password().unwrap_or_default())
-            .await;
-        if result.unwrap_or(false) {
+        let device = Device::from(req.request());
+        let authenticated_user = match db.find_user_by_name(user).await {
+            Ok(found_user) => found_user.filter(|found_user| {
+                found_user.password == sha256(auth.password().unwrap_or_default())
+            }),
+            Err(e) => {
+                error!("Unable to get user \"{user}\": {e}");
+                None
+            }
+        };
+        if let Some(authenticated_user) = authenticated_user {
+            if let Err(e) = db.touch_user_session(user, &device).await {
+                error!("Unable to update session of user \"{user}\": {e}");
+            }
+            let quotas = req
+                .app_data::<Data<RequestQuotas>>()
+                .map(|quotas| *quotas.get_ref())
+                .unwrap_or_default();
+            match db.record_user_request(user).await {
+                Ok(requests) if quotas.is_user_exceeded(requests) => {
+                    warn!("User \"{user}\" exceeded the quota with {requests} requests per minute");
+                    return Err((
+                        ErrorTooManyRequests("too many requests, try again in a minute"),
+                        req,
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => error!("Unable to record request of user \"{user}\": {e}"),
+            }
+            if let Err(e) = db.add_user_login(user, &device).await {
+                error!("Unable to record login of user \"{user}\": {e}");
+            }
+            // Cached to not query the user in every resolver.
+            req.extensions_mut().insert(authenticated_user);
             return Ok(req);
         }
+        if let Err(e) = db
+            .add_user_activity(user, ActivityKind::FailedLogin, &device, None)
+            .await
+        {
+            error!("Unable to record failed login of user \"{user}\": {e}");
+        }
     }
 
     warn!("User \"{user}\" failed to authenticate");
@@ -42,9 +194,49 @@ pub async fn auth_validator(
     Err((AuthenticationError::from(config).into(), req))
 }
 
-pub fn auth_from_ctx<'a>(ctx: &Context<'a>) -> &'a BasicAuth {
-    ctx.data::<BasicAuth>()
-        .expect("BasicAuth object isn't passed for request")
+/// Returns the user on whose behalf the request is executed, loaded during authentication.
+/// During impersonation it's the impersonated user.
+pub fn auth_from_ctx<'a>(ctx: &Context<'a>) -> &'a User {
+    ctx.data::<User>()
+        .expect("User object isn't passed for request")
+}
+
+/// Hides manager-only fields from introspection of other users.
+pub fn is_manager(ctx: &Context<'_>) -> bool {
+    ctx.data_opt::<User>()
+        .is_some_and(|user| user.role == UserRole::Manager)
+}
+
+/// Allows access to a field only for users with the role.
+/// Combine using [async_graphql::GuardExt::or] to allow several roles.
+pub struct RoleGuard(UserRole);
+
+impl RoleGuard {
+    pub fn new(role: UserRole) -> Self {
+        Self(role)
+    }
+}
+
+#[async_trait]
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<User>() {
+            Some(user) if user.role == self.0 => Ok(()),
+            _ => Err(AppError::forbidden("access denied").into()),
+        }
+    }
+}
+
+pub fn device_from_ctx<'a>(ctx: &Context<'a>) -> &'a Device {
+    ctx.data::<Device>()
+        .expect("Device object isn't passed for request")
+}
+
+/// Generates a random URL-safe string that can be used as a secret.
+pub fn random_token() -> String {
+    let mut bytes = [0; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
 }
 
 pub fn sha256(data: &str) -> String {
@@ -52,3 +244,31 @@ pub fn sha256(data: &str) -> String {
     sha256.update(data);
     format!("{:x}", sha256.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn forwarded_address_is_used_only_from_trusted_proxies() {
+        let proxy = "10.0.0.1:40000".parse().unwrap();
+        let request = || {
+            TestRequest::default()
+                .peer_addr(proxy)
+                .insert_header(("x-forwarded-for", "203.0.113.7"))
+        };
+        let device = Device::from(&request().to_http_request());
+        assert_eq!(device.ip_address.as_deref(), Some("10.0.0.1"));
+
+        let trusted_proxies = TrustedProxies(vec![proxy.ip()]);
+        let req = request()
+            .app_data(Data::new(trusted_proxies))
+            .to_http_request();
+        assert_eq!(
+            Device::from(&req).ip_address.as_deref(),
+            Some("203.0.113.7")
+        );
+    }
+}
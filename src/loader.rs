@@ -0,0 +1,111 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Batch loading of entities referenced by other objects,
+//! so the same row isn't fetched many times within one response.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::{
+    async_trait::async_trait,
+    dataloader::{DataLoader, Loader},
+    Context,
+};
+
+use crate::{datastore::Datastore, db, error::AppError, types::*};
+
+pub struct UserLoader(pub Arc<dyn Datastore>);
+pub struct AddressLoader(pub Arc<dyn Datastore>);
+pub struct CategoryLoader(pub Arc<dyn Datastore>);
+pub struct FoodLoader(pub Arc<dyn Datastore>);
+/// Loads stock at all locations by food ID.
+pub struct LocationStockLoader(pub Arc<dyn Datastore>);
+/// Loads all price changes by food ID.
+pub struct PriceHistoryLoader(pub Arc<dyn Datastore>);
+
+#[async_trait]
+impl Loader<ID> for UserLoader {
+    type Value = User;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.users_by_ids(keys).await.map_err(Arc::new)
+    }
+}
+
+#[async_trait]
+impl Loader<ID> for AddressLoader {
+    type Value = Address;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.addresses_by_ids(keys).await.map_err(Arc::new)
+    }
+}
+
+#[async_trait]
+impl Loader<ID> for CategoryLoader {
+    type Value = Category;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.categories_by_ids(keys).await.map_err(Arc::new)
+    }
+}
+
+#[async_trait]
+impl Loader<ID> for FoodLoader {
+    type Value = Food;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.food_by_ids(keys).await.map_err(Arc::new)
+    }
+}
+
+#[async_trait]
+impl Loader<ID> for LocationStockLoader {
+    type Value = Vec<LocationStock>;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.location_stock(keys).await.map_err(Arc::new)
+    }
+}
+
+#[async_trait]
+impl Loader<ID> for PriceHistoryLoader {
+    type Value = Vec<PriceChange>;
+    type Error = Arc<db::Error>;
+
+    async fn load(&self, keys: &[ID]) -> Result<HashMap<ID, Self::Value>, Self::Error> {
+        self.0.price_history(keys).await.map_err(Arc::new)
+    }
+}
+
+/// Loads an entity using the loader registered on the schema.
+/// Returns an error if there is no entity with such ID.
+pub async fn load<T>(ctx: &Context<'_>, id: ID) -> Result<T::Value, AppError>
+where
+    T: Loader<ID, Error = Arc<db::Error>>,
+{
+    ctx.data_unchecked::<DataLoader<T>>()
+        .load_one(id)
+        .await
+        .map_err(|err| AppError::from(err.as_ref()))?
+        .ok_or_else(|| AppError::not_found(format!("there is no entity with ID {id}")))
+}
+
+/// Same as [load], but returns the default value if there is nothing for the ID.
+pub async fn load_or_default<T>(ctx: &Context<'_>, id: ID) -> Result<T::Value, AppError>
+where
+    T: Loader<ID, Error = Arc<db::Error>>,
+    T::Value: Default,
+{
+    ctx.data_unchecked::<DataLoader<T>>()
+        .load_one(id)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(|err| AppError::from(err.as_ref()))
+}
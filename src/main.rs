@@ -2,47 +2,295 @@
 // Contacts: <nikita.dudko.95@gmail.com>
 // Licensed under the MIT License.
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use actix_cors::Cors;
-use actix_web::{http::header, middleware::Logger, web::Data, App, HttpServer};
-use async_graphql::{EmptySubscription, Schema};
+use actix_web::{
+    dev::{Server, Service},
+    error::{Error, InternalError},
+    http::header::{self, HeaderName},
+    middleware::Logger,
+    rt::{
+        self,
+        signal::unix::{self, SignalKind},
+    },
+    web::{self, Data},
+    App, HttpMessage, HttpServer,
+};
+use anyhow::bail;
+use async_graphql::http::MultipartOptions;
 use env_logger::Env;
+use log::{error, info, warn};
 
-use gogo_delivery::{db, mutation::MutationRoot, query::QueryRoot, rest};
+use gogo_delivery::{
+    build_schema,
+    cli::{self, Cli, Command},
+    config::Config,
+    db, jobs,
+    persisted::PersistedQueries,
+    request_id::{self, RequestId, REQUEST_ID_HEADER},
+    rest::{
+        self, StatusCache, ADMIN_TOKEN_HEADER, IMPERSONATE_USER_HEADER, IMPERSONATE_WRITE_HEADER,
+        UPLOAD_LENGTH_HEADER, UPLOAD_OFFSET_HEADER,
+    },
+    scan::UploadScanner,
+    stats::ExecutionStats,
+    tls,
+};
 
-const SERVER_ADDRESS: (&str, u16) = ("0.0.0.0", 5000);
-const CORS_MAX_AGE_SECS: usize = 3600;
+/// Default format of [Logger] followed by the request ID.
+const ACCESS_LOG_FORMAT: &str =
+    r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#;
 
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
-    env_logger::init_from_env(Env::new().default_filter_or("INFO"));
+    env_logger::Builder::from_env(Env::new().default_filter_or("INFO"))
+        .format(request_id::format_log)
+        .init();
 
-    let db = Arc::new(db::Client::connect().await?);
-    let schema = Schema::build(
-        QueryRoot::new(Arc::clone(&db)),
-        MutationRoot::new(Arc::clone(&db)),
-        EmptySubscription,
-    )
-    .finish();
+    let Cli { command, config } = Cli::parse()?;
+    match command {
+        Command::Serve => serve(config).await,
+        command => cli::run(command, &config).await,
+    }
+}
+
+async fn serve(config: Config) -> anyhow::Result<()> {
+    let db = db::Client::connect(config.connection_string()?, &config.database).await?;
+    if config.database.migrate {
+        let versions = db.migrate(false).await?;
+        if !versions.is_empty() {
+            info!("Applied migrations {versions:?}");
+        }
+    }
+    let db = Arc::new(db);
+    let failures = db.check_statements().await;
+    if !failures.is_empty() {
+        failures.iter().for_each(|failure| error!("{failure}"));
+        bail!(
+            "database schema doesn't match {} of the embedded statements",
+            failures.len()
+        );
+    }
+    let schema_options = config.schema;
+    let execution_stats = ExecutionStats::default();
+    let schema = build_schema(
+        db.clone(),
+        schema_options,
+        UploadScanner::from_config(&config.scan),
+        execution_stats.clone(),
+    );
+    let limits = config.limits;
+    let admin_access = config.admin;
+    let quotas = config.quotas;
+    // Shared by the workers, so a query is registered once.
+    let persisted_queries = Data::new(PersistedQueries::new(&config.persisted_queries)?);
+    let status_cache = Data::new(StatusCache::default());
+    jobs::spawn_birthday_promos(Arc::clone(&db));
+    jobs::spawn_late_delivery_compensation(Arc::clone(&db));
+    jobs::spawn_sla_monitor(Arc::clone(&db));
+    jobs::spawn_notifications_cleanup(Arc::clone(&db), config.jobs.notification_retention_days);
+    jobs::spawn_rider_pings_cleanup(Arc::clone(&db), config.jobs.rider_ping_retention_days);
+    jobs::spawn_trash_cleanup(Arc::clone(&db));
+    jobs::spawn_uploads_cleanup(Arc::clone(&db));
+    jobs::spawn_order_queue(Arc::clone(&db));
+    if let Some(days) = config.jobs.order_archive_days {
+        jobs::spawn_order_archiver(Arc::clone(&db), days);
+    }
+    jobs::spawn_daily_settlement(Arc::clone(&db));
+
+    let metrics_server = config
+        .server
+        .metrics_address
+        .map(|address| {
+            let (db, execution_stats) = (Arc::clone(&db), execution_stats.clone());
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(Data::new(Arc::clone(&db)))
+                    .app_data(Data::new(execution_stats.clone()))
+                    .configure(rest::configure_metrics)
+            })
+            .workers(1)
+            .bind(address)
+        })
+        .transpose()?;
+
+    let tls = config.server.tls.load()?;
+    let redirect_server = config
+        .server
+        .tls
+        .redirect_port
+        .map(|redirect_port| {
+            let https_port = config.server.port;
+            HttpServer::new(move || {
+                App::new().configure(|config| tls::configure_redirect(config, https_port))
+            })
+            .workers(1)
+            .bind((config.server.address.as_str(), redirect_port))
+        })
+        .transpose()?;
 
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let cors_config = config.cors;
+    let trusted_proxies = Data::new(config.server.trusted_proxies);
+    let db_data = Data::new(Arc::clone(&db));
     let server = HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allowed_methods(vec!["POST"])
+        let cors = if cors_config.origins.is_empty() {
+            Cors::default().allow_any_origin()
+        } else {
+            cors_config
+                .origins
+                .iter()
+                .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        };
+        let cors = cors
+            .allowed_methods(vec!["GET", "POST", "HEAD", "PATCH"])
             .allowed_headers(vec![
                 header::ACCEPT,
                 header::AUTHORIZATION,
                 header::CONTENT_TYPE,
+                HeaderName::from_static(IMPERSONATE_USER_HEADER),
+                HeaderName::from_static(IMPERSONATE_WRITE_HEADER),
+                HeaderName::from_static(ADMIN_TOKEN_HEADER),
+                HeaderName::from_static(UPLOAD_OFFSET_HEADER),
+                HeaderName::from_static(REQUEST_ID_HEADER),
             ])
-            .max_age(CORS_MAX_AGE_SECS);
+            .expose_headers(vec![
+                HeaderName::from_static(REQUEST_ID_HEADER),
+                HeaderName::from_static(UPLOAD_OFFSET_HEADER),
+                HeaderName::from_static(UPLOAD_LENGTH_HEADER),
+            ])
+            .max_age(cors_config.max_age_secs);
 
+        let admin_access = admin_access.clone();
+        let request_stats = execution_stats.clone();
         App::new()
-            .wrap(Logger::default())
+            .wrap_fn(move |mut req, srv| {
+                let path = req.path().to_string();
+                let call = limits.check(&mut req).and_then(|body_limit| {
+                    admin_access.check(&req)?;
+                    Ok((body_limit, srv.call(req)))
+                });
+                async move {
+                    let (body_limit, call) = call?;
+                    let result = call.await;
+                    body_limit.check(&path, result)
+                }
+            })
+            .wrap_fn(|req, srv| {
+                let request_id = RequestId::from_headers(req.headers());
+                req.extensions_mut().insert(request_id.clone());
+                // Middleware and handlers may log something before the future is polled.
+                let call = request_id.clone().sync_scope(|| srv.call(req));
+                request_id.clone().scope(async move {
+                    let (name, value) = (
+                        HeaderName::from_static(REQUEST_ID_HEADER),
+                        request_id.to_header_value(),
+                    );
+                    match call.await {
+                        Ok(mut response) => {
+                            response.headers_mut().insert(name, value);
+                            Ok(response)
+                        }
+                        // Rejected requests get the header too.
+                        Err(e) => {
+                            let mut response = e.error_response();
+                            response.headers_mut().insert(name, value);
+                            Err(InternalError::from_response(e.to_string(), response).into())
+                        }
+                    }
+                })
+            })
+            .wrap(Logger::new(ACCESS_LOG_FORMAT))
+            // Outermost to also count the rejected requests.
+            .wrap_fn(move |req, srv| {
+                let start = Instant::now();
+                let method = req.method().to_string();
+                let path = req
+                    .match_pattern()
+                    .unwrap_or_else(|| "unmatched".to_string());
+                let call = srv.call(req);
+                let request_stats = request_stats.clone();
+                async move {
+                    let result = call.await;
+                    let status = match &result {
+                        Ok(response) => response.status(),
+                        Err(e) => Error::as_response_error(e).status_code(),
+                    };
+                    request_stats.record_request(&method, &path, status.as_u16(), start.elapsed());
+                    result
+                }
+            })
             .wrap(cors)
+            // Apply to requests which are sent without the content length.
+            .app_data(MultipartOptions::default().max_file_size(limits.upload))
+            .app_data(web::PayloadConfig::new(limits.rest))
+            .app_data(web::JsonConfig::default().limit(limits.rest))
             .app_data(Data::new(schema.clone()))
-            .app_data(Data::new(Arc::clone(&db)))
-            .configure(rest::configure_service)
+            .app_data(db_data.clone())
+            .app_data(Data::new(quotas))
+            .app_data(Data::new(limits))
+            .app_data(persisted_queries.clone())
+            .app_data(status_cache.clone())
+            .app_data(trusted_proxies.clone())
+            .app_data(Data::new(execution_stats.clone()))
+            .configure(|config| rest::configure_service(config, schema_options))
+    });
+    let server = server
+        .disable_signals()
+        .shutdown_timeout(shutdown_timeout.as_secs());
+    let address = (config.server.address.as_str(), config.server.port);
+    let server = match tls {
+        Some(tls) => server.bind_rustls_0_23(address, tls)?,
+        None => server.bind(address)?,
+    }
+    .run();
+    let metrics_server = metrics_server.map(|server| {
+        server
+            .disable_signals()
+            .shutdown_timeout(shutdown_timeout.as_secs())
+            .run()
     });
-    server.bind(SERVER_ADDRESS)?.run().await.map_err(Into::into)
+    let redirect_server = redirect_server.map(|server| {
+        server
+            .disable_signals()
+            .shutdown_timeout(shutdown_timeout.as_secs())
+            .run()
+    });
+    let auxiliary_servers: Vec<_> = [metrics_server, redirect_server]
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut handles = vec![server.handle()];
+    handles.extend(auxiliary_servers.iter().map(Server::handle));
+    // Unlike the default handling, SIGINT doesn't force the shutdown.
+    for kind in [SignalKind::interrupt(), SignalKind::terminate()] {
+        let mut signal = unix::signal(kind)?;
+        let handles = handles.clone();
+        rt::spawn(async move {
+            signal.recv().await;
+            info!("Stopping the server, waiting for in-flight requests");
+            for handle in handles {
+                handle.stop(true).await;
+            }
+        });
+    }
+    let auxiliary_servers: Vec<_> = auxiliary_servers.into_iter().map(rt::spawn).collect();
+    let result = server.await;
+
+    for server in auxiliary_servers {
+        let _ = server.await;
+    }
+    info!("Waiting for background jobs to finish");
+    if !jobs::drain(shutdown_timeout).await {
+        warn!("Background jobs didn't finish in time");
+    }
+    match Arc::try_unwrap(db) {
+        Ok(db) => db.close(),
+        Err(_) => warn!("Database connection is still in use, it will be dropped"),
+    }
+    result.map_err(Into::into)
 }
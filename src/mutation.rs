@@ -2,49 +2,171 @@
 // Contacts: <nikita.dudko.95@gmail.com>
 // Licensed under the MIT License.
 
-use std::{
-    io::{self, Read},
-    sync::Arc,
-};
+use std::{io::Read, sync::Arc};
+
+use async_graphql::{Context, Json, MaybeUndefined, Object, Upload};
+use log::{error, info};
+use rust_decimal::Decimal;
 
-use async_graphql::{Context, Object, Result, Upload};
-use log::info;
+use crate::{
+    auth_from_ctx,
+    datastore::Datastore,
+    db, device_from_ctx,
+    error::{AppError, Result},
+    is_manager, random_token,
+    scan::UploadScanner,
+    types::*,
+    RoleGuard,
+};
 
-use crate::{auth_from_ctx, db, types::*};
+const MIN_PASSWORD_LENGTH: usize = 8;
 
 pub struct MutationRoot {
-    db: Arc<db::Client>,
+    db: Arc<dyn Datastore>,
 }
 
 impl MutationRoot {
-    pub fn new(db: Arc<db::Client>) -> Self {
+    pub fn new(db: Arc<dyn Datastore>) -> Self {
         Self { db }
     }
+
+    /// Reads the preview from either the upload or the complete resumable upload.
+    async fn read_any_preview(
+        &self,
+        ctx: &Context<'_>,
+        preview: Option<Upload>,
+        preview_upload: Option<String>,
+    ) -> Result<Option<Vec<u8>>> {
+        match preview_upload {
+            Some(_) if preview.is_some() => Err(both_previews_error()),
+            Some(token) => self.take_preview_upload(ctx, &token).await.map(Some),
+            None => read_preview(ctx, preview).await,
+        }
+    }
+
+    async fn take_preview_upload(&self, ctx: &Context<'_>, token: &str) -> Result<Vec<u8>> {
+        let buf = self
+            .db
+            .take_upload(&auth_from_ctx(ctx).username, token)
+            .await
+            .map_err(|err| match err {
+                db::Error::NotFound(_) => {
+                    invalid_input("previewUpload", "must be a token of a complete upload")
+                }
+                err => err.into(),
+            })?;
+        scan_preview(ctx, "previewUpload", &buf).await?;
+        Ok(buf)
+    }
+
+    /// Checks that the old password is correct and the new one can replace it.
+    async fn check_password_change(
+        &self,
+        username: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        if !self.db.is_credentials_valid(username, old_password).await? {
+            return Err(AppError::Validation {
+                field: Some("oldPassword".to_string()),
+                message: "old password is incorrect".to_string(),
+            });
+        }
+        check_password_strength(username, new_password)?;
+        if new_password == old_password {
+            return Err(invalid_input("newPassword", "must differ from the old one"));
+        }
+        Ok(())
+    }
 }
 
+#[Object]
 impl MutationRoot {
-    async fn current_user(&self, ctx: &Context<'_>) -> Result<User> {
+    /// Returns `false` if there is nothing to update.
+    async fn update_profile(&self, ctx: &Context<'_>, input: UserPatch) -> Result<bool> {
+        input.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
-            .user_by_name(auth_from_ctx(ctx).user_id())
+            .update_user(username, &input)
             .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" updated profile");
+                }
+            })
             .map_err(Into::into)
     }
-}
 
-#[Object]
-impl MutationRoot {
+    async fn change_password(
+        &self,
+        ctx: &Context<'_>,
+        old_password: String,
+        new_password: String,
+    ) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.check_password_change(username, &old_password, &new_password)
+            .await?;
+
+        let result = self.db.set_user_password(username, &new_password).await?;
+        if result {
+            self.db
+                .add_user_activity(
+                    username,
+                    ActivityKind::PasswordChange,
+                    device_from_ctx(ctx),
+                    None,
+                )
+                .await?;
+            info!("User \"{username}\" changed password");
+        }
+        Ok(result)
+    }
+
+    /// Erases personal data of the current customer. Managers can specify `username`
+    /// of a customer to delete. Password of the current user must be confirmed.
+    /// Orders are kept for accounting, but addresses and other personal data are scrubbed.
+    async fn delete_account(
+        &self,
+        ctx: &Context<'_>,
+        password_confirmation: String,
+        username: Option<String>,
+    ) -> Result<bool> {
+        let current_username = auth_from_ctx(ctx).username.as_str();
+        if !self
+            .db
+            .is_credentials_valid(current_username, &password_confirmation)
+            .await?
+        {
+            return Err(invalid_input("passwordConfirmation", "is incorrect"));
+        }
+        let username = match username {
+            Some(username) if username != current_username => {
+                if auth_from_ctx(ctx).role != UserRole::Manager {
+                    return Err(AppError::forbidden("access denied"));
+                }
+                username
+            }
+            _ => current_username.to_string(),
+        };
+        if self.db.user_by_name(&username).await?.role != UserRole::Customer {
+            return Err(AppError::forbidden("only customer accounts can be deleted"));
+        }
+
+        self.db.erase_user(&username).await?;
+        info!("User \"{current_username}\" deleted account of \"{username}\"");
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
     async fn set_user_role(
         &self,
         ctx: &Context<'_>,
         username: String,
         role: UserRole,
     ) -> Result<bool> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
-        }
+        let current_user = auth_from_ctx(ctx);
         if current_user.username == username {
-            return Err("you cannot change role for yourself".into());
+            return Err(AppError::forbidden("you cannot change role for yourself"));
         }
         self.db
             .set_user_role(&username, role)
@@ -61,16 +183,15 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager).or(RoleGuard::new(UserRole::Rider))")]
     async fn send_direct_notification(
         &self,
         ctx: &Context<'_>,
         target_user_id: ID,
         notification: Notification,
     ) -> Result<ID> {
-        let current_user = self.current_user(ctx).await?;
-        if let UserRole::Customer = current_user.role {
-            return Err("access denied".into());
-        }
+        notification.validate()?;
+        let current_user = auth_from_ctx(ctx);
         self.db
             .add_user_notification(target_user_id, &notification)
             .await
@@ -84,18 +205,25 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Segment can be specified only if the target role is `CUSTOMER`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
     async fn broadcast_notification(
         &self,
         ctx: &Context<'_>,
         target_users_role: UserRole,
+        target_segment: Option<CustomerSegment>,
         notification: Notification,
     ) -> Result<Vec<ID>> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
+        notification.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        if target_segment.is_some() && target_users_role != UserRole::Customer {
+            return Err(invalid_input(
+                "targetSegment",
+                "can be specified only for customers",
+            ));
         }
         self.db
-            .add_notifications(target_users_role, notification)
+            .add_notifications(target_users_role, target_segment, notification)
             .await
             .map(|ids| {
                 info!(
@@ -107,44 +235,349 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Mutations of other users are rejected while the maintenance mode is enabled.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_maintenance(
+        &self,
+        ctx: &Context<'_>,
+        enabled: bool,
+        message: Option<String>,
+    ) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .set_maintenance(&Maintenance {
+                is_enabled: enabled,
+                message,
+            })
+            .await?;
+        info!(
+            "Manager \"{}\" {} the maintenance mode",
+            current_user.username,
+            if enabled { "enabled" } else { "disabled" }
+        );
+        Ok(true)
+    }
+
+    /// When the number of active orders reaches `capacity`, new orders are queued.
+    /// Set it to `null` to remove the limit.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_order_capacity(&self, ctx: &Context<'_>, capacity: Option<i32>) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        if capacity.is_some_and(|capacity| capacity <= 0) {
+            return Err(invalid_input("capacity", "must be positive"));
+        }
+        self.db.set_order_capacity(capacity).await?;
+        info!(
+            "Manager \"{}\" set order capacity to {}",
+            current_user.username,
+            capacity.map_or("unlimited".to_string(), |capacity| capacity.to_string())
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_birthday_promo_settings(
+        &self,
+        ctx: &Context<'_>,
+        settings: BirthdayPromoSettings,
+    ) -> Result<bool> {
+        settings.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_birthday_promo_settings(&settings).await?;
+        info!(
+            "Manager \"{}\" changed birthday promo settings",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    /// Moves orders, addresses, favorites and promo codes of the duplicate customer account
+    /// to the surviving one and deletes the duplicate.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn merge_users(
+        &self,
+        ctx: &Context<'_>,
+        source_id: ID,
+        target_id: ID,
+    ) -> Result<UserMerge> {
+        let current_user = auth_from_ctx(ctx);
+        let merge = self
+            .db
+            .merge_users(source_id, target_id, &current_user.username)
+            .await?;
+        info!(
+            "Manager \"{}\" merged user with ID {source_id} into user with ID {target_id}",
+            current_user.username
+        );
+        Ok(merge)
+    }
+
+    /// Announces the incident on the status page until it's resolved.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn add_incident(&self, ctx: &Context<'_>, incident: Incident) -> Result<ID> {
+        incident.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        let id = self.db.add_incident(&incident).await?;
+        info!(
+            "Manager \"{}\" added incident with ID {id}",
+            current_user.username
+        );
+        Ok(id)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn resolve_incident(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .resolve_incident(id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" resolved incident with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_order_scheduling(
+        &self,
+        ctx: &Context<'_>,
+        scheduling: OrderScheduling,
+    ) -> Result<bool> {
+        scheduling.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_order_scheduling(&scheduling).await?;
+        info!(
+            "Manager \"{}\" changed order scheduling",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_sla_policy(&self, ctx: &Context<'_>, policy: SlaPolicy) -> Result<bool> {
+        policy.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_sla_policy(&policy).await?;
+        info!("Manager \"{}\" changed SLA policy", current_user.username);
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_feedback_policy(&self, ctx: &Context<'_>, policy: FeedbackPolicy) -> Result<bool> {
+        policy.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_feedback_policy(&policy).await?;
+        info!(
+            "Manager \"{}\" changed feedback policy",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_late_delivery_policy(
+        &self,
+        ctx: &Context<'_>,
+        policy: LateDeliveryPolicy,
+    ) -> Result<bool> {
+        policy.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_late_delivery_policy(&policy).await?;
+        info!(
+            "Manager \"{}\" changed late delivery policy",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    /// Returns the generated key. It's not stored, so it can't be retrieved later.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn create_api_key(
+        &self,
+        ctx: &Context<'_>,
+        title: String,
+        scope: ApiKeyScope,
+    ) -> Result<String> {
+        let current_user = auth_from_ctx(ctx);
+        check_title("title", &title)?;
+        let key = random_token();
+        let id = self.db.add_api_key(&title, &key, scope).await?;
+        info!(
+            "Manager \"{}\" created API key \"{title}\" with ID {id}",
+            current_user.username
+        );
+        Ok(key)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn delete_api_key(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .delete_api_key(id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" deleted API key with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    async fn mark_notification_read(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        self.db
+            .read_user_notification(&auth_from_ctx(ctx).username, id)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn delete_user_notification(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .delete_user_notification(username, id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" deleted notification with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    async fn mark_all_read(&self, ctx: &Context<'_>) -> Result<bool> {
+        self.db
+            .read_user_notifications(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Revokes all sessions except the current one. Credentials are sent with each
+    /// request, so the password must be changed to lock out the other devices.
+    async fn revoke_all_sessions(
+        &self,
+        ctx: &Context<'_>,
+        old_password: String,
+        new_password: String,
+    ) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.check_password_change(username, &old_password, &new_password)
+            .await?;
+
+        let device = device_from_ctx(ctx);
+        let result = self
+            .db
+            .revoke_other_user_sessions(username, device, &new_password)
+            .await?;
+        if result {
+            self.db
+                .add_user_activity(username, ActivityKind::PasswordChange, device, None)
+                .await?;
+            info!("User \"{username}\" changed password and revoked all other sessions");
+        }
+        Ok(result)
+    }
+
     async fn add_user_address(&self, ctx: &Context<'_>, address: Address) -> Result<ID> {
-        let username = auth_from_ctx(ctx).user_id();
+        address.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
+        let id = self.db.add_user_address(username, address).await?;
+        self.db
+            .add_user_activity(
+                username,
+                ActivityKind::AddressAdded,
+                device_from_ctx(ctx),
+                None,
+            )
+            .await?;
+        info!("User \"{username}\" added new address with ID {id}");
+        Ok(id)
+    }
+
+    async fn update_user_address(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        address: Address,
+    ) -> Result<bool> {
+        address.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
-            .add_user_address(username, address)
+            .update_user_address(username, id, &address)
             .await
-            .map(|id| {
-                info!("User \"{username}\" added new address with ID {id}");
-                id
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" updated address with ID {id}");
+                }
             })
             .map_err(Into::into)
     }
 
-    async fn delete_user_address(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let username = auth_from_ctx(ctx).user_id();
+    async fn set_default_address(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
-            .delete_user_address(username, id)
+            .set_default_user_address(username, id)
             .await
-            .map(|result| {
+            .inspect(|&result| {
                 if result {
-                    info!("User \"{username}\" deleted address with ID {id}");
+                    info!("User \"{username}\" set address with ID {id} as default");
                 }
-                result
             })
             .map_err(Into::into)
     }
 
+    /// Moves the address to the trash. It can be restored within 30 days.
+    async fn delete_user_address(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        let result = self.db.delete_user_address(username, id).await?;
+        if result {
+            self.db
+                .add_user_activity(
+                    username,
+                    ActivityKind::AddressDeleted,
+                    device_from_ctx(ctx),
+                    None,
+                )
+                .await?;
+            info!("User \"{username}\" deleted address with ID {id}");
+        }
+        Ok(result)
+    }
+
+    /// Fails with the `CONFLICT` error code if a category with similar title exists.
+    /// Set `force` to add it anyway. `preview_upload` is a token of the complete resumable
+    /// upload (see `POST /uploads`) used instead of `preview`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
     async fn add_category(
         &self,
         ctx: &Context<'_>,
         category: Category,
         preview: Option<Upload>,
+        preview_upload: Option<String>,
+        #[graphql(default)] force: bool,
     ) -> Result<ID> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
+        category.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        if !force {
+            if let Some(id) = self.db.similar_category_id(&category.title).await? {
+                return Err(conflict_error(
+                    "category with similar title already exists",
+                    id,
+                ));
+            }
         }
         self.db
-            .add_category(&category, read_preview(ctx, preview)?)
+            .add_category(
+                &current_user.username,
+                &category,
+                self.read_any_preview(ctx, preview, preview_upload).await?,
+            )
             .await
             .map(|id| {
                 info!(
@@ -156,13 +589,47 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Omit `preview` to keep the current one or set it to `null` to remove it.
+    /// `preview_upload` can be specified instead of `preview` as for `addCategory`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn update_category(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        category: Category,
+        preview: MaybeUndefined<Upload>,
+        preview_upload: Option<String>,
+    ) -> Result<bool> {
+        category.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        let preview = match (preview, preview_upload) {
+            (MaybeUndefined::Undefined, None) => None,
+            (MaybeUndefined::Undefined, Some(token)) => {
+                Some(Some(self.take_preview_upload(ctx, &token).await?))
+            }
+            (_, Some(_)) => return Err(both_previews_error()),
+            (MaybeUndefined::Null, None) => Some(None),
+            (MaybeUndefined::Value(preview), None) => Some(read_preview(ctx, Some(preview)).await?),
+        };
+        self.db
+            .update_category(&current_user.username, id, &category, preview)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" updated category with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
     async fn delete_category(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
-        }
+        let current_user = auth_from_ctx(ctx);
         self.db
-            .delete_category(id)
+            .delete_category(&current_user.username, id)
             .await
             .map(|result| {
                 if result {
@@ -176,18 +643,38 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Fails with the `CONFLICT` error code if food with similar title exists in the category.
+    /// Set `force` to add it anyway. `preview_upload` can be specified instead of `preview`
+    /// as for `addCategory`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
     async fn add_food(
         &self,
         ctx: &Context<'_>,
         food: IndexedFood,
         preview: Option<Upload>,
+        preview_upload: Option<String>,
+        #[graphql(default)] force: bool,
     ) -> Result<ID> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
+        food.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        if !force {
+            if let Some(id) = self
+                .db
+                .similar_food_id(food.category_id, &food.title)
+                .await?
+            {
+                return Err(conflict_error(
+                    "food with similar title already exists in the category",
+                    id,
+                ));
+            }
         }
         self.db
-            .add_food(&food, read_preview(ctx, preview)?)
+            .add_food(
+                &current_user.username,
+                &food,
+                self.read_any_preview(ctx, preview, preview_upload).await?,
+            )
             .await
             .map(|id| {
                 info!(
@@ -199,13 +686,159 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
-    async fn delete_food(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Manager {
-            return Err("access denied".into());
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn update_food(&self, ctx: &Context<'_>, id: ID, patch: FoodPatch) -> Result<bool> {
+        patch.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .update_food(&current_user.username, id, &patch)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" updated food with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Returns the new count.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn restock_food(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        quantity: i32,
+        comment: Option<String>,
+    ) -> Result<i32> {
+        let current_user = auth_from_ctx(ctx);
+        if quantity <= 0 {
+            return Err(invalid_input("quantity", "must be positive"));
+        }
+        let count = self
+            .db
+            .restock_food(&current_user.username, id, quantity, comment.as_deref())
+            .await?
+            .ok_or_else(|| AppError::not_found("there is no food with such ID"))?;
+        info!(
+            "Manager \"{}\" restocked food with ID {id} by {quantity}",
+            current_user.username
+        );
+        Ok(count)
+    }
+
+    /// Changes the count by `delta`, which is negative if items were written off.
+    /// Returns the new count.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn adjust_food_stock(
+        &self,
+        ctx: &Context<'_>,
+        food_id: ID,
+        delta: i32,
+        reason: String,
+    ) -> Result<i32> {
+        let current_user = auth_from_ctx(ctx);
+        if delta == 0 {
+            return Err(invalid_input("delta", "must not be zero"));
+        }
+        check_title("reason", &reason)?;
+        let count = self
+            .db
+            .adjust_food_stock(&current_user.username, food_id, delta, &reason)
+            .await?
+            .ok_or_else(|| AppError::not_found("there is no food with such ID"))?;
+        info!(
+            "Manager \"{}\" adjusted stock of food with ID {food_id} by {delta}: {reason}",
+            current_user.username
+        );
+        Ok(count)
+    }
+
+    /// Restores the catalog from a document produced by `exportCatalog`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn import_catalog(
+        &self,
+        ctx: &Context<'_>,
+        document: Json<CatalogDocument>,
+    ) -> Result<CatalogImportSummary> {
+        let current_user = auth_from_ctx(ctx);
+        if let Some(food) = document
+            .categories
+            .iter()
+            .flat_map(|category| &category.food)
+            .find(|food| food.count < 0 || food.price.is_sign_negative())
+        {
+            return Err(AppError::invalid(format!(
+                "count and price of \"{}\" can't be negative",
+                food.title
+            )));
+        }
+        self.db
+            .import_catalog(&current_user.username, &document)
+            .await
+            .inspect(|summary| {
+                info!(
+                    "Manager \"{}\" imported the catalog: {} categories and {} food created, \
+                     {} categories and {} food updated",
+                    current_user.username,
+                    summary.created_categories,
+                    summary.created_food,
+                    summary.updated_categories,
+                    summary.updated_food
+                );
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn add_location(&self, ctx: &Context<'_>, location: Location) -> Result<ID> {
+        location.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .add_location(&location)
+            .await
+            .inspect(|_| {
+                info!(
+                    "Manager \"{}\" added new location \"{}\"",
+                    current_user.username, location.title
+                );
+            })
+            .map_err(Into::into)
+    }
+
+    /// Returns the new total count of the food.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_location_stock(
+        &self,
+        ctx: &Context<'_>,
+        location_id: ID,
+        food_id: ID,
+        count: i32,
+    ) -> Result<i32> {
+        let current_user = auth_from_ctx(ctx);
+        if count < 0 {
+            return Err(invalid_input("count", "can't be negative"));
         }
         self.db
-            .delete_food(id)
+            .set_location_stock(&current_user.username, location_id, food_id, count)
+            .await
+            .inspect(|_| {
+                info!(
+                    "Manager \"{}\" set stock of food with ID {food_id} \
+                     at location with ID {location_id} to {count}",
+                    current_user.username
+                );
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn delete_food(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .delete_food(&current_user.username, id)
             .await
             .map(|result| {
                 if result {
@@ -219,8 +852,25 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Restores the state of a food item or category which preceded the change.
+    /// Returns ID of the change which records reverting.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn revert_catalog_change(&self, ctx: &Context<'_>, id: ID) -> Result<ID> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .revert_catalog_change(&current_user.username, id)
+            .await
+            .inspect(|_| {
+                info!(
+                    "Manager \"{}\" reverted catalog change with ID {id}",
+                    current_user.username
+                );
+            })
+            .map_err(Into::into)
+    }
+
     async fn add_user_favorite(&self, ctx: &Context<'_>, favorite: IndexedFavorite) -> Result<ID> {
-        let username = auth_from_ctx(ctx).user_id();
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
             .add_user_favorite(username, &favorite)
             .await
@@ -234,8 +884,22 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    async fn restore_user_address(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .restore_user_address(username, id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" restored address with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Moves the favorite to the trash. It can be restored within 30 days.
     async fn delete_user_favorite(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let username = auth_from_ctx(ctx).user_id();
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
             .delete_user_favorite(username, id)
             .await
@@ -248,23 +912,176 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
-    async fn add_user_cart_item(&self, ctx: &Context<'_>, item: IndexedCartItem) -> Result<ID> {
-        let username = auth_from_ctx(ctx).user_id();
+    /// Set `collection_id` to `null` to remove the favorite from its collection.
+    async fn move_user_favorite(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        collection_id: Option<ID>,
+    ) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
-            .add_user_cart_item(username, &item)
+            .move_user_favorite(username, id, collection_id)
             .await
-            .map(|id| {
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" moved favorite with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    async fn add_user_favorite_collection(
+        &self,
+        ctx: &Context<'_>,
+        collection: FavoriteCollection,
+    ) -> Result<ID> {
+        collection.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .add_user_favorite_collection(username, &collection)
+            .await
+            .inspect(|_| {
                 info!(
-                    "User \"{username}\" added food with ID {} into the cart",
-                    item.food_id
+                    "User \"{username}\" added favorite collection \"{}\"",
+                    collection.title
                 );
+            })
+            .map_err(Into::into)
+    }
+
+    async fn update_user_favorite_collection(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        collection: FavoriteCollection,
+    ) -> Result<bool> {
+        collection.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .update_user_favorite_collection(username, id, &collection)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" updated favorite collection with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Favorites from the collection aren't deleted.
+    async fn delete_user_favorite_collection(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .delete_user_favorite_collection(username, id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" deleted favorite collection with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Same as `deleteUserFavorite`, but for several favorites at once.
+    /// Returns the number of deleted favorites.
+    async fn delete_user_favorites(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<i32> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        let count = self.db.delete_user_favorites(username, &ids).await?;
+        if count != 0 {
+            info!("User \"{username}\" deleted {count} favorites");
+        }
+        Ok(count as i32)
+    }
+
+    /// Adds one item of each favorite food into the cart, incrementing counts of the food
+    /// which is already there. Returns the number of added favorites.
+    async fn add_favorites_to_cart(&self, ctx: &Context<'_>, ids: Vec<ID>) -> Result<i32> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        let count = self.db.add_favorites_to_cart(username, &ids).await?;
+        if count != 0 {
+            info!("User \"{username}\" added {count} favorites to the cart");
+        }
+        Ok(count as i32)
+    }
+
+    async fn restore_user_favorite(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .restore_user_favorite(username, id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" restored favorite with ID {id}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Increments count of the existing item if the food is already in the cart.
+    async fn add_user_cart_item(&self, ctx: &Context<'_>, item: IndexedCartItem) -> Result<ID> {
+        item.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .add_user_cart_item(username, &item)
+            .await
+            .map(|(id, inserted)| {
+                if inserted {
+                    info!(
+                        "User \"{username}\" added food with ID {} into the cart",
+                        item.food_id
+                    );
+                } else {
+                    info!(
+                        "User \"{username}\" added {} more of food with ID {} into the cart",
+                        item.count, item.food_id
+                    );
+                }
                 id
             })
             .map_err(Into::into)
     }
 
+    /// Returns a one-time token for a gift order made without an address. The recipient
+    /// enters the address by sending it to `POST /gift_address/{token}` within 3 days,
+    /// the address isn't shown to the customer.
+    async fn gift_address_link(&self, ctx: &Context<'_>, order_id: ID) -> Result<String> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        let token = self.db.create_gift_address_link(username, order_id).await?;
+        info!("User \"{username}\" created a gift address link for order #{order_id}");
+        Ok(token)
+    }
+
+    /// Copies items of the delivered order into the cart.
+    async fn reorder(&self, ctx: &Context<'_>, order_id: ID) -> Result<Reorder> {
+        let username = auth_from_ctx(ctx).username.as_str();
+        let reorder = self.db.reorder(username, order_id).await?;
+        info!(
+            "User \"{username}\" added {} items of order #{order_id} into the cart",
+            reorder.added_count
+        );
+        Ok(reorder)
+    }
+
+    /// Count 0 removes the item from the cart.
+    async fn update_user_cart_item(&self, ctx: &Context<'_>, id: ID, count: i32) -> Result<bool> {
+        if count < 0 {
+            return Err(invalid_input("count", "can't be negative"));
+        }
+        let username = auth_from_ctx(ctx).username.as_str();
+        self.db
+            .update_user_cart_item(username, id, count)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!("User \"{username}\" set count of cart item with ID {id} to {count}");
+                }
+            })
+            .map_err(Into::into)
+    }
+
     async fn delete_user_cart_item(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let username = auth_from_ctx(ctx).user_id();
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
             .delete_user_cart_item(username, id)
             .await
@@ -281,10 +1098,12 @@ impl MutationRoot {
         &self,
         ctx: &Context<'_>,
         order: IndexedOrder,
+        promo_code: Option<String>,
     ) -> Result<ID> {
-        let username = auth_from_ctx(ctx).user_id();
+        order.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
-            .make_order_from_user_cart(username, order)
+            .make_order_from_user_cart(username, order, promo_code.as_deref())
             .await
             .map(|id| {
                 info!("User \"{username}\" made an order with ID {id}");
@@ -293,11 +1112,9 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    #[graphql(guard = "RoleGuard::new(UserRole::Rider)")]
     async fn take_order(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let current_user = self.current_user(ctx).await?;
-        if current_user.role != UserRole::Rider {
-            return Err("access denied".into());
-        }
+        let current_user = auth_from_ctx(ctx);
         self.db
             .take_order(&current_user.username, id)
             .await
@@ -313,8 +1130,29 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
+    /// Returns an accepted or picked up order to other riders, e.g. if the rider
+    /// can't deliver it. Riders can release only their own orders.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager).or(RoleGuard::new(UserRole::Rider))")]
+    async fn release_order(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        let rider_username =
+            (current_user.role == UserRole::Rider).then_some(current_user.username.as_str());
+        self.db
+            .release_order(id, rider_username, &current_user.username)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "User \"{}\" released order with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
     async fn complete_order(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let username = auth_from_ctx(ctx).user_id();
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
             .complete_order(username, id)
             .await
@@ -327,14 +1165,135 @@ impl MutationRoot {
             .map_err(Into::into)
     }
 
-    async fn delete_untaken_user_order(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
-        let username = auth_from_ctx(ctx).user_id();
+    /// Should be sent periodically while the rider is online.
+    #[graphql(guard = "RoleGuard::new(UserRole::Rider)")]
+    async fn report_rider_location(&self, ctx: &Context<'_>, location_id: ID) -> Result<bool> {
         self.db
-            .delete_untaken_user_order(username, id)
+            .add_rider_ping(&auth_from_ctx(ctx).username, location_id)
+            .await?;
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Rider)")]
+    async fn advance_order_status(&self, ctx: &Context<'_>, id: ID) -> Result<OrderStatus> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .advance_order_status(&current_user.username, id)
+            .await
+            .inspect(|status| {
+                info!(
+                    "Rider \"{}\" changed status of order with ID {id} to {status:?}",
+                    current_user.username
+                );
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn mark_order_ready_for_pickup(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .mark_order_ready_for_pickup(id)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" marked order with ID {id} as ready for pickup",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Returns `false` if the order isn't ready for pickup or the code doesn't match.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn hand_over_order(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        pickup_code: String,
+    ) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .hand_over_order(id, &pickup_code)
+            .await
+            .inspect(|&result| {
+                if result {
+                    info!(
+                        "Manager \"{}\" handed over order with ID {id}",
+                        current_user.username
+                    );
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// Customers are charged according to the cancellation policy. If the fee isn't zero,
+    /// the error with the `FEE_CONFIRMATION_REQUIRED` code and the fee in the extensions
+    /// is returned until the same fee is passed in `confirmed_fee`.
+    /// The reason is shown to the assigned rider.
+    async fn cancel_order(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        confirmed_fee: Option<Decimal>,
+        reason: Option<String>,
+    ) -> Result<bool> {
+        check_length("reason", reason.as_deref(), MAX_TEXT_LENGTH)?;
+        let current_user = auth_from_ctx(ctx);
+        let customer_username = match current_user.role {
+            UserRole::Customer => Some(current_user.username.as_str()),
+            UserRole::Manager => None,
+            UserRole::Rider => return Err(AppError::forbidden("access denied")),
+        };
+        if let Some(username) = customer_username {
+            let fee = self.db.cancellation_fee(id, username).await?;
+            if !fee.is_zero() && confirmed_fee != Some(fee) {
+                return Err(AppError::FeeConfirmationRequired(fee));
+            }
+        }
+        self.db
+            .cancel_order(id, customer_username, confirmed_fee, reason.as_deref())
+            .await
+            .map(|fee| {
+                info!(
+                    "User \"{}\" cancelled order with ID {id} (fee: {fee})",
+                    current_user.username
+                );
+                true
+            })
+            .map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn set_cancellation_policy(
+        &self,
+        ctx: &Context<'_>,
+        policy: CancellationPolicy,
+    ) -> Result<bool> {
+        policy.validate()?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.set_cancellation_policy(&policy).await?;
+        info!(
+            "Manager \"{}\" changed the cancellation policy",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn mark_order_item_unavailable(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .mark_order_item_unavailable(id)
             .await
             .map(|result| {
                 if result {
-                    info!("User \"{username}\" deleted untaken order with ID {id}");
+                    info!(
+                        "Manager \"{}\" marked order item with ID {id} as unavailable",
+                        current_user.username
+                    );
                 }
                 result
             })
@@ -342,7 +1301,8 @@ impl MutationRoot {
     }
 
     async fn add_user_feedback(&self, ctx: &Context<'_>, feedback: Feedback) -> Result<ID> {
-        let username = auth_from_ctx(ctx).user_id();
+        feedback.validate()?;
+        let username = auth_from_ctx(ctx).username.as_str();
         self.db
             .add_user_feedback(username, &feedback)
             .await
@@ -355,14 +1315,127 @@ impl MutationRoot {
             })
             .map_err(Into::into)
     }
+
+    /// Hidden feedbacks stay visible to their authors but aren't counted in ratings.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn hide_feedback(&self, ctx: &Context<'_>, id: ID, reason: String) -> Result<bool> {
+        check_length("reason", Some(&reason), MAX_TEXT_LENGTH)?;
+        let current_user = auth_from_ctx(ctx);
+        self.db.hide_feedback(id, &reason).await?;
+        info!(
+            "Manager \"{}\" hid feedback with ID {id}",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    /// Customers can change only their own feedbacks within the edit window
+    /// (see `feedbackPolicy`). Managers can change any feedback.
+    async fn update_user_feedback(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        rating: Option<i16>,
+        comment: Option<String>,
+    ) -> Result<bool> {
+        rating.map_or(Ok(()), |rating| check_range("rating", rating, 0..=5))?;
+        check_length("comment", comment.as_deref(), MAX_TEXT_LENGTH)?;
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .update_user_feedback(
+                id,
+                feedback_author(current_user)?,
+                rating,
+                comment.as_deref(),
+            )
+            .await?;
+        info!(
+            "User \"{}\" updated feedback with ID {id}",
+            current_user.username
+        );
+        Ok(true)
+    }
+
+    /// Same restrictions as for `updateUserFeedback` are applied.
+    async fn delete_user_feedback(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
+        let current_user = auth_from_ctx(ctx);
+        self.db
+            .delete_user_feedback(id, feedback_author(current_user)?)
+            .await?;
+        info!(
+            "User \"{}\" deleted feedback with ID {id}",
+            current_user.username
+        );
+        Ok(true)
+    }
+}
+
+/// Returns the username to check the feedback author against, or `None` for managers.
+fn feedback_author(user: &User) -> Result<Option<&str>> {
+    match user.role {
+        UserRole::Customer => Ok(Some(user.username.as_str())),
+        UserRole::Manager => Ok(None),
+        UserRole::Rider => Err(AppError::forbidden("access denied")),
+    }
 }
 
-fn read_preview(ctx: &Context<'_>, preview: Option<Upload>) -> io::Result<Option<Vec<u8>>> {
+async fn read_preview(ctx: &Context<'_>, preview: Option<Upload>) -> Result<Option<Vec<u8>>> {
     if preview.is_none() {
         return Ok(None);
     }
     let mut buf = Vec::new();
-    let mut file = preview.unwrap().value(ctx)?.content;
-    file.read_to_end(&mut buf)?;
+    let upload = preview
+        .unwrap()
+        .value(ctx)
+        .map_err(|err| invalid_input("preview", &err.to_string()))?;
+    let mut file = upload.content;
+    file.read_to_end(&mut buf).map_err(|e| {
+        error!("Unable to read upload \"{}\": {e}", upload.filename);
+        AppError::Internal
+    })?;
+    scan_preview(ctx, &upload.filename, &buf).await?;
     Ok(Some(buf))
 }
+
+async fn scan_preview(ctx: &Context<'_>, filename: &str, buf: &[u8]) -> Result<()> {
+    if let Some(scanner) = ctx.data_opt::<UploadScanner>() {
+        scanner
+            .check(filename, buf)
+            .await
+            .map_err(|message| invalid_input("preview", &message))?;
+    }
+    Ok(())
+}
+
+fn both_previews_error() -> AppError {
+    AppError::invalid("either preview or previewUpload can be specified")
+}
+
+fn conflict_error(message: &str, existing_id: ID) -> AppError {
+    AppError::Conflict {
+        message: message.to_string(),
+        existing_id: Some(existing_id),
+    }
+}
+
+fn check_password_strength(username: &str, password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(invalid_input(
+            "newPassword",
+            &format!("must contain at least {MIN_PASSWORD_LENGTH} characters"),
+        ));
+    }
+    if !password.chars().any(char::is_alphabetic) || !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err(invalid_input(
+            "newPassword",
+            "must contain both letters and digits",
+        ));
+    }
+    if password.to_lowercase().contains(&username.to_lowercase()) {
+        return Err(invalid_input(
+            "newPassword",
+            "must not contain the username",
+        ));
+    }
+    Ok(())
+}
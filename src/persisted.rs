@@ -0,0 +1,156 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Automatic persisted queries: once a query is registered, clients can send
+//! only its SHA-256 hash in the `persistedQuery` extension.
+//!
+//! If the allowlist directory is configured, only the documents stored in it
+//! can be executed and new queries aren't registered.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use async_graphql::{ErrorExtensionValues, Request, ServerError};
+use log::info;
+use lru::LruCache;
+use serde::Deserialize;
+
+use crate::sha256;
+
+const PERSISTED_QUERY_EXTENSION: &str = "persistedQuery";
+/// Message expected by the clients to send the full query.
+const NOT_FOUND_MESSAGE: &str = "PersistedQueryNotFound";
+/// Extension of the files with allowed operations.
+const OPERATION_FILE_EXTENSION: &str = "graphql";
+
+#[derive(Deserialize)]
+struct PersistedQuery {
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PersistedQueriesConfig {
+    /// Directory with the `*.graphql` files of the allowed operations.
+    pub allowlist_dir: Option<PathBuf>,
+    /// Maximum number of the registered queries.
+    pub cache_size: usize,
+}
+
+impl Default for PersistedQueriesConfig {
+    fn default() -> Self {
+        Self {
+            allowlist_dir: None,
+            cache_size: 1000,
+        }
+    }
+}
+
+pub struct PersistedQueries {
+    /// Registered queries by their hashes. Least recently used ones are evicted.
+    cache: Mutex<LruCache<String, String>>,
+    /// Allowed documents by their hashes, `None` if any query can be executed.
+    allowlist: Option<HashMap<String, String>>,
+}
+
+impl PersistedQueries {
+    /// Loads the allowed operations if the directory is configured.
+    pub fn new(config: &PersistedQueriesConfig) -> io::Result<Self> {
+        let allowlist = match &config.allowlist_dir {
+            Some(dir) => {
+                let allowlist = load_documents(dir)?;
+                info!(
+                    "Only {} operations from {} are allowed",
+                    allowlist.len(),
+                    dir.display()
+                );
+                Some(allowlist)
+            }
+            None => None,
+        };
+        Ok(Self {
+            cache: Mutex::new(LruCache::new(config.cache_size)),
+            allowlist,
+        })
+    }
+
+    /// Fills the query of the request by the hash or registers the sent query.
+    /// In the allowlist mode, rejects the request if the query isn't allowed.
+    pub fn resolve(&self, req: &mut Request) -> Result<(), ServerError> {
+        let hash = match req.extensions.get(PERSISTED_QUERY_EXTENSION) {
+            Some(extension) => Some(
+                async_graphql::from_value::<PersistedQuery>(extension.clone())
+                    .map_err(|_| ServerError::new("invalid persisted query extension", None))?
+                    .sha256_hash
+                    .to_lowercase(),
+            ),
+            None => None,
+        };
+        if let Some(hash) = &hash {
+            if !req.query.is_empty() && sha256(&req.query) != *hash {
+                return Err(ServerError::new(
+                    "provided sha256Hash doesn't match the query",
+                    None,
+                ));
+            }
+        }
+
+        if let Some(allowlist) = &self.allowlist {
+            let hash = hash.unwrap_or_else(|| sha256(&req.query));
+            return match allowlist.get(&hash) {
+                Some(query) => {
+                    req.query = query.clone();
+                    Ok(())
+                }
+                None => Err(error("operation isn't allowed", "OPERATION_NOT_ALLOWED")),
+            };
+        }
+        let Some(hash) = hash else {
+            return Ok(());
+        };
+        let mut cache = self.cache.lock().unwrap();
+        if req.query.is_empty() {
+            return match cache.get(&hash) {
+                Some(query) => {
+                    req.query = query.clone();
+                    Ok(())
+                }
+                None => Err(error(NOT_FOUND_MESSAGE, "PERSISTED_QUERY_NOT_FOUND")),
+            };
+        }
+        cache.put(hash, req.query.clone());
+        Ok(())
+    }
+}
+
+/// Returns documents of the directory by their hashes. Documents are matched
+/// exactly, so clients must send them without changes.
+fn load_documents(dir: &Path) -> io::Result<HashMap<String, String>> {
+    let mut documents = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == OPERATION_FILE_EXTENSION)
+        {
+            let document = fs::read_to_string(&path)?;
+            documents.insert(sha256(&document), document);
+        }
+    }
+    Ok(documents)
+}
+
+fn error(message: &str, code: &str) -> ServerError {
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+    ServerError {
+        extensions: Some(extensions),
+        ..ServerError::new(message, None)
+    }
+}
@@ -4,89 +4,474 @@
 
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result};
+use async_graphql::{connection::CursorType, Context, Json, Object};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
 
-use crate::{auth_from_ctx, db, types::*};
+use crate::{
+    auth_from_ctx,
+    datastore::Datastore,
+    device_from_ctx,
+    error::{AppError, Result},
+    is_manager,
+    stats::ExecutionStats,
+    types::*,
+    RoleGuard,
+};
 
 pub struct QueryRoot {
-    db: Arc<db::Client>,
+    db: Arc<dyn Datastore>,
 }
 
 impl QueryRoot {
-    pub fn new(db: Arc<db::Client>) -> Self {
+    pub fn new(db: Arc<dyn Datastore>) -> Self {
         Self { db }
     }
 }
 
+#[Object]
 impl QueryRoot {
-    async fn current_user_impl(&self, ctx: &Context<'_>) -> Result<User> {
+    async fn current_user(&self, ctx: &Context<'_>) -> User {
+        auth_from_ctx(ctx).clone()
+    }
+
+    /// Specify `role` to get only users with the role.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn users(
+        &self,
+        role: Option<UserRole>,
+        #[graphql(default_with = "SortUsersBy::Username")] sort_by: SortUsersBy,
+        #[graphql(default_with = "SortOrder::Ascending")] sort_order: SortOrder,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<User>> {
         self.db
-            .user_by_name(auth_from_ctx(ctx).user_id())
+            .users(role, sort_by, sort_order, pagination)
             .await
             .map_err(Into::into)
     }
-}
 
-#[Object]
-impl QueryRoot {
-    async fn current_user(&self, ctx: &Context<'_>) -> Result<User> {
-        self.current_user_impl(ctx).await
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn user(&self, id: ID) -> Result<Option<User>> {
+        self.db.user_by_id(id).await.map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn user_by_name(&self, username: String) -> Result<Option<User>> {
+        self.db
+            .find_user_by_name(&username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Full catalog as a single JSON document, which can be restored using `importCatalog`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn export_catalog(&self) -> Result<Json<CatalogDocument>> {
+        self.db.export_catalog().await.map(Json).map_err(Into::into)
+    }
+
+    /// Can be requested without authentication.
+    async fn maintenance(&self) -> Result<Maintenance> {
+        self.db.maintenance().await.map_err(Into::into)
+    }
+
+    /// Can be requested without authentication.
+    async fn locations(&self) -> Result<Vec<Location>> {
+        self.db.locations().await.map_err(Into::into)
+    }
+
+    /// Returns `None` if the number of active orders isn't limited.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn order_capacity(&self) -> Result<Option<i32>> {
+        self.db.order_capacity().await.map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn birthday_promo_settings(&self) -> Result<BirthdayPromoSettings> {
+        self.db.birthday_promo_settings().await.map_err(Into::into)
+    }
+
+    /// Groups of customer accounts which likely belong to the same person.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn duplicate_users(&self) -> Result<Vec<DuplicateUsers>> {
+        self.db.duplicate_users().await.map_err(Into::into)
+    }
+
+    /// Ongoing incidents shown on the status page.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn incidents(&self) -> Result<Vec<Incident>> {
+        self.db.incidents().await.map_err(Into::into)
+    }
+
+    /// Opening hours and rules of placing orders in advance.
+    async fn order_scheduling(&self) -> Result<OrderScheduling> {
+        self.db.order_scheduling().await.map_err(Into::into)
+    }
+
+    async fn feedback_policy(&self) -> Result<FeedbackPolicy> {
+        self.db.feedback_policy().await.map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn sla_policy(&self) -> Result<SlaPolicy> {
+        self.db.sla_policy().await.map_err(Into::into)
+    }
+
+    /// SLA attainment of delivery orders made during the last `days`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn sla_report(&self, #[graphql(default = 30)] days: i32) -> Result<SlaReport> {
+        if days <= 0 {
+            return Err(invalid_input("days", "must be positive"));
+        }
+        self.db.sla_report(days).await.map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn late_delivery_policy(&self) -> Result<LateDeliveryPolicy> {
+        self.db.late_delivery_policy().await.map_err(Into::into)
+    }
+
+    /// Compensation of late deliveries for the last `days`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn late_delivery_report(
+        &self,
+        #[graphql(default = 30)] days: i32,
+    ) -> Result<LateDeliveryReport> {
+        if days <= 0 {
+            return Err(invalid_input("days", "must be positive"));
+        }
+        self.db.late_delivery_report(days).await.map_err(Into::into)
+    }
+
+    /// Daily totals from `from` to `to` inclusive for reconciliation, the oldest first.
+    /// Days are settled after they end. Also exported as CSV by `GET /export/settlements`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn settlements(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Settlement>> {
+        if from > to {
+            return Err(invalid_input("from", "must not be after to"));
+        }
+        self.db.settlements(from, to).await.map_err(Into::into)
+    }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn api_keys(&self) -> Result<Vec<ApiKey>> {
+        self.db.api_keys().await.map_err(Into::into)
+    }
+
+    /// Usage statistics of all users, the most active first.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn api_usage(&self, #[graphql(default)] pagination: Pagination) -> Result<Vec<ApiUsage>> {
+        self.db.api_usage(pagination).await.map_err(Into::into)
+    }
+
+    async fn own_api_usage(&self, ctx: &Context<'_>) -> Result<ApiUsage> {
+        self.db
+            .user_api_usage(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Total size of the stored previews. Set the `PREVIEW_STORAGE_QUOTA` environment variable
+    /// (in bytes) to reject uploads exceeding it.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn storage_usage(&self) -> Result<StorageUsage> {
+        self.db.storage_usage().await.map_err(Into::into)
+    }
+
+    /// Filters changes by the entity if it's specified.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn catalog_history(
+        &self,
+        entity: Option<CatalogEntity>,
+        entity_id: Option<ID>,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<CatalogChange>> {
+        self.db
+            .catalog_history(entity, entity_id, pagination)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Lists food predicted to run out within `days`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn reorder_suggestions(
+        &self,
+        days: i32,
+        #[graphql(default = 30, desc = "Period of sales used to compute the rate.")]
+        lookback_days: i32,
+        #[graphql(default = 30, desc = "Number of days the restocked food should last.")]
+        cover_days: i32,
+    ) -> Result<Vec<ReorderSuggestion>> {
+        for (field, value) in [
+            ("days", days),
+            ("lookbackDays", lookback_days),
+            ("coverDays", cover_days),
+        ] {
+            if value <= 0 {
+                return Err(invalid_input(field, "must be positive"));
+            }
+        }
+        self.db
+            .reorder_suggestions(days, lookback_days, cover_days)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Average rating of the rider given by customers in feedbacks.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn rider_rating_summary(&self, username: String) -> Result<RiderRatingSummary> {
+        self.db
+            .rider_rating_summary(&username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Moderation queue, the newest feedbacks first.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn feedbacks(
+        &self,
+        #[graphql(default_with = "FeedbacksFilter::Visible")] filter: FeedbacksFilter,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<Feedback>> {
+        self.db
+            .feedbacks(filter, pagination)
+            .await
+            .map_err(Into::into)
     }
 
-    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
-        if self.current_user_impl(ctx).await?.role != UserRole::Manager {
-            return Err("access denied".into());
+    /// Rating distribution and frequent keywords of feedbacks on orders completed
+    /// during the last `days`. Specify `max_rating` to find keywords of complaints only.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn feedback_analytics(
+        &self,
+        #[graphql(default = 30)] days: i32,
+        max_rating: Option<i16>,
+        #[graphql(default = 20)] keyword_limit: i32,
+    ) -> Result<FeedbackAnalytics> {
+        if days <= 0 {
+            return Err(invalid_input("days", "must be positive"));
+        }
+        if keyword_limit <= 0 || i64::from(keyword_limit) > MAX_PAGE_SIZE {
+            return Err(invalid_input(
+                "keywordLimit",
+                &format!("must be between 1 and {MAX_PAGE_SIZE}"),
+            ));
         }
-        self.db.users().await.map_err(Into::into)
+        self.db
+            .feedback_analytics(days, max_rating, keyword_limit as usize)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Execution statistics of GraphQL operations, the most time-consuming first.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn operation_stats(&self, ctx: &Context<'_>) -> Vec<OperationStats> {
+        ctx.data_opt::<ExecutionStats>()
+            .map(ExecutionStats::operations)
+            .unwrap_or_default()
     }
 
-    async fn user_notifications(&self, ctx: &Context<'_>) -> Result<Vec<Notification>> {
+    /// Online riders and delivery orders per location and hour for the last `days`.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn rider_coverage(
+        &self,
+        #[graphql(default = 7)] days: i32,
+    ) -> Result<Vec<RiderCoverage>> {
+        if days <= 0 {
+            return Err(invalid_input("days", "must be positive"));
+        }
+        self.db.rider_coverage(days).await.map_err(Into::into)
+    }
+
+    /// Returns movements of the specified food or all food.
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager)", visible = "is_manager")]
+    async fn stock_history(
+        &self,
+        food_id: Option<ID>,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<StockMovement>> {
+        self.db
+            .stock_history(food_id, pagination)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn account_activity(&self, ctx: &Context<'_>) -> Result<Vec<Activity>> {
         self.db
-            .user_notifications(auth_from_ctx(ctx).user_id())
+            .user_activities(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn active_sessions(&self, ctx: &Context<'_>) -> Result<Vec<Session>> {
+        self.db
+            .user_sessions(&auth_from_ctx(ctx).username, device_from_ctx(ctx))
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn user_promo_codes(&self, ctx: &Context<'_>) -> Result<Vec<PromoCode>> {
+        self.db
+            .user_promo_codes(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn user_notifications(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] unread_only: bool,
+    ) -> Result<Vec<Notification>> {
+        self.db
+            .user_notifications(&auth_from_ctx(ctx).username, unread_only)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn unread_count(&self, ctx: &Context<'_>) -> Result<i64> {
+        self.db
+            .unread_user_notifications_count(&auth_from_ctx(ctx).username)
             .await
             .map_err(Into::into)
     }
 
     async fn user_addresses(&self, ctx: &Context<'_>) -> Result<Vec<Address>> {
         self.db
-            .user_addresses(auth_from_ctx(ctx).user_id())
+            .user_addresses(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deleted addresses which can be restored.
+    async fn trashed_user_addresses(&self, ctx: &Context<'_>) -> Result<Vec<Address>> {
+        self.db
+            .trashed_user_addresses(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[graphql(cache_control(max_age = 300))]
+    async fn categories(
+        &self,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<Category>> {
+        self.db
+            .categories(Some(pagination))
             .await
             .map_err(Into::into)
     }
 
-    async fn categories(&self) -> Result<Vec<Category>> {
-        self.db.categories().await.map_err(Into::into)
+    /// Returns the category with a page of its food.
+    #[graphql(cache_control(max_age = 60))]
+    async fn category(
+        &self,
+        id: ID,
+        #[graphql(default_with = "SortFoodBy::Title")] sort_by: SortFoodBy,
+        #[graphql(default_with = "SortOrder::Ascending")] sort_order: SortOrder,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Option<CategoryWithFood>> {
+        let Some(category) = self.db.category_by_id(id).await? else {
+            return Ok(None);
+        };
+        let food = self
+            .db
+            .food_in_category(id, FoodFilter::default(), sort_by, sort_order, pagination)
+            .await?;
+        Ok(Some(CategoryWithFood { category, food }))
+    }
+
+    #[graphql(cache_control(max_age = 60))]
+    async fn food(&self, id: ID) -> Result<Option<Food>> {
+        self.db.food_by_id(id).await.map_err(Into::into)
     }
 
+    #[graphql(cache_control(max_age = 60))]
+    #[allow(clippy::too_many_arguments)]
     async fn food_in_category(
         &self,
         category_id: ID,
         sort_by: SortFoodBy,
         sort_order: SortOrder,
+        #[graphql(default)] pagination: Pagination,
+        min_price: Option<Decimal>,
+        max_price: Option<Decimal>,
+        #[graphql(default)] exclude_alcohol: bool,
+        #[graphql(default, desc = "Skip food with zero count.")] in_stock_only: bool,
     ) -> Result<Vec<IndexedFood>> {
+        let filter = FoodFilter {
+            min_price,
+            max_price,
+            exclude_alcohol,
+            in_stock_only,
+        };
         self.db
-            .food_in_category(category_id, sort_by, sort_order)
+            .food_in_category(category_id, filter, sort_by, sort_order, pagination)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[graphql(cache_control(max_age = 60))]
+    async fn food_connection(
+        &self,
+        category_id: ID,
+        sort_by: SortFoodBy,
+        sort_order: SortOrder,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] first: i64,
+        after: Option<String>,
+    ) -> Result<Connection<IndexedFood>> {
+        self.db
+            .food_connection(
+                category_id,
+                sort_by,
+                sort_order,
+                first,
+                decode_cursor(after)?,
+            )
             .await
             .map_err(Into::into)
     }
 
     async fn is_user_favorite(&self, ctx: &Context<'_>, food_id: ID) -> Result<bool> {
         self.db
-            .is_user_favorite(auth_from_ctx(ctx).user_id(), food_id)
+            .is_user_favorite(&auth_from_ctx(ctx).username, food_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Specify `collection_id` to get only favorites from the collection.
+    async fn user_favorites(
+        &self,
+        ctx: &Context<'_>,
+        collection_id: Option<ID>,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<Favorite>> {
+        self.db
+            .user_favorites(&auth_from_ctx(ctx).username, collection_id, pagination)
             .await
             .map_err(Into::into)
     }
 
-    async fn user_favorites(&self, ctx: &Context<'_>) -> Result<Vec<Favorite>> {
+    /// The oldest first.
+    async fn user_favorite_collections(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<FavoriteCollection>> {
         self.db
-            .user_favorites(auth_from_ctx(ctx).user_id())
+            .user_favorite_collections(&auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deleted favorites which can be restored.
+    async fn trashed_user_favorites(&self, ctx: &Context<'_>) -> Result<Vec<Favorite>> {
+        self.db
+            .trashed_user_favorites(&auth_from_ctx(ctx).username)
             .await
             .map_err(Into::into)
     }
 
     async fn is_in_user_cart(&self, ctx: &Context<'_>, food_id: ID) -> Result<bool> {
         self.db
-            .is_in_user_cart(auth_from_ctx(ctx).user_id(), food_id)
+            .is_in_user_cart(&auth_from_ctx(ctx).username, food_id)
             .await
             .map_err(Into::into)
     }
@@ -98,22 +483,115 @@ impl QueryRoot {
         sort_order: SortOrder,
     ) -> Result<Cart> {
         self.db
-            .user_cart(auth_from_ctx(ctx).user_id(), sort_by, sort_order)
+            .user_cart(&auth_from_ctx(ctx).username, sort_by, sort_order)
             .await
             .map_err(Into::into)
     }
 
-    async fn orders(&self, ctx: &Context<'_>, filter: OrdersFilter) -> Result<Vec<Order>> {
-        if let UserRole::Customer = self.current_user_impl(ctx).await?.role {
-            return Err("access denied".into());
-        }
-        self.db.orders(filter).await.map_err(Into::into)
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager).or(RoleGuard::new(UserRole::Rider))")]
+    async fn orders(
+        &self,
+        filter: OrdersFilter,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<Order>> {
+        self.db.orders(filter, pagination).await.map_err(Into::into)
+    }
+
+    /// Delivery orders without a rider, oldest first by default.
+    /// Specify `location_id` to get only orders fulfilled from the location.
+    #[graphql(guard = "RoleGuard::new(UserRole::Rider)")]
+    async fn available_orders(
+        &self,
+        #[graphql(default_with = "SortOrder::Ascending")] sort_order: SortOrder,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] limit: i64,
+        location_id: Option<ID>,
+    ) -> Result<Vec<Order>> {
+        self.db
+            .available_orders(sort_order, limit, location_id)
+            .await
+            .map_err(Into::into)
     }
 
-    async fn user_orders(&self, ctx: &Context<'_>, filter: OrdersFilter) -> Result<Vec<Order>> {
+    /// Orders taken by the current rider which aren't delivered yet.
+    #[graphql(guard = "RoleGuard::new(UserRole::Rider)")]
+    async fn active_orders(&self, ctx: &Context<'_>) -> Result<Vec<Order>> {
         self.db
-            .user_orders(auth_from_ctx(ctx).user_id(), filter)
+            .rider_active_orders(&auth_from_ctx(ctx).username)
             .await
             .map_err(Into::into)
     }
+
+    #[graphql(guard = "RoleGuard::new(UserRole::Manager).or(RoleGuard::new(UserRole::Rider))")]
+    async fn orders_connection(
+        &self,
+        filter: OrdersFilter,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] first: i64,
+        after: Option<String>,
+    ) -> Result<Connection<Order>> {
+        self.db
+            .orders_connection(None, filter, first, decode_cursor(after)?)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn user_orders_connection(
+        &self,
+        ctx: &Context<'_>,
+        filter: OrdersFilter,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] first: i64,
+        after: Option<String>,
+    ) -> Result<Connection<Order>> {
+        self.db
+            .orders_connection(
+                Some(&auth_from_ctx(ctx).username),
+                filter,
+                first,
+                decode_cursor(after)?,
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn cancellation_policy(&self) -> Result<CancellationPolicy> {
+        self.db.cancellation_policy().await.map_err(Into::into)
+    }
+
+    /// Fee which will be charged for cancelling the order by the current customer.
+    /// Returns an error if the order can't be cancelled.
+    async fn cancellation_fee(&self, ctx: &Context<'_>, order_id: ID) -> Result<Decimal> {
+        self.db
+            .cancellation_fee(order_id, &auth_from_ctx(ctx).username)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns `None` if the order isn't queued.
+    async fn order_queue_position(
+        &self,
+        ctx: &Context<'_>,
+        order_id: ID,
+    ) -> Result<Option<QueuePosition>> {
+        self.db
+            .order_queue_position(&auth_from_ctx(ctx).username, order_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn user_orders(
+        &self,
+        ctx: &Context<'_>,
+        filter: OrdersFilter,
+        #[graphql(default)] pagination: Pagination,
+    ) -> Result<Vec<Order>> {
+        self.db
+            .user_orders(&auth_from_ctx(ctx).username, filter, pagination)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+fn decode_cursor(cursor: Option<String>) -> Result<Option<Cursor>> {
+    cursor
+        .map(|cursor| Cursor::decode_cursor(&cursor).map_err(AppError::invalid))
+        .transpose()
 }
@@ -0,0 +1,209 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Plain text receipts of orders, bundled into a ZIP archive for expense reporting,
+//! and delivery dockets.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    iter, mem,
+    rc::Rc,
+};
+
+use chrono::NaiveDateTime;
+use zip::{result::ZipResult, write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::types::{FulfillmentType, Order, OrderStatus};
+
+/// Makes a ZIP archive with a receipt file per order. It's yielded in chunks:
+/// a chunk per receipt and then the central directory.
+pub fn bundle(orders: Vec<Order>) -> impl Iterator<Item = ZipResult<Vec<u8>>> {
+    archive(orders.into_iter().map(|order| {
+        let indexed_order = &order.indexed_order;
+        let name = format!(
+            "{}-order-{}.txt",
+            indexed_order.create_time.format("%Y-%m-%d"),
+            indexed_order.id
+        );
+        (name, indexed_order.create_time, render(&order))
+    }))
+}
+
+pub fn render(order: &Order) -> String {
+    let indexed_order = &order.indexed_order;
+    let mut lines = vec![
+        format!("Order #{}", indexed_order.id),
+        format!(
+            "Date: {}",
+            indexed_order.create_time.format("%Y-%m-%d %H:%M")
+        ),
+        format!(
+            "Fulfillment: {}",
+            match indexed_order.fulfillment {
+                FulfillmentType::Delivery => "delivery",
+                FulfillmentType::Pickup => "pickup",
+            }
+        ),
+        String::new(),
+    ];
+
+    if let Some(gift) = &indexed_order.gift {
+        lines.push(format!("Gift for {}", gift.recipient_name));
+        if let Some(message) = &gift.message {
+            lines.push(format!("Message: {message}"));
+        }
+        lines.push(String::new());
+    }
+
+    if indexed_order.status == OrderStatus::Cancelled {
+        lines.push(format!(
+            "Cancelled, cancellation fee: {}",
+            indexed_order.cancellation_fee.unwrap_or_default()
+        ));
+    } else {
+        lines.extend(order.items.iter().map(|item| {
+            format!(
+                "{} x {}: {}{}",
+                item.food.indexed_food.title,
+                item.indexed_item.count,
+                item.total_price,
+                if item.indexed_item.is_unavailable {
+                    " (unavailable, not charged)"
+                } else {
+                    ""
+                }
+            )
+        }));
+        if indexed_order.discount_percent != 0 {
+            lines.push(format!("Discount: {}%", indexed_order.discount_percent));
+        }
+        lines.push(format!("Total: {}", order.total_price));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Delivery docket which is attached to the package.
+/// Prices are omitted for gifts, so the recipient doesn't see them.
+pub fn docket(order: &Order) -> String {
+    let indexed_order = &order.indexed_order;
+    let gift = indexed_order.gift.as_ref();
+    let mut lines = vec![format!("Order #{}", indexed_order.id)];
+    if let Some(gift) = gift {
+        lines.push(format!(
+            "Gift for {}, phone: {}",
+            gift.recipient_name, gift.recipient_phone
+        ));
+    }
+    lines.push(String::new());
+
+    let available_items = order
+        .items
+        .iter()
+        .filter(|item| !item.indexed_item.is_unavailable);
+    for item in available_items {
+        let title = &item.food.indexed_food.title;
+        let count = item.indexed_item.count;
+        lines.push(match gift {
+            Some(_) => format!("{title} x {count}"),
+            None => format!("{title} x {count}: {}", item.total_price),
+        });
+    }
+    if gift.is_none() {
+        lines.push(format!("Total: {}", order.total_price));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Writes the files without compression, which is enough for small text files.
+/// Only the last chunk is kept in memory instead of the whole archive.
+fn archive(
+    mut files: impl Iterator<Item = (String, NaiveDateTime, String)>,
+) -> impl Iterator<Item = ZipResult<Vec<u8>>> {
+    let chunk = Chunk::default();
+    let mut writer = Some(ZipWriter::new_stream(chunk.clone()));
+    iter::from_fn(move || {
+        let result = match files.next() {
+            Some((name, modified, content)) => {
+                let writer = writer.as_mut()?;
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Stored)
+                    // Dates before 1980 can't be represented.
+                    .last_modified_time(DateTime::try_from(modified).unwrap_or_default());
+                writer
+                    .start_file(name, options)
+                    .and_then(|()| Ok(writer.write_all(content.as_bytes())?))
+            }
+            None => writer.take()?.finish().map(|_| ()),
+        };
+        Some(result.map(|()| chunk.take()))
+    })
+}
+
+/// Bytes written since the last chunk was taken.
+#[derive(Clone, Default)]
+struct Chunk(Rc<RefCell<Vec<u8>>>);
+
+impl Chunk {
+    fn take(&self) -> Vec<u8> {
+        mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+impl Write for Chunk {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn archive_contains_files() {
+        let modified = "2024-03-15T12:30:10".parse().unwrap();
+        let files = [
+            ("first.txt".to_string(), modified, "Order #1\n".to_string()),
+            ("заказ.txt".to_string(), modified, String::new()),
+        ];
+        let chunks = archive(files.clone().into_iter())
+            .collect::<ZipResult<Vec<_>>>()
+            .unwrap();
+        // A chunk per file and the central directory.
+        assert_eq!(chunks.len(), 3);
+
+        let mut zip = ZipArchive::new(Cursor::new(chunks.concat())).unwrap();
+        assert_eq!(zip.len(), files.len());
+        for (name, _, content) in files {
+            let mut file = zip.by_name(&name).unwrap();
+            let mut read_content = String::new();
+            file.read_to_string(&mut read_content).unwrap();
+            assert_eq!(read_content, content);
+            assert_eq!(
+                file.last_modified(),
+                Some(DateTime::try_from(modified).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn empty_archive_is_valid() {
+        let chunks = archive(iter::empty())
+            .collect::<ZipResult<Vec<_>>>()
+            .unwrap();
+        let zip = ZipArchive::new(Cursor::new(chunks.concat())).unwrap();
+        assert_eq!(zip.len(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Identifiers of HTTP requests, so users can quote them in support tickets
+//! and the corresponding log lines can be found.
+
+use std::{fmt::Display, future::Future, io, sync::Arc};
+
+use actix_web::{
+    http::header::{HeaderMap, HeaderValue},
+    HttpMessage, HttpRequest,
+};
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextRequest},
+    Response, ServerError,
+};
+use async_trait::async_trait;
+use env_logger::fmt::Formatter;
+use log::Record;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Identifiers sent by clients are replaced if they are longer.
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// Added to the request extensions and the GraphQL context.
+#[derive(Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Honors the identifier sent by the client if it consists of up to 64 visible
+    /// ASCII characters, otherwise generates a new one.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LENGTH
+                    && id.chars().all(|char| char.is_ascii_graphic())
+            })
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(|| Self(Uuid::new_v4().to_string()))
+    }
+
+    /// Returns the identifier of the request which is being handled by the current task.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs the future, so log lines written within it contain the identifier.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Same as [Self::scope], but for a synchronous function.
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        CURRENT.sync_scope(self, f)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn to_header_value(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.0).expect("request ID contains only visible ASCII")
+    }
+
+    /// Adds the identifier to extensions of the errors.
+    pub fn tag_errors(&self, errors: &mut [ServerError]) {
+        for err in errors {
+            err.extensions
+                .get_or_insert_with(Default::default)
+                .set("requestId", self.as_str());
+        }
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&HttpRequest> for RequestId {
+    fn from(req: &HttpRequest) -> Self {
+        req.extensions()
+            .get::<Self>()
+            .cloned()
+            .unwrap_or_else(|| Self::from_headers(req.headers()))
+    }
+}
+
+/// Adds the identifier of the request being handled to extensions of the response errors.
+pub struct RequestIdExtension;
+
+impl ExtensionFactory for RequestIdExtension {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RequestIdExtension)
+    }
+}
+
+#[async_trait]
+impl Extension for RequestIdExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let mut response = next.run(ctx).await;
+        // Data of the request isn't available in the context at this stage.
+        if let Some(request_id) = RequestId::current() {
+            request_id.tag_errors(&mut response.errors);
+        }
+        response
+    }
+}
+
+/// Same as the default format of `env_logger`, but includes the request identifier
+/// if the record is written while handling a request.
+pub fn format_log(buf: &mut Formatter, record: &Record) -> io::Result<()> {
+    use std::io::Write;
+
+    let level = buf.default_styled_level(record.level());
+    match RequestId::current() {
+        Some(request_id) => writeln!(
+            buf,
+            "[{} {level} {} {request_id}] {}",
+            buf.timestamp(),
+            record.target(),
+            record.args()
+        ),
+        None => writeln!(
+            buf,
+            "[{} {level} {}] {}",
+            buf.timestamp(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
@@ -2,41 +2,580 @@
 // Contacts: <nikita.dudko.95@gmail.com>
 // Licensed under the MIT License.
 
-use std::sync::Arc;
+use std::{
+    cell::Cell,
+    net::IpAddr,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use actix_web::{
-    get,
+    dev::{Payload, ServiceRequest},
+    error::{ErrorForbidden, ErrorPayloadTooLarge, PayloadError},
+    get, head,
     http::header,
-    post,
-    web::{Data, Query, ServiceConfig},
-    HttpResponse,
+    middleware::Condition,
+    patch, post,
+    web::{self, Data, Query, ServiceConfig},
+    Either, HttpMessage, HttpRequest, HttpResponse,
 };
 use actix_web_httpauth::{extractors::basic::BasicAuth, middleware::HttpAuthentication};
-use async_graphql::http::GraphQLPlaygroundConfig;
+use async_graphql::{
+    http::GraphQLPlaygroundConfig,
+    parser::{
+        parse_query,
+        types::{OperationType, Selection},
+    },
+    ErrorExtensionValues, ServerError,
+};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
 use base64::Engine;
-use log::info;
+use chrono::NaiveDate;
+use futures_util::{stream, StreamExt};
+use log::{error, info, warn};
 use serde::Deserialize;
 
 use crate::{
     auth_validator,
     db::{self, PreviewOf},
+    error::AppError,
+    persisted::PersistedQueries,
+    receipt,
+    request_id::RequestId,
     sha256,
-    types::{User, ID},
-    AppSchema,
+    stats::ExecutionStats,
+    types::{
+        ActivityKind, Address, ApiKeyScope, ServiceStatus, Settlement, User, UserRole, Validate, ID,
+    },
+    AppSchema, Device,
 };
 
-pub fn configure_service(config: &mut ServiceConfig) {
+/// Offset of the chunk sent to a resumable upload or the number of received bytes.
+pub const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+/// Declared size of a resumable upload.
+pub const UPLOAD_LENGTH_HEADER: &str = "upload-length";
+
+/// Username of a customer to impersonate (managers only).
+pub const IMPERSONATE_USER_HEADER: &str = "x-impersonate-user";
+/// Set to "true" to allow mutations during impersonation.
+pub const IMPERSONATE_WRITE_HEADER: &str = "x-impersonate-write";
+/// Used by the integration endpoint instead of Basic authentication.
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Grants access to the administrative endpoints.
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Public root fields that can be queried without user authentication.
+const CATALOG_FIELDS: &[&str] = &[
+    "categories",
+    "category",
+    "food",
+    "foodInCategory",
+    "foodConnection",
+    "maintenance",
+    "locations",
+    "__typename",
+];
+
+/// Returned when a request is rejected due to the maintenance mode.
+const MAINTENANCE_MESSAGE: &str = "service temporarily unavailable";
+
+/// Path prefixes of the administrative endpoints (metrics, exports and so on).
+const ADMIN_PATHS: &[&str] = &["/metrics", "/export"];
+
+/// The status is computed at most once per this period regardless of the number of requests.
+const STATUS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Paths of the services which accept GraphQL requests.
+const GRAPHQL_PATHS: &[&str] = &["/", "/integration", "/catalog"];
+
+/// Maximum sizes of request bodies in bytes.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PayloadLimits {
+    /// GraphQL requests without uploads.
+    pub graphql: usize,
+    /// GraphQL multipart requests (with uploaded files).
+    pub upload: usize,
+    /// Other endpoints.
+    pub rest: usize,
+}
+
+impl Default for PayloadLimits {
+    fn default() -> Self {
+        Self {
+            graphql: 1024 * 1024,
+            upload: 10 * 1024 * 1024,
+            rest: 64 * 1024,
+        }
+    }
+}
+
+impl PayloadLimits {
+    /// Rejects the request with 413 if its declared length exceeds the corresponding limit.
+    /// A body without the declared length (chunked) is cut off once it exceeds the limit:
+    /// the returned [BodyLimit] must be checked after the request is handled.
+    pub fn check(&self, req: &mut ServiceRequest) -> actix_web::Result<BodyLimit> {
+        let (limit, variable) = self.limit_of(req);
+        let body_limit = BodyLimit {
+            limit,
+            variable,
+            is_exceeded: Rc::default(),
+        };
+        let content_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        let Some(content_length) = content_length else {
+            let (_, payload) = req.parts_mut();
+            let is_exceeded = Rc::clone(&body_limit.is_exceeded);
+            let mut received = 0;
+            let limited = payload.take().map(move |chunk| {
+                let chunk = chunk?;
+                received += chunk.len();
+                if received > limit {
+                    is_exceeded.set(true);
+                    return Err(PayloadError::Overflow);
+                }
+                Ok(chunk)
+            });
+            req.set_payload(Payload::Stream {
+                payload: Box::pin(limited),
+            });
+            return Ok(body_limit);
+        };
+
+        if content_length > limit {
+            warn!(
+                "Rejected request to {} with body of {content_length} bytes",
+                req.path()
+            );
+            return Err(ErrorPayloadTooLarge(format!(
+                "request body is {content_length} bytes, but the limit is {limit} bytes \
+                 (can be changed using {variable})"
+            )));
+        }
+        Ok(body_limit)
+    }
+
+    fn limit_of(&self, req: &ServiceRequest) -> (usize, &'static str) {
+        if !GRAPHQL_PATHS.contains(&req.path()) {
+            (self.rest, "MAX_REST_REQUEST_SIZE")
+        } else if req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"))
+        {
+            (self.upload, "MAX_UPLOAD_SIZE")
+        } else {
+            (self.graphql, "MAX_GRAPHQL_REQUEST_SIZE")
+        }
+    }
+}
+
+/// Returned by [PayloadLimits::check] to reject a request which body turned out
+/// to be too large while it was read.
+pub struct BodyLimit {
+    limit: usize,
+    variable: &'static str,
+    is_exceeded: Rc<Cell<bool>>,
+}
+
+impl BodyLimit {
+    /// Handlers fail in different ways when a body is cut off,
+    /// so the result is replaced with 413 in this case.
+    pub fn check<T>(&self, path: &str, result: actix_web::Result<T>) -> actix_web::Result<T> {
+        if !self.is_exceeded.get() {
+            return result;
+        }
+        warn!("Rejected request to {path} with chunked body exceeding the limit");
+        Err(ErrorPayloadTooLarge(format!(
+            "request body exceeds the limit of {} bytes (can be changed using {})",
+            self.limit, self.variable
+        )))
+    }
+}
+
+/// Exposure of the GraphQL schema.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaOptions {
+    /// Serve `GET /schema` without authentication instead of only to managers.
+    #[serde(rename = "public")]
+    pub is_public: bool,
+    /// Allow introspection queries. Should be disabled in production
+    /// if clients don't need it.
+    pub introspection: bool,
+}
+
+impl Default for SchemaOptions {
+    fn default() -> Self {
+        Self {
+            is_public: false,
+            introspection: true,
+        }
+    }
+}
+
+/// Maximum numbers of requests per minute, `None` means unlimited.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestQuotas {
+    /// Requests of an authenticated user.
+    pub user: Option<i32>,
+    /// Requests of an integration using an API key.
+    pub api_key: Option<i32>,
+}
+
+impl RequestQuotas {
+    pub fn is_user_exceeded(&self, requests: i32) -> bool {
+        self.user.is_some_and(|quota| requests > quota)
+    }
+
+    pub fn is_api_key_exceeded(&self, requests: i32) -> bool {
+        self.api_key.is_some_and(|quota| requests > quota)
+    }
+}
+
+/// Status returned by `GET /status` shared by the workers.
+#[derive(Default)]
+pub struct StatusCache(Mutex<Option<(Instant, ServiceStatus)>>);
+
+impl StatusCache {
+    fn get(&self) -> Option<ServiceStatus> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(time, _)| time.elapsed() < STATUS_CACHE_TTL)
+            .map(|(_, status)| status.clone())
+    }
+
+    fn set(&self, status: ServiceStatus) {
+        *self.0.lock().unwrap() = Some((Instant::now(), status));
+    }
+}
+
+pub fn configure_service(config: &mut ServiceConfig, schema_options: SchemaOptions) {
     config
         .service(request)
+        .service(integration_request)
+        .service(catalog_request)
         .service(playground)
         .service(preview)
+        .service(receipts)
+        .service(create_upload)
+        .service(upload_offset)
+        .service(append_upload)
+        .service(export_catalog)
+        .service(export_settlements)
+        .service(gift_address)
+        .service(service_status)
+        .service(metrics)
+        .service(
+            web::resource("/schema")
+                .wrap(Condition::new(
+                    !schema_options.is_public,
+                    HttpAuthentication::basic(auth_validator),
+                ))
+                .route(web::get().to(schema_sdl)),
+        )
         .service(sign_up);
 }
 
 #[post("/", wrap = "HttpAuthentication::basic(auth_validator)")]
-async fn request(schema: Data<AppSchema>, req: GraphQLRequest, auth: BasicAuth) -> GraphQLResponse {
-    schema.execute(req.into_inner().data(auth)).await.into()
+async fn request(
+    schema: Data<AppSchema>,
+    db: Data<Arc<db::Client>>,
+    persisted_queries: Data<PersistedQueries>,
+    http_req: HttpRequest,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let mut req = req.into_inner();
+    if let Err(err) = persisted_queries.resolve(&mut req) {
+        return rejected(&http_req, err);
+    }
+    let authenticated_user = http_req
+        .extensions()
+        .get::<User>()
+        .cloned()
+        .expect("user isn't cached during authentication");
+    let user = match impersonated_user(&db, &http_req, &req, &authenticated_user).await {
+        Ok(Some(user)) => user,
+        Ok(None) => authenticated_user.clone(),
+        Err(err) => return rejected(&http_req, err.into()),
+    };
+    if is_mutation(&req.query) {
+        if let Err(err) = check_maintenance(&db, &authenticated_user).await {
+            return rejected(&http_req, err);
+        }
+    }
+    schema
+        .execute(
+            req.data(user)
+                .data(Device::from(&http_req))
+                .data(RequestId::from(&http_req)),
+        )
+        .await
+        .into()
+}
+
+/// Response to a GraphQL request which is rejected before execution.
+fn rejected(http_req: &HttpRequest, err: ServerError) -> GraphQLResponse {
+    let mut errors = vec![err];
+    RequestId::from(http_req).tag_errors(&mut errors);
+    async_graphql::Response::from_errors(errors).into()
+}
+
+async fn impersonated_user(
+    db: &db::Client,
+    http_req: &HttpRequest,
+    req: &async_graphql::Request,
+    authenticated_user: &User,
+) -> Result<Option<User>, AppError> {
+    let target = match http_req.headers().get(IMPERSONATE_USER_HEADER) {
+        Some(value) => value
+            .to_str()
+            .map_err(|err| AppError::invalid(err.to_string()))?,
+        None => return Ok(None),
+    };
+    let manager = &authenticated_user.username;
+    if authenticated_user.role != UserRole::Manager {
+        warn!("User \"{manager}\" tried to impersonate user \"{target}\"");
+        return Err(AppError::forbidden("access denied"));
+    }
+    let target_user = match db.user_by_name(target).await {
+        Ok(user) if user.role == UserRole::Customer => user,
+        _ => {
+            return Err(AppError::not_found(
+                "only existing customers can be impersonated",
+            ))
+        }
+    };
+
+    let is_read_only = http_req
+        .headers()
+        .get(IMPERSONATE_WRITE_HEADER)
+        .map(|value| value != "true")
+        .unwrap_or(true);
+    if is_read_only && is_mutation(&req.query) {
+        return Err(AppError::forbidden("impersonation is read-only"));
+    }
+
+    let mode = if is_read_only {
+        "read-only"
+    } else {
+        "read-write"
+    };
+    let operation = req.operation_name.as_deref().unwrap_or("unnamed operation");
+    warn!("Manager \"{manager}\" impersonates customer \"{target}\" ({mode}): {operation}");
+
+    // The request isn't executed if it can't be audited.
+    let device = Device::from(http_req);
+    db.add_user_activity(
+        target,
+        ActivityKind::Impersonated,
+        &device,
+        Some(&format!("{mode} \"{operation}\" by manager \"{manager}\"")),
+    )
+    .await?;
+    db.add_user_activity(
+        manager,
+        ActivityKind::Impersonating,
+        &device,
+        Some(&format!("{mode} \"{operation}\" as customer \"{target}\"")),
+    )
+    .await?;
+    Ok(Some(target_user))
+}
+
+/// Access to the administrative endpoints, separate from the user authentication.
+/// If neither a token nor allowed IP addresses are set, the endpoints are inaccessible.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminAccess {
+    /// Expected in the `x-admin-token` header.
+    pub token: Option<String>,
+    pub allowed_ips: Vec<IpAddr>,
+}
+
+impl AdminAccess {
+    /// Rejects the request to an administrative endpoint with 403 unless it's sent from
+    /// an allowed IP address or contains the token. Other requests are passed.
+    pub fn check(&self, req: &ServiceRequest) -> actix_web::Result<()> {
+        if !ADMIN_PATHS
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix))
+        {
+            return Ok(());
+        }
+        // Address of the connection is used as forwarded headers can be spoofed.
+        let is_ip_allowed = req
+            .peer_addr()
+            .is_some_and(|addr| self.allowed_ips.contains(&addr.ip()));
+        let is_token_valid = self.token.as_ref().is_some_and(|token| {
+            req.headers()
+                .get(ADMIN_TOKEN_HEADER)
+                .is_some_and(|value| value.as_bytes() == token.as_bytes())
+        });
+        if is_ip_allowed || is_token_valid {
+            return Ok(());
+        }
+        warn!(
+            "Rejected request to administrative endpoint {} from {}",
+            req.path(),
+            req.peer_addr()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_default()
+        );
+        Err(ErrorForbidden("access denied"))
+    }
+}
+
+fn is_mutation(query: &str) -> bool {
+    parse_query(query)
+        .map(|document| {
+            document
+                .operations
+                .iter()
+                .any(|(_, operation)| operation.node.ty == OperationType::Mutation)
+        })
+        .unwrap_or(false)
+}
+
+/// Rejects changes made by non-managers while the maintenance mode is enabled.
+/// The error has the `SERVICE_UNAVAILABLE` code in the extensions.
+async fn check_maintenance(db: &db::Client, user: &User) -> Result<(), ServerError> {
+    let maintenance = match db.maintenance().await {
+        Ok(maintenance) => maintenance,
+        Err(e) => {
+            error!("Unable to get the maintenance mode: {e}");
+            return Ok(());
+        }
+    };
+    if !maintenance.is_enabled {
+        return Ok(());
+    }
+    if user.role == UserRole::Manager {
+        return Ok(());
+    }
+
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", "SERVICE_UNAVAILABLE");
+    Err(ServerError {
+        extensions: Some(extensions),
+        ..ServerError::new(
+            maintenance
+                .message
+                .unwrap_or_else(|| MAINTENANCE_MESSAGE.to_string()),
+            None,
+        )
+    })
+}
+
+/// GraphQL endpoint for server-to-server integrations authenticated by an API key.
+#[post("/integration")]
+async fn integration_request(
+    schema: Data<AppSchema>,
+    db: Data<Arc<db::Client>>,
+    quotas: Data<RequestQuotas>,
+    persisted_queries: Data<PersistedQueries>,
+    http_req: HttpRequest,
+    req: GraphQLRequest,
+) -> Either<GraphQLResponse, HttpResponse> {
+    let key = http_req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let usage = match key {
+        Some(key) => db.use_api_key(key).await.ok().flatten(),
+        None => None,
+    };
+    let scope = match usage {
+        Some((_, requests)) if quotas.is_api_key_exceeded(requests) => {
+            warn!("API key exceeded the quota with {requests} requests per minute");
+            return Either::Right(
+                HttpResponse::TooManyRequests().body("too many requests, try again in a minute"),
+            );
+        }
+        Some((scope, _)) => scope,
+        None => return Either::Right(HttpResponse::Unauthorized().body("invalid API key")),
+    };
+
+    let allowed_fields = match scope {
+        ApiKeyScope::CatalogRead => CATALOG_FIELDS,
+    };
+    let mut req = req.into_inner();
+    if let Err(err) = persisted_queries.resolve(&mut req) {
+        return Either::Left(rejected(&http_req, err));
+    }
+    if !is_query_allowed(allowed_fields, &req.query) {
+        return Either::Right(
+            HttpResponse::Forbidden().body("request isn't allowed for the API key scope"),
+        );
+    }
+    Either::Left(
+        schema
+            .execute(
+                req.data(Device::from(&http_req))
+                    .data(RequestId::from(&http_req)),
+            )
+            .await
+            .into(),
+    )
+}
+
+/// Unauthenticated endpoint for catalog queries sent using GET,
+/// so responses can be cached according to the Cache-Control header.
+#[get("/catalog")]
+async fn catalog_request(
+    schema: Data<AppSchema>,
+    persisted_queries: Data<PersistedQueries>,
+    http_req: HttpRequest,
+    req: GraphQLRequest,
+) -> Either<GraphQLResponse, HttpResponse> {
+    let mut req = req.into_inner();
+    if let Err(err) = persisted_queries.resolve(&mut req) {
+        return Either::Left(rejected(&http_req, err));
+    }
+    if !is_query_allowed(CATALOG_FIELDS, &req.query) {
+        return Either::Right(HttpResponse::Forbidden().body("only catalog queries are allowed"));
+    }
+    Either::Left(
+        schema
+            .execute(
+                req.data(Device::from(&http_req))
+                    .data(RequestId::from(&http_req)),
+            )
+            .await
+            .into(),
+    )
+}
+
+/// Checks that the query contains only read operations that select the allowed root fields.
+fn is_query_allowed(allowed_fields: &[&str], query: &str) -> bool {
+    let document = match parse_query(query) {
+        Ok(document) => document,
+        Err(_) => return false,
+    };
+    document.operations.iter().all(|(_, operation)| {
+        operation.node.ty == OperationType::Query
+            && operation
+                .node
+                .selection_set
+                .node
+                .items
+                .iter()
+                .all(|selection| match &selection.node {
+                    Selection::Field(field) => {
+                        allowed_fields.contains(&field.node.name.node.as_str())
+                    }
+                    // Fragments could contain arbitrary fields.
+                    _ => false,
+                })
+    })
 }
 
 #[get("/", wrap = "HttpAuthentication::basic(auth_validator)")]
@@ -63,26 +602,335 @@ struct PreviewQuery {
 async fn preview(query: Query<PreviewQuery>, db: Data<Arc<db::Client>>) -> HttpResponse {
     db.preview(query.of, query.id)
         .await
-        .map(|bytes| HttpResponse::Ok().content_type("image/jpeg").body(bytes))
+        .map(|bytes| match bytes {
+            Some(bytes) => HttpResponse::Ok().content_type("image/jpeg").body(bytes),
+            None => HttpResponse::NotFound().body("there is no preview"),
+        })
         .unwrap_or_else(|err| HttpResponse::BadRequest().body(err.to_string()))
 }
 
+/// Unauthenticated endpoint which a gift recipient uses to enter the address.
+/// The token is created by the customer for a gift order made without an address.
+#[post("/gift_address/{token}")]
+async fn gift_address(
+    token: web::Path<String>,
+    address: Query<Address>,
+    db: Data<Arc<db::Client>>,
+) -> HttpResponse {
+    if let Err(err) = address.validate() {
+        return HttpResponse::BadRequest().body(err.message());
+    }
+    match db.add_gift_address(&token, &address).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().body("link is invalid or expired"),
+        Err(e) => {
+            error!("Unable to add the gift address: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UploadQuery {
+    size: i32,
+}
+
+/// Starts a resumable upload of a preview for poor connections (managers only).
+/// Returns the token. Chunks are sent using `PATCH /uploads/{token}` and the token
+/// of the complete upload is passed as `previewUpload` to the catalog mutations.
+#[post("/uploads", wrap = "HttpAuthentication::basic(auth_validator)")]
+async fn create_upload(
+    query: Query<UploadQuery>,
+    db: Data<Arc<db::Client>>,
+    limits: Data<PayloadLimits>,
+    http_req: HttpRequest,
+) -> HttpResponse {
+    let Some(username) = manager_username(&http_req) else {
+        return HttpResponse::Forbidden().finish();
+    };
+    if query.size <= 0 || query.size as usize > limits.upload {
+        warn!(
+            "Manager \"{username}\" tried to start an upload of {} bytes, \
+             but MAX_UPLOAD_SIZE is {} bytes",
+            query.size, limits.upload
+        );
+        return HttpResponse::BadRequest()
+            .body(format!("size must be from 1 to {} bytes", limits.upload));
+    }
+    match db.create_upload(&username, query.size).await {
+        Ok(token) => HttpResponse::Created()
+            .insert_header((header::LOCATION, format!("/uploads/{token}")))
+            .body(token),
+        Err(e) => upload_error(e),
+    }
+}
+
+/// Returns the number of received bytes in the `Upload-Offset` header,
+/// so an interrupted upload can be resumed from it.
+#[head("/uploads/{token}", wrap = "HttpAuthentication::basic(auth_validator)")]
+async fn upload_offset(
+    token: web::Path<String>,
+    db: Data<Arc<db::Client>>,
+    http_req: HttpRequest,
+) -> HttpResponse {
+    let Some(username) = manager_username(&http_req) else {
+        return HttpResponse::Forbidden().finish();
+    };
+    match db.upload_offset(&username, &token).await {
+        Ok(Some((offset, size))) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .insert_header((UPLOAD_OFFSET_HEADER, offset.to_string()))
+            .insert_header((UPLOAD_LENGTH_HEADER, size.to_string()))
+            .finish(),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => upload_error(e),
+    }
+}
+
+/// Appends the body to the upload. The `Upload-Offset` header must be equal to the number
+/// of received bytes, otherwise 409 is returned. Chunk size is limited by
+/// `MAX_REST_REQUEST_SIZE`.
+#[patch("/uploads/{token}", wrap = "HttpAuthentication::basic(auth_validator)")]
+async fn append_upload(
+    token: web::Path<String>,
+    chunk: web::Bytes,
+    db: Data<Arc<db::Client>>,
+    http_req: HttpRequest,
+) -> HttpResponse {
+    let Some(username) = manager_username(&http_req) else {
+        return HttpResponse::Forbidden().finish();
+    };
+    let offset = http_req
+        .headers()
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i32>().ok());
+    let Some(offset) = offset else {
+        return HttpResponse::BadRequest().body("Upload-Offset header is required");
+    };
+    match db
+        .append_upload_chunk(&username, &token, offset, &chunk)
+        .await
+    {
+        Ok(offset) => HttpResponse::NoContent()
+            .insert_header((UPLOAD_OFFSET_HEADER, offset.to_string()))
+            .finish(),
+        Err(e) => upload_error(e),
+    }
+}
+
+fn manager_username(http_req: &HttpRequest) -> Option<String> {
+    http_req
+        .extensions()
+        .get::<User>()
+        .filter(|user| user.role == UserRole::Manager)
+        .map(|user| user.username.clone())
+}
+
+fn upload_error(err: db::Error) -> HttpResponse {
+    match err {
+        db::Error::NotFound(message) => HttpResponse::NotFound().body(message),
+        db::Error::Conflict(message) => HttpResponse::Conflict().body(message),
+        db::Error::Invalid(message) => HttpResponse::BadRequest().body(message),
+        db::Error::UnknownUser(_) => HttpResponse::Unauthorized().finish(),
+        err => {
+            error!("Unable to process upload: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ReceiptsQuery {
+    year: i32,
+}
+
+/// ZIP archive with receipts of the user's orders for the year.
+#[get("/receipts", wrap = "HttpAuthentication::basic(auth_validator)")]
+async fn receipts(
+    query: Query<ReceiptsQuery>,
+    db: Data<Arc<db::Client>>,
+    http_req: HttpRequest,
+) -> HttpResponse {
+    let username = http_req
+        .extensions()
+        .get::<User>()
+        .map(|user| user.username.clone())
+        .expect("user isn't cached during authentication");
+    match db.user_receipt_orders(&username, query.year).await {
+        Ok(orders) => HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"receipts-{}.zip\"", query.year),
+            ))
+            .streaming(stream::iter(
+                receipt::bundle(orders).map(|chunk| chunk.map(web::Bytes::from)),
+            )),
+        Err(db::Error::UnknownUser(_)) => HttpResponse::Unauthorized().finish(),
+        Err(e) => {
+            error!("Unable to get receipts of user \"{username}\": {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// GraphQL schema in the SDL format. A user is authenticated only if the schema isn't public.
+async fn schema_sdl(schema: Data<AppSchema>, http_req: HttpRequest) -> HttpResponse {
+    if let Some(user) = http_req.extensions().get::<User>() {
+        if user.role != UserRole::Manager {
+            warn!("User \"{}\" tried to get the schema", user.username);
+            return HttpResponse::Forbidden().finish();
+        }
+    }
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=UTF-8")
+        .body(schema.sdl())
+}
+
+/// Unauthenticated service health for embedding into other sites.
+/// Responses are cached, so the database is queried at most once per [STATUS_CACHE_TTL].
+#[get("/status")]
+async fn service_status(db: Data<Arc<db::Client>>, cache: Data<StatusCache>) -> HttpResponse {
+    let status = match cache.get() {
+        Some(status) => status,
+        None => match db.service_status().await {
+            Ok(status) => {
+                cache.set(status.clone());
+                status
+            }
+            Err(e) => {
+                error!("Unable to get the service status: {e}");
+                return HttpResponse::InternalServerError().finish();
+            }
+        },
+    };
+    HttpResponse::Ok()
+        .insert_header((
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", STATUS_CACHE_TTL.as_secs()),
+        ))
+        .json(status)
+}
+
+/// Registers only the metrics endpoint, e.g. to serve it on a separate address.
+pub fn configure_metrics(config: &mut ServiceConfig) {
+    config.service(metrics);
+}
+
+/// Metrics in the Prometheus text format. Protected by [AdminAccess].
+#[get("/metrics")]
+async fn metrics(stats: Data<ExecutionStats>, db: Data<Arc<db::Client>>) -> HttpResponse {
+    let mut output = stats.to_prometheus();
+    output.push_str("# TYPE db_connection_up gauge\n");
+    output.push_str(&format!(
+        "db_connection_up {}\n",
+        u8::from(db.is_connected().await)
+    ));
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(output)
+}
+
+/// Protected by [AdminAccess] instead of user authentication.
+#[get("/export/catalog")]
+async fn export_catalog(db: Data<Arc<db::Client>>) -> HttpResponse {
+    match db.export_catalog().await {
+        Ok(document) => HttpResponse::Ok()
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"catalog.json\"",
+            ))
+            .json(document),
+        Err(e) => {
+            error!("Unable to export the catalog: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SettlementsQuery {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+/// Settlements of the days from `from` to `to` inclusive in the CSV format.
+/// Protected by [AdminAccess] instead of user authentication.
+#[get("/export/settlements")]
+async fn export_settlements(
+    query: Query<SettlementsQuery>,
+    db: Data<Arc<db::Client>>,
+) -> HttpResponse {
+    match db.settlements(query.from, query.to).await {
+        Ok(settlements) => {
+            let mut csv = Settlement::CSV_HEADER.to_string();
+            for settlement in &settlements {
+                csv.push('\n');
+                csv.push_str(&settlement.to_csv_row());
+            }
+            csv.push('\n');
+            HttpResponse::Ok()
+                .content_type("text/csv; charset=UTF-8")
+                .insert_header((
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"settlements-{}-{}.csv\"",
+                        query.from, query.to
+                    ),
+                ))
+                .body(csv)
+        }
+        Err(e) => {
+            error!("Unable to export settlements: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 #[post("/sign_up")]
 async fn sign_up(
     mut user: Query<User>,
     auth: BasicAuth,
     db: Data<Arc<db::Client>>,
+    http_req: HttpRequest,
 ) -> HttpResponse {
+    match db.maintenance().await {
+        Ok(maintenance) if maintenance.is_enabled => {
+            return HttpResponse::ServiceUnavailable().body(
+                maintenance
+                    .message
+                    .unwrap_or_else(|| MAINTENANCE_MESSAGE.to_string()),
+            )
+        }
+        Ok(_) => {}
+        Err(e) => error!("Unable to get the maintenance mode: {e}"),
+    }
+
     let username = auth.user_id();
     user.username = username.to_string();
     if let Some(password) = auth.password() {
         user.password = sha256(password);
     }
-    db.add_user(user.into_inner())
+    if let Err(err) = user.validate() {
+        return HttpResponse::BadRequest().body(err.message());
+    }
+    let id = match db.add_user(user.into_inner()).await {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    info!("New customer \"{username}\" signed up");
+    if let Err(e) = db
+        .add_user_activity(
+            username,
+            ActivityKind::SignUp,
+            &Device::from(&http_req),
+            None,
+        )
         .await
-        .map(|id| {
-            info!("New customer \"{username}\" signed up");
-            HttpResponse::Ok().body(id.to_string())
-        })
-        .unwrap_or_else(|err| HttpResponse::BadRequest().body(err.to_string()))
+    {
+        error!("Unable to record sign up of user \"{username}\": {e}");
+    }
+    HttpResponse::Ok().body(id.to_string())
 }
@@ -0,0 +1,146 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Scanning of uploaded files for malware before they are stored.
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{error, warn};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// Size of chunks in which data is streamed to clamd.
+const CLAMD_CHUNK_SIZE: usize = 64 * 1024;
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub enum Verdict {
+    Clean,
+    /// Contains the name of the detected threat.
+    Infected(String),
+}
+
+#[async_trait]
+pub trait Scanner: Send + Sync {
+    async fn scan(&self, data: &[u8]) -> anyhow::Result<Verdict>;
+}
+
+/// ClamAV daemon which is accessed over TCP using the `INSTREAM` command.
+pub struct Clamd {
+    address: String,
+}
+
+impl Clamd {
+    pub fn new(address: String) -> Self {
+        Self { address }
+    }
+}
+
+#[async_trait]
+impl Scanner for Clamd {
+    async fn scan(&self, data: &[u8]) -> anyhow::Result<Verdict> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in data.chunks(CLAMD_CHUNK_SIZE) {
+            stream
+                .write_all(&(chunk.len() as u32).to_be_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+        }
+        stream.write_all(&0_u32.to_be_bytes()).await?;
+
+        // Response is terminated by the null character due to the "z" prefix of the command.
+        let mut response = Vec::new();
+        BufReader::new(stream)
+            .read_until(b'\0', &mut response)
+            .await?;
+        // Response looks like "stream: OK" or "stream: <threat> FOUND".
+        let response = String::from_utf8_lossy(&response);
+        let response = response.trim_end_matches(['\0', '\n']);
+        let result = response.strip_prefix("stream: ").unwrap_or(response);
+        if result == "OK" {
+            Ok(Verdict::Clean)
+        } else if let Some(threat) = result.strip_suffix(" FOUND") {
+            Ok(Verdict::Infected(threat.to_string()))
+        } else {
+            Err(anyhow!("unexpected response from clamd: {response}"))
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Address of clamd, scanning is disabled if it's not set.
+    pub clamd_address: Option<String>,
+    /// Flagged files are put there.
+    pub quarantine_dir: Option<PathBuf>,
+}
+
+/// Checks uploads using the configured scanner (if any).
+#[derive(Default)]
+pub struct UploadScanner {
+    scanner: Option<Box<dyn Scanner>>,
+    /// If specified, flagged files are saved here for review instead of just being rejected.
+    quarantine_dir: Option<PathBuf>,
+}
+
+impl UploadScanner {
+    pub fn new(scanner: Option<Box<dyn Scanner>>, quarantine_dir: Option<PathBuf>) -> Self {
+        Self {
+            scanner,
+            quarantine_dir,
+        }
+    }
+
+    pub fn from_config(config: &ScanConfig) -> Self {
+        Self::new(
+            config
+                .clamd_address
+                .clone()
+                .map(|address| Box::new(Clamd::new(address)) as Box<dyn Scanner>),
+            config.quarantine_dir.clone(),
+        )
+    }
+
+    /// Returns an error if the file is flagged or it couldn't be scanned.
+    pub async fn check(&self, filename: &str, data: &[u8]) -> Result<(), String> {
+        let Some(scanner) = &self.scanner else {
+            return Ok(());
+        };
+        let result = tokio::time::timeout(SCAN_TIMEOUT, scanner.scan(data))
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("scanning timed out")));
+        let threat = match result {
+            Ok(Verdict::Clean) => return Ok(()),
+            Ok(Verdict::Infected(threat)) => threat,
+            Err(e) => {
+                error!("Unable to scan upload \"{filename}\": {e}");
+                return Err("unable to scan the uploaded file".to_string());
+            }
+        };
+
+        warn!("Upload \"{filename}\" is flagged as {threat}");
+        if let Some(dir) = &self.quarantine_dir {
+            let path = dir.join(format!(
+                "{}-{}",
+                Utc::now().format("%Y%m%d%H%M%S%f"),
+                // Do not let the client choose the directory.
+                filename.replace(['/', '\\'], "_")
+            ));
+            match tokio::fs::write(&path, data).await {
+                Ok(_) => warn!("Upload \"{filename}\" is quarantined as {}", path.display()),
+                Err(e) => error!("Unable to quarantine upload \"{filename}\": {e}"),
+            }
+        }
+        Err(format!(
+            "the uploaded file is rejected as it contains {threat}"
+        ))
+    }
+}
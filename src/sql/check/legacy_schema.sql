@@ -0,0 +1,8 @@
+-- Whether the schema was created before migrations were introduced.
+SELECT
+    to_regclass('public.users') IS NOT NULL
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            schema_version);
@@ -0,0 +1,48 @@
+-- Archive tables whose columns differ from the columns of the hot tables
+-- in names or types, so the archived rows would lose data.
+WITH tables (hot, archive) AS
+(
+    VALUES
+        ('orders', 'orders_archive'),
+        ('orders_food', 'orders_food_archive'),
+        ('feedbacks', 'feedbacks_archive')
+),
+columns AS
+(
+    SELECT
+        relname AS table_name,
+        attname AS name,
+        format_type(atttypid, atttypmod) AS type
+    FROM
+        pg_attribute
+    JOIN
+        pg_class
+    ON
+        pg_class.oid = attrelid
+    WHERE
+        relnamespace = 'public'::regnamespace
+    AND
+        attnum > 0
+    AND
+        NOT attisdropped
+)
+SELECT
+    hot,
+    archive
+FROM
+    tables
+WHERE
+    EXISTS
+    (
+        (
+            SELECT name, type FROM columns WHERE table_name = hot
+            EXCEPT
+            SELECT name, type FROM columns WHERE table_name = archive
+        )
+        UNION ALL
+        (
+            SELECT name, type FROM columns WHERE table_name = archive
+            EXCEPT
+            SELECT name, type FROM columns WHERE table_name = hot
+        )
+    );
@@ -0,0 +1,13 @@
+SELECT EXISTS
+(
+    SELECT
+        1
+    FROM
+        addresses
+    WHERE
+        customer_id = $1
+    AND
+        id = $2
+    AND
+        delete_time IS NULL
+);
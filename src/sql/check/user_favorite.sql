@@ -5,7 +5,9 @@ SELECT EXISTS
     FROM
         favorites
     WHERE
-    	user_id = $1
-	AND
-		food_id = $2
+        user_id = $1
+    AND
+        food_id = $2
+    AND
+        delete_time IS NULL
 );
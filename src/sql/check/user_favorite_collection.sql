@@ -0,0 +1,11 @@
+SELECT EXISTS
+(
+    SELECT
+        1
+    FROM
+        favorite_collections
+    WHERE
+        user_id = $1
+    AND
+        id = $2
+);
@@ -0,0 +1,4 @@
+DELETE FROM
+    api_keys
+WHERE
+    id = $1;
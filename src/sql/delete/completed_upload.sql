@@ -0,0 +1,10 @@
+DELETE FROM
+    uploads
+WHERE
+    token = $1
+AND
+    user_id = $2
+AND
+    octet_length(data) = size
+RETURNING
+    data;
@@ -0,0 +1,4 @@
+DELETE FROM
+    feedbacks
+WHERE
+    id = $1;
@@ -0,0 +1,4 @@
+DELETE FROM
+    notifications
+WHERE
+    sent_time < CURRENT_TIMESTAMP - make_interval(days => $1::integer);
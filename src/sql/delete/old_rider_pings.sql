@@ -0,0 +1,4 @@
+DELETE FROM
+    rider_pings
+WHERE
+    "time" < CURRENT_TIMESTAMP - make_interval(days => $1::integer);
@@ -0,0 +1,4 @@
+DELETE FROM
+    uploads
+WHERE
+    create_time < CURRENT_TIMESTAMP - make_interval(hours => $1);
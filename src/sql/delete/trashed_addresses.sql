@@ -0,0 +1,14 @@
+-- Addresses of orders are kept.
+DELETE FROM
+    addresses
+WHERE
+    delete_time <= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+AND NOT EXISTS
+(
+    SELECT
+        1
+    FROM
+        all_orders AS orders
+    WHERE
+        address_id = addresses.id
+);
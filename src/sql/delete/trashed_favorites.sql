@@ -0,0 +1,4 @@
+DELETE FROM
+    favorites
+WHERE
+    delete_time <= CURRENT_TIMESTAMP - make_interval(days => $1::integer);
@@ -1,8 +0,0 @@
-DELETE FROM
-    orders
-WHERE
-    customer_id = $1
-AND
-    id = $2
-AND
-    rider_id IS NULL;
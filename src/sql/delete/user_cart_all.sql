@@ -1,4 +0,0 @@
-DELETE FROM
-    cart
-WHERE
-    customer_id = $1;
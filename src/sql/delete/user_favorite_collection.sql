@@ -0,0 +1,6 @@
+DELETE FROM
+    favorite_collections
+WHERE
+    user_id = $1
+AND
+    id = $2;
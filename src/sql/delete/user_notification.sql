@@ -1,5 +1,5 @@
 DELETE FROM
-    favorites
+    notifications
 WHERE
     user_id = $1
 AND
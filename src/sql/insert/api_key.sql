@@ -0,0 +1,15 @@
+INSERT INTO api_keys
+(
+    title,
+    key_hash,
+    scope,
+    create_time
+)
+VALUES
+(
+    $1,
+    $2,
+    $3,
+    CURRENT_TIMESTAMP
+)
+RETURNING id;
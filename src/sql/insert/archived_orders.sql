@@ -0,0 +1,64 @@
+-- Moves completed orders with their items and feedbacks to the archive tables.
+-- Columns are listed explicitly, the archive tables must have the same ones.
+WITH moved_orders AS
+(
+    DELETE FROM
+        orders
+    WHERE
+        status IN ('Delivered', 'Cancelled')
+    AND
+        COALESCE(completed_time, cancel_time)
+            < CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+    RETURNING
+        *
+),
+moved_items AS
+(
+    DELETE FROM
+        orders_food
+    WHERE
+        order_id IN (SELECT id FROM moved_orders)
+    RETURNING
+        *
+),
+moved_feedbacks AS
+(
+    DELETE FROM
+        feedbacks
+    WHERE
+        order_id IN (SELECT id FROM moved_orders)
+    RETURNING
+        *
+),
+archived_items AS
+(
+    INSERT INTO orders_food_archive (id, order_id, food_id, count, is_unavailable, price)
+    SELECT id, order_id, food_id, count, is_unavailable, price FROM moved_items
+),
+archived_feedbacks AS
+(
+    INSERT INTO feedbacks_archive
+    (
+        id, order_id, rating, comment, rider_rating, create_time, is_hidden, hidden_reason
+    )
+    SELECT
+        id, order_id, rating, comment, rider_rating, create_time, is_hidden, hidden_reason
+    FROM
+        moved_feedbacks
+)
+INSERT INTO orders_archive
+(
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason, cancel_time
+)
+SELECT
+    id, customer_id, address_id, create_time, rider_id, completed_time, status,
+    fulfillment, pickup_code, discount_percent, location_id, cancellation_fee,
+    promised_time, gift_recipient_name, gift_recipient_phone, gift_message, comment,
+    accept_time, sla_breach_time, scheduled_for, gift_address_token,
+    gift_address_expire_time, cancellation_reason, cancel_time
+FROM
+    moved_orders;
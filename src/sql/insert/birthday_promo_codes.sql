@@ -0,0 +1,55 @@
+-- Grants codes to customers whose birthday is today
+-- unless they have already got one this year.
+INSERT INTO promo_codes
+(
+    customer_id,
+    code,
+    reason,
+    discount_percent,
+    create_time,
+    expire_time
+)
+SELECT
+    id,
+    upper(substr(md5(random()::text || id::text), 1, 10)),
+    'Birthday',
+    $1,
+    CURRENT_TIMESTAMP,
+    CURRENT_TIMESTAMP + make_interval(days => $2::integer)
+FROM
+    users
+WHERE
+    role = 'Customer'
+AND
+    erased_time IS NULL
+AND
+(
+    to_char(birth_date, 'MM-DD') = to_char(CURRENT_DATE, 'MM-DD')
+OR
+    -- Customers born on February 29 are congratulated on February 28 in non-leap years.
+    (
+        to_char(birth_date, 'MM-DD') = '02-29'
+    AND
+        to_char(CURRENT_DATE, 'MM-DD') = '02-28'
+    AND
+        to_char(CURRENT_DATE + 1, 'MM-DD') = '03-01'
+    )
+)
+AND NOT EXISTS
+(
+    SELECT
+        1
+    FROM
+        promo_codes
+    WHERE
+        customer_id = users.id
+    AND
+        reason = 'Birthday'
+    AND
+        create_time >= date_trunc('year', CURRENT_DATE)
+)
+RETURNING
+    customer_id,
+    code,
+    discount_percent,
+    expire_time;
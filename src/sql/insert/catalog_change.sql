@@ -0,0 +1,23 @@
+INSERT INTO catalog_history
+(
+    "time",
+    manager_id,
+    entity,
+    entity_id,
+    action,
+    before,
+    after
+)
+SELECT
+    CURRENT_TIMESTAMP,
+    id,
+    $2,
+    $3,
+    $4,
+    $5,
+    $6
+FROM
+    users
+WHERE
+    username = $1
+RETURNING id;
@@ -0,0 +1,11 @@
+-- Placeholders in curly braces are replaced according to the entity.
+INSERT INTO {table}
+(
+    id,
+    {columns}
+)
+SELECT
+    id,
+    {columns}
+FROM
+    jsonb_populate_record(NULL::{table}, $1);
@@ -0,0 +1,24 @@
+-- One item of each selected favorite food is added.
+INSERT INTO cart
+(
+    customer_id,
+    food_id,
+    count,
+    add_time
+)
+SELECT
+    user_id,
+    food_id,
+    1,
+    CURRENT_TIMESTAMP
+FROM
+    favorites
+WHERE
+    user_id = $1
+AND
+    id = ANY($2)
+AND
+    delete_time IS NULL
+ON CONFLICT ON CONSTRAINT food_per_customer DO UPDATE
+SET
+    count = cart.count + EXCLUDED.count;
@@ -2,7 +2,8 @@ INSERT INTO feedbacks
 (
     order_id,
     rating,
-    comment
+    comment,
+    rider_rating
 )
-VALUES ($1, $2, $3)
+VALUES ($1, $2, $3, $4)
 RETURNING id;
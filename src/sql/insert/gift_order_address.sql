@@ -0,0 +1,49 @@
+-- Attaches the address to the gift order if the link is valid.
+-- The link can be used once as the order must be without an address.
+WITH gift_order AS
+(
+    SELECT
+        id
+    FROM
+        orders
+    WHERE
+        gift_address_token = $1
+    AND
+        gift_address_expire_time > CURRENT_TIMESTAMP
+    AND
+        address_id IS NULL
+    AND
+        status NOT IN ('Delivered', 'Cancelled')
+),
+address AS
+(
+    INSERT INTO addresses
+    (
+        locality,
+        street,
+        house,
+        corps,
+        apartment
+    )
+    SELECT
+        $2,
+        $3,
+        $4,
+        $5,
+        $6
+    FROM
+        gift_order
+    RETURNING
+        id
+)
+UPDATE
+    orders
+SET
+    address_id = address.id
+FROM
+    address
+WHERE
+    orders.id = (SELECT id FROM gift_order)
+RETURNING
+    orders.id,
+    orders.customer_id;
@@ -0,0 +1,13 @@
+INSERT INTO incidents
+(
+    title,
+    description,
+    start_time
+)
+VALUES
+(
+    $1,
+    $2,
+    CURRENT_TIMESTAMP
+)
+RETURNING id;
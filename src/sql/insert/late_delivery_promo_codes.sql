@@ -0,0 +1,47 @@
+-- Grants codes to customers whose orders were delivered later than promised
+-- by more than the tolerance. Each order is compensated only once.
+INSERT INTO promo_codes
+(
+    customer_id,
+    code,
+    reason,
+    discount_percent,
+    create_time,
+    expire_time,
+    compensated_order_id
+)
+SELECT
+    orders.customer_id,
+    upper(substr(md5(random()::text || orders.id::text), 1, 10)),
+    'LateDelivery',
+    $2,
+    CURRENT_TIMESTAMP,
+    CURRENT_TIMESTAMP + make_interval(days => $3::integer),
+    orders.id
+FROM
+    orders
+JOIN
+    users
+ON
+    users.id = orders.customer_id
+WHERE
+    orders.status = 'Delivered'
+AND
+    orders.completed_time > orders.promised_time + make_interval(mins => $1::integer)
+AND
+    users.erased_time IS NULL
+AND NOT EXISTS
+(
+    SELECT
+        1
+    FROM
+        promo_codes
+    WHERE
+        compensated_order_id = orders.id
+)
+RETURNING
+    customer_id,
+    code,
+    discount_percent,
+    expire_time,
+    compensated_order_id;
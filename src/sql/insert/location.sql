@@ -0,0 +1,7 @@
+INSERT INTO locations
+(
+    title,
+    localities
+)
+VALUES ($1, $2)
+RETURNING id;
@@ -0,0 +1,20 @@
+-- Records the current price of the food unless it's already the latest one.
+INSERT INTO price_history (food_id, price)
+SELECT
+    id,
+    price
+FROM
+    food
+WHERE
+    id = $1
+    AND price IS DISTINCT FROM (
+        SELECT
+            price
+        FROM
+            price_history
+        WHERE
+            food_id = $1
+        ORDER BY
+            change_time DESC,
+            id DESC
+        LIMIT 1);
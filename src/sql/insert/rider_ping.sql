@@ -0,0 +1,7 @@
+INSERT INTO rider_pings
+(
+    rider_id,
+    location_id,
+    "time"
+)
+VALUES ($1, $2, CURRENT_TIMESTAMP);
@@ -0,0 +1,55 @@
+-- Settles finished days which aren't settled yet. Orders are counted by the completion
+-- or cancellation date using the prices at the time of ordering. Discounts are rounded
+-- the same way as order totals.
+INSERT INTO settlements
+(
+    day,
+    delivered_count,
+    cancelled_count,
+    gross,
+    discounts,
+    fees
+)
+SELECT
+    COALESCE(orders.completed_time, orders.cancel_time)::date AS day,
+    count(*) FILTER (WHERE orders.status = 'Delivered'),
+    count(*) FILTER (WHERE orders.status = 'Cancelled'),
+    COALESCE(sum(totals.subtotal) FILTER (WHERE orders.status = 'Delivered'), 0),
+    COALESCE(
+        sum(
+            totals.subtotal - round(totals.subtotal * (100 - orders.discount_percent) / 100, 2)
+        ) FILTER (WHERE orders.status = 'Delivered'),
+        0
+    ),
+    COALESCE(sum(orders.cancellation_fee), 0)
+FROM
+    all_orders AS orders
+LEFT JOIN LATERAL
+(
+    SELECT
+        COALESCE(sum(orders_food.count * orders_food.price), 0) AS subtotal
+    FROM
+        all_orders_food AS orders_food
+    WHERE
+        orders_food.order_id = orders.id
+    AND
+        NOT orders_food.is_unavailable
+) AS totals
+ON
+    true
+WHERE
+    orders.status IN ('Delivered', 'Cancelled')
+AND
+    COALESCE(orders.completed_time, orders.cancel_time) < CURRENT_DATE
+AND NOT EXISTS
+(
+    SELECT
+        1
+    FROM
+        settlements
+    WHERE
+        settlements.day = COALESCE(orders.completed_time, orders.cancel_time)::date
+)
+GROUP BY
+    1
+ON CONFLICT (day) DO NOTHING;
@@ -0,0 +1,7 @@
+INSERT INTO uploads
+(
+    token,
+    user_id,
+    size
+)
+VALUES ($1, $2, $3);
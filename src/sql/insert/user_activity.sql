@@ -0,0 +1,20 @@
+INSERT INTO activities
+(
+    user_id,
+    "time",
+    kind,
+    ip_address,
+    user_agent,
+    details
+)
+SELECT
+    id,
+    CURRENT_TIMESTAMP,
+    $2,
+    $3,
+    $4,
+    $5
+FROM
+    users
+WHERE
+    username = $1;
@@ -5,7 +5,28 @@ INSERT INTO addresses
     street,
     house,
     corps,
-    apartment
+    apartment,
+    is_default
+)
+VALUES
+(
+    $1,
+    $2,
+    $3,
+    $4,
+    $5,
+    $6,
+    -- The first address becomes default.
+    NOT EXISTS
+    (
+        SELECT
+            1
+        FROM
+            addresses
+        WHERE
+            customer_id = $1
+        AND
+            is_default
+    )
 )
-VALUES ($1, $2, $3, $4, $5, $6)
 RETURNING id;
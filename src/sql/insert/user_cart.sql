@@ -12,4 +12,10 @@ VALUES
     $3,
     CURRENT_TIMESTAMP
 )
-RETURNING id;
+ON CONFLICT ON CONSTRAINT food_per_customer DO UPDATE
+SET
+    count = cart.count + EXCLUDED.count
+RETURNING
+    id,
+    -- Zero for a freshly inserted row.
+    xmax = 0 AS inserted;
@@ -2,12 +2,21 @@ INSERT INTO favorites
 (
     user_id,
     food_id,
-    add_time
+    add_time,
+    collection_id
 )
 VALUES
 (
     $1,
     $2,
-    CURRENT_TIMESTAMP
+    CURRENT_TIMESTAMP,
+    $3
 )
+-- Favorite from the trash is added again.
+ON CONFLICT ON CONSTRAINT food_per_user DO UPDATE SET
+    add_time = EXCLUDED.add_time,
+    delete_time = NULL,
+    collection_id = EXCLUDED.collection_id
+WHERE
+    favorites.delete_time IS NOT NULL
 RETURNING id;
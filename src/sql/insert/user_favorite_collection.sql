@@ -0,0 +1,7 @@
+INSERT INTO favorite_collections
+(
+    user_id,
+    title
+)
+VALUES ($1, $2)
+RETURNING id;
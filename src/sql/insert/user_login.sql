@@ -0,0 +1,33 @@
+INSERT INTO activities
+(
+    user_id,
+    "time",
+    kind,
+    ip_address,
+    user_agent
+)
+SELECT
+    id,
+    CURRENT_TIMESTAMP,
+    'NewDeviceLogin',
+    $2::character varying,
+    $3::text
+FROM
+    users
+WHERE
+    username = $1
+AND NOT EXISTS
+(
+    SELECT
+        1
+    FROM
+        activities
+    WHERE
+        activities.user_id = users.id
+    AND
+        activities.kind IN ('SignUp', 'NewDeviceLogin')
+    AND
+        activities.ip_address IS NOT DISTINCT FROM $2
+    AND
+        activities.user_agent IS NOT DISTINCT FROM $3
+);
@@ -1,22 +1,220 @@
-INSERT INTO orders
+-- Creates the order from the cart items in a single statement: takes the items from
+-- the stock, inserts them into the order, marks the promo code as used and empties
+-- the cart. Nothing is changed if any stock count would become negative.
+-- ID of the order is taken in advance, as the promo code references the order.
+WITH order_id AS
 (
-    customer_id,
-    address_id,
-    create_time
-)
-VALUES
+    SELECT nextval(pg_get_serial_sequence('orders', 'id'))::integer AS id
+),
+-- Discount is taken from the promo code only if it's still unused. Otherwise the
+-- discount is NULL and the insert fails, so a code can't be used by two orders.
+used_promo_code AS
+(
+    UPDATE
+        promo_codes
+    SET
+        order_id = order_id.id
+    FROM
+        order_id
+    WHERE
+        promo_codes.id = $14
+    AND
+        promo_codes.customer_id = $1
+    AND
+        promo_codes.order_id IS NULL
+    AND
+        promo_codes.expire_time > CURRENT_TIMESTAMP
+    RETURNING
+        promo_codes.discount_percent
+),
+new_order AS
 (
-    $1,
+    INSERT INTO orders
+    (
+        id,
+        customer_id,
+        address_id,
+        create_time,
+        fulfillment,
+        pickup_code,
+        discount_percent,
+        location_id,
+        status,
+        promised_time,
+        gift_recipient_name,
+        gift_recipient_phone,
+        gift_message,
+        comment,
+        scheduled_for
+    )
+    VALUES
     (
-        SELECT
-            id
-        FROM
-            addresses
-        WHERE
-            id = $2
-        AND
-            customer_id = $3
-    ),
-    CURRENT_TIMESTAMP
+        (SELECT id FROM order_id),
+        $1,
+        (
+            SELECT
+                id
+            FROM
+                addresses
+            WHERE
+                id = $2
+            AND
+                customer_id = $1
+            AND
+                delete_time IS NULL
+        ),
+        CURRENT_TIMESTAMP,
+        $3,
+        $4,
+        CASE
+            WHEN $14::integer IS NULL THEN 0
+            ELSE (SELECT discount_percent FROM used_promo_code)
+        END,
+        $5,
+        -- Orders which aren't scheduled are queued if there are no free slots or
+        -- other orders are already waiting. The settings row must be locked by
+        -- the transaction, so concurrent orders can't take the same slot.
+        COALESCE(
+            $6,
+            (
+                SELECT
+                    CASE
+                        WHEN order_capacity IS NOT NULL
+                        AND
+                        (
+                            (
+                                SELECT
+                                    count(*)
+                                FROM
+                                    orders
+                                WHERE
+                                    status IN ('Created', 'Accepted', 'PickedUp', 'ReadyForPickup')
+                            ) >= order_capacity
+                        OR
+                            EXISTS (SELECT FROM orders WHERE status = 'Queued')
+                        )
+                        THEN 'Queued'::"OrderStatus"
+                        ELSE 'Created'::"OrderStatus"
+                    END
+                FROM
+                    settings
+            ),
+            -- There is no limit if the settings row doesn't exist.
+            'Created'
+        ),
+        -- NULL if there is no promised delivery time.
+        -- Scheduled orders are promised by the scheduled time.
+        CASE WHEN $3::"FulfillmentType" = 'Delivery' THEN
+            (
+                SELECT
+                    CASE
+                        WHEN promised_delivery_minutes IS NULL THEN NULL
+                        WHEN $11::timestamp IS NOT NULL THEN $11
+                        ELSE CURRENT_TIMESTAMP + make_interval(mins => promised_delivery_minutes)
+                    END
+                FROM
+                    settings
+            )
+        END,
+        $7,
+        $8,
+        $9,
+        $10,
+        $11
+    )
+    RETURNING
+        id
+),
+items AS
+(
+    SELECT
+        *
+    FROM
+        unnest($12::integer[], $13::integer[]) WITH ORDINALITY AS items (food_id, count, position)
+),
+updated_food AS
+(
+    UPDATE
+        food
+    SET
+        count = food.count - items.count
+    FROM
+        items
+    WHERE
+        food.id = items.food_id
+    RETURNING
+        food.id,
+        food.count,
+        items.count AS taken
+),
+updated_location_stock AS
+(
+    UPDATE
+        location_stock
+    SET
+        count = location_stock.count - items.count
+    FROM
+        items
+    WHERE
+        location_stock.location_id = $5
+    AND
+        location_stock.food_id = items.food_id
+),
+recorded_movements AS
+(
+    INSERT INTO stock_movements
+    (
+        food_id,
+        "time",
+        kind,
+        delta,
+        count_after,
+        order_id
+    )
+    SELECT
+        updated_food.id,
+        CURRENT_TIMESTAMP,
+        'OrderDecrement',
+        -updated_food.taken,
+        updated_food.count,
+        new_order.id
+    FROM
+        updated_food,
+        new_order
+),
+order_food AS
+(
+    INSERT INTO orders_food
+    (
+        order_id,
+        food_id,
+        count,
+        price
+    )
+    SELECT
+        new_order.id,
+        items.food_id,
+        items.count,
+        food.price
+    FROM
+        new_order,
+        items
+    JOIN
+        food
+    ON
+        food.id = items.food_id
+    -- Items are numbered in the order they were added to the cart.
+    ORDER BY
+        items.position
+),
+emptied_cart AS
+(
+    DELETE FROM
+        cart
+    WHERE
+        customer_id = $1
 )
-RETURNING id;
+SELECT
+    id
+FROM
+    new_order;
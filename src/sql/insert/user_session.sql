@@ -0,0 +1,43 @@
+WITH
+    user_row AS
+    (
+        SELECT
+            id
+        FROM
+            users
+        WHERE
+            username = $1
+    ),
+    updated AS
+    (
+        UPDATE
+            sessions
+        SET
+            last_seen = CURRENT_TIMESTAMP
+        WHERE
+            user_id = (SELECT id FROM user_row)
+        AND
+            ip_address IS NOT DISTINCT FROM $2::character varying
+        AND
+            user_agent IS NOT DISTINCT FROM $3::text
+        RETURNING
+            id
+    )
+INSERT INTO sessions
+(
+    user_id,
+    ip_address,
+    user_agent,
+    created,
+    last_seen
+)
+SELECT
+    id,
+    $2,
+    $3,
+    CURRENT_TIMESTAMP,
+    CURRENT_TIMESTAMP
+FROM
+    user_row
+WHERE
+    NOT EXISTS (SELECT 1 FROM updated);
@@ -0,0 +1,12 @@
+-- Locations serving locality of the address, the locality is compared case-insensitively.
+SELECT
+    locations.id
+FROM
+    locations,
+    addresses
+WHERE
+    addresses.id = $1
+AND
+    lower(addresses.locality) IN (SELECT lower(unnest(locations.localities)))
+ORDER BY
+    locations.id;
@@ -3,4 +3,4 @@ SELECT
 FROM
     addresses
 WHERE
-    id = $1;
+    id = ANY($1);
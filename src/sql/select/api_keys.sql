@@ -0,0 +1,18 @@
+SELECT
+    id,
+    title,
+    -- Do not select 'key_hash' as it mustn't leave the database.
+    scope,
+    create_time,
+    last_used_time,
+    request_count,
+    CASE
+        WHEN quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+        THEN quota_window_requests
+        ELSE 0
+    END AS current_minute_requests
+FROM
+    api_keys
+ORDER BY
+    create_time
+DESC;
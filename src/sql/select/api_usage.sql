@@ -0,0 +1,22 @@
+SELECT
+    username,
+    role,
+    request_count,
+    last_request_time,
+    -- Requests of the previous minutes aren't counted.
+    CASE
+        WHEN quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+        THEN quota_window_requests
+        ELSE 0
+    END AS current_minute_requests
+FROM
+    users
+WHERE
+    request_count > 0
+ORDER BY
+    request_count DESC,
+    username
+LIMIT
+    $1
+OFFSET
+    $2;
@@ -0,0 +1,25 @@
+SELECT
+    *
+FROM
+    orders
+WHERE
+    rider_id IS NULL
+AND
+    status = 'Created'
+AND
+    fulfillment = 'Delivery'
+-- Gift recipient hasn't entered the address yet.
+AND
+    address_id IS NOT NULL
+AND
+(
+    $2::integer IS NULL
+OR
+    location_id = $2
+)
+-- Placeholder in curly braces is replaced according to the sorting order.
+ORDER BY
+    create_time {direction},
+    id {direction}
+LIMIT
+    $1;
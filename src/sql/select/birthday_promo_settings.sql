@@ -0,0 +1,6 @@
+SELECT
+    is_birthday_promo_enabled,
+    birthday_discount_percent,
+    birthday_promo_days
+FROM
+    settings;
@@ -0,0 +1,5 @@
+SELECT
+    accepted_cancellation_fee_percent,
+    picked_up_cancellation_fee_percent
+FROM
+    settings;
@@ -0,0 +1,6 @@
+SELECT
+    *
+FROM
+    catalog_history
+WHERE
+    id = $1;
@@ -0,0 +1,16 @@
+SELECT
+    *
+FROM
+    catalog_history
+WHERE
+    -- NULL means any entity.
+    ($1::"CatalogEntity" IS NULL OR entity = $1)
+AND
+    ($2::integer IS NULL OR entity_id = $2)
+ORDER BY
+    id
+DESC
+LIMIT
+    $3
+OFFSET
+    $4;
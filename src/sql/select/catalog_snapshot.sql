@@ -0,0 +1,7 @@
+-- Placeholder in curly braces is replaced by the table name of the entity.
+SELECT
+    to_jsonb(entity) - 'preview'
+FROM
+    {table} AS entity
+WHERE
+    id = $1;
@@ -6,4 +6,9 @@ SELECT
 FROM
     categories
 ORDER BY
-    title;
+    title
+LIMIT
+    -- NULL means no limit.
+    $1
+OFFSET
+    $2;
@@ -0,0 +1,9 @@
+SELECT
+    id,
+    title,
+    description
+    -- Do not select 'preview' as it contains large data (JPEG image).
+FROM
+    categories
+WHERE
+    id = ANY($1);
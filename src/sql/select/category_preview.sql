@@ -1,5 +1,28 @@
 SELECT
-    preview
+    COALESCE(
+        categories.preview,
+        -- Preview of the most ordered food is shown if the category doesn't have one.
+        (
+            SELECT
+                food.preview
+            FROM
+                food
+            LEFT JOIN
+                all_orders_food AS orders_food
+            ON
+                orders_food.food_id = food.id
+            WHERE
+                food.category_id = categories.id
+            AND
+                food.preview IS NOT NULL
+            GROUP BY
+                food.id
+            ORDER BY
+                sum(orders_food.count) DESC NULLS LAST,
+                food.id
+            LIMIT 1
+        )
+    ) AS preview
 FROM
     categories
 WHERE
@@ -0,0 +1,21 @@
+-- Customers with the same full name and birth date are likely the same person.
+SELECT
+    array_agg(id ORDER BY id) AS user_ids
+FROM
+    users
+WHERE
+    role = 'Customer'
+AND
+    erased_time IS NULL
+AND
+    first_name IS NOT NULL
+AND
+    last_name IS NOT NULL
+GROUP BY
+    lower(trim(first_name)),
+    lower(trim(last_name)),
+    birth_date
+HAVING
+    count(*) > 1
+ORDER BY
+    min(id);
@@ -0,0 +1,11 @@
+-- Average time of delivering an order during the last hour.
+SELECT
+    (extract(epoch FROM avg(completed_time - create_time)) / 60)::integer
+FROM
+    orders
+WHERE
+    status = 'Delivered'
+AND
+    fulfillment = 'Delivery'
+AND
+    completed_time > CURRENT_TIMESTAMP - interval '1 hour';
@@ -0,0 +1,10 @@
+SELECT
+    id,
+    title,
+    description,
+    -- Images are exported as references.
+    preview IS NOT NULL AS has_preview
+FROM
+    categories
+ORDER BY
+    title;
@@ -0,0 +1,14 @@
+SELECT
+    id,
+    title,
+    description,
+    category_id,
+    count,
+    is_alcohol,
+    price,
+    -- Images are exported as references.
+    preview IS NOT NULL AS has_preview
+FROM
+    food
+ORDER BY
+    title;
@@ -0,0 +1,12 @@
+SELECT
+    feedbacks.order_id,
+    orders.customer_id,
+    feedbacks.create_time > CURRENT_TIMESTAMP - make_interval(hours => $2) AS is_editable
+FROM
+    feedbacks
+INNER JOIN
+    orders
+ON
+    orders.id = feedbacks.order_id
+WHERE
+    feedbacks.id = $1;
@@ -0,0 +1,14 @@
+SELECT
+    feedbacks.comment
+FROM
+    all_feedbacks AS feedbacks
+JOIN
+    all_orders AS orders
+ON
+    orders.id = feedbacks.order_id
+WHERE
+    orders.completed_time >= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+AND
+    feedbacks.comment IS NOT NULL
+AND
+    ($2::smallint IS NULL OR feedbacks.rating <= $2);
@@ -0,0 +1,4 @@
+SELECT
+    feedback_edit_hours
+FROM
+    settings;
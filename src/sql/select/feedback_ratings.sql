@@ -0,0 +1,15 @@
+SELECT
+    feedbacks.rating,
+    count(*) AS count
+FROM
+    all_feedbacks AS feedbacks
+JOIN
+    all_orders AS orders
+ON
+    orders.id = feedbacks.order_id
+WHERE
+    orders.completed_time >= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+GROUP BY
+    feedbacks.rating
+ORDER BY
+    feedbacks.rating;
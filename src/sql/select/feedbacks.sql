@@ -0,0 +1,15 @@
+SELECT
+    *
+FROM
+    all_feedbacks AS feedbacks
+WHERE
+    $1::boolean IS NULL
+OR
+    is_hidden = $1
+ORDER BY
+    create_time DESC,
+    id DESC
+LIMIT
+    $2
+OFFSET
+    $3;
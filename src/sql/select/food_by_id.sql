@@ -0,0 +1,15 @@
+SELECT
+    id,
+    title,
+    description,
+    -- Do not select 'preview' as it contains large data (JPEG image).
+    category_id,
+    count,
+    is_alcohol,
+    price,
+    average_rating,
+    ratings_count
+FROM
+    food
+WHERE
+    id = $1;
@@ -6,8 +6,27 @@ SELECT
     category_id,
     count,
     is_alcohol,
-    price
+    price,
+    average_rating,
+    ratings_count
 FROM
     food
 WHERE
-    category_id = $1;
+    category_id = $1
+-- Filters are skipped if the corresponding parameters are NULL or false.
+AND
+    ($4::numeric IS NULL OR price >= $4)
+AND
+    ($5::numeric IS NULL OR price <= $5)
+AND
+    (NOT $6 OR NOT is_alcohol)
+AND
+    (NOT $7 OR count > 0)
+-- Placeholders in curly braces are replaced according to the sorting parameters.
+ORDER BY
+    {sort_column} {direction},
+    id {direction}
+LIMIT
+    $2
+OFFSET
+    $3;
@@ -0,0 +1,28 @@
+-- Placeholders in curly braces are replaced according to the sorting parameters.
+SELECT
+    id,
+    title,
+    description,
+    -- Do not select 'preview' as it contains large data (JPEG image).
+    category_id,
+    count,
+    is_alcohol,
+    price,
+    average_rating,
+    ratings_count
+FROM
+    food
+WHERE
+    category_id = $1
+AND
+(
+    -- ID of the cursor, NULL for the first page.
+    $2::integer IS NULL
+OR
+    ({sort_column}, id) {comparison} ($3::text::{sort_type}, $2)
+)
+ORDER BY
+    {sort_column} {direction},
+    id {direction}
+LIMIT
+    $4;
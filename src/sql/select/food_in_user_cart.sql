@@ -6,7 +6,9 @@ SELECT
     food.category_id,
     food.count,
     food.is_alcohol,
-    food.price
+    food.price,
+    food.average_rating,
+    food.ratings_count
 FROM
     cart,
     food
@@ -0,0 +1,7 @@
+SELECT
+    promised_delivery_minutes,
+    late_delivery_tolerance_minutes,
+    late_delivery_discount_percent,
+    late_delivery_promo_days
+FROM
+    settings;
@@ -0,0 +1,29 @@
+SELECT
+    count(*) AS issued_count,
+    count(promo_codes.order_id) AS used_count,
+    COALESCE(sum(discounts.amount), 0) AS discount_total
+FROM
+    promo_codes
+-- Discount given by the code on the order where it was used.
+LEFT JOIN LATERAL
+(
+    SELECT
+        round(sum(orders_food.count * food.price) * promo_codes.discount_percent / 100, 2)
+            AS amount
+    FROM
+        all_orders_food AS orders_food
+    JOIN
+        food
+    ON
+        food.id = orders_food.food_id
+    WHERE
+        orders_food.order_id = promo_codes.order_id
+    AND
+        NOT orders_food.is_unavailable
+) AS discounts
+ON
+    true
+WHERE
+    promo_codes.reason = 'LateDelivery'
+AND
+    promo_codes.create_time >= CURRENT_TIMESTAMP - make_interval(days => $1::integer);
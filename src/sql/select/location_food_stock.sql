@@ -0,0 +1,9 @@
+SELECT
+    food_id,
+    count
+FROM
+    location_stock
+WHERE
+    location_id = $1
+AND
+    food_id = ANY($2);
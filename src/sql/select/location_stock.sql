@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    location_stock
+WHERE
+    food_id = ANY($1)
+ORDER BY
+    location_id;
@@ -0,0 +1,6 @@
+SELECT
+    *
+FROM
+    locations
+ORDER BY
+    id;
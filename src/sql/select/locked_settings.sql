@@ -0,0 +1,6 @@
+-- Locks the settings row, if it exists, until the end of the transaction.
+SELECT
+    order_capacity
+FROM
+    settings
+FOR UPDATE;
@@ -0,0 +1,5 @@
+SELECT
+    is_maintenance,
+    maintenance_message
+FROM
+    settings;
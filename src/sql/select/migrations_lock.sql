@@ -0,0 +1,3 @@
+-- Waits until other servers finish applying migrations.
+SELECT
+    pg_advisory_lock($1);
@@ -0,0 +1,9 @@
+-- Names of the passed tables and views which don't exist.
+SELECT
+    name
+FROM
+    unnest($1::text[]) AS name
+WHERE
+    to_regclass('public.' || name) IS NULL
+ORDER BY
+    name;
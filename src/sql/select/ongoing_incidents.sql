@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    incidents
+WHERE
+    resolve_time IS NULL
+ORDER BY
+    start_time DESC;
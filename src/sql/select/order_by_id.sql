@@ -0,0 +1,6 @@
+SELECT
+    *
+FROM
+    all_orders AS orders
+WHERE
+    id = $1;
@@ -0,0 +1,4 @@
+SELECT
+    order_capacity
+FROM
+    settings;
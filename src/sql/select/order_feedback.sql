@@ -1,6 +0,0 @@
-SELECT
-    *
-FROM
-    feedbacks
-WHERE
-    order_id = $1;
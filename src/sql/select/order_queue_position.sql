@@ -0,0 +1,54 @@
+WITH
+    target AS
+    (
+        SELECT
+            id,
+            create_time
+        FROM
+            orders
+        WHERE
+            id = $1
+        AND
+            customer_id = $2
+        AND
+            status = 'Queued'
+    ),
+    queue AS
+    (
+        SELECT
+            count(*) AS position
+        FROM
+            orders,
+            target
+        WHERE
+            orders.status = 'Queued'
+        AND
+            (orders.create_time, orders.id) <= (target.create_time, target.id)
+    ),
+    -- Average time of completing an order during the last day.
+    fulfillment AS
+    (
+        SELECT
+            avg(completed_time - create_time) AS duration
+        FROM
+            orders
+        WHERE
+            status = 'Delivered'
+        AND
+            completed_time > CURRENT_TIMESTAMP - interval '1 day'
+    )
+SELECT
+    queue.position::integer,
+    (
+        CURRENT_TIMESTAMP
+        + ceil(queue.position::double precision / settings.order_capacity)
+        * COALESCE(fulfillment.duration, make_interval(mins => $3))
+    )::timestamp AS estimated_acceptance_time
+FROM
+    target,
+    queue,
+    fulfillment
+LEFT JOIN
+    settings
+ON
+    true;
@@ -0,0 +1,7 @@
+SELECT
+    opening_time,
+    closing_time,
+    min_schedule_minutes,
+    dispatch_minutes
+FROM
+    settings;
@@ -1,7 +1,13 @@
 SELECT
     *
 FROM
-    orders
+    all_orders AS orders
+WHERE
+    status = ANY($1)
 ORDER BY
     create_time
-DESC;
+DESC
+LIMIT
+    $2
+OFFSET
+    $3;
@@ -0,0 +1,6 @@
+SELECT
+    *
+FROM
+    all_feedbacks AS feedbacks
+WHERE
+    order_id = ANY($1);
@@ -1,4 +1,4 @@
-SELECT
+SELECT DISTINCT
     food.id,
     food.title,
     food.description,
@@ -6,11 +6,13 @@ SELECT
     food.category_id,
     food.count,
     food.is_alcohol,
-    food.price
+    food.price,
+    food.average_rating,
+    food.ratings_count
 FROM
     food,
-	orders_food
+    all_orders_food AS orders_food
 WHERE
-    orders_food.order_id = $1
+    orders_food.order_id = ANY($1)
 AND
     orders_food.food_id = food.id;
@@ -0,0 +1,10 @@
+SELECT
+    *
+FROM
+    all_orders_food AS orders_food
+WHERE
+    order_id = ANY($1)
+ORDER BY
+    -- Views have no tuple IDs, so items are sorted by insertion order.
+    id
+DESC;
@@ -0,0 +1,18 @@
+SELECT
+    *
+FROM
+    all_orders AS orders
+WHERE
+    status = ANY($1)
+AND
+(
+    -- ID of the cursor, NULL for the first page.
+    $2::integer IS NULL
+OR
+    (create_time, id) < ($3::text::timestamp, $2)
+)
+ORDER BY
+    create_time DESC,
+    id DESC
+LIMIT
+    $4;
@@ -0,0 +1,11 @@
+SELECT
+    food_id,
+    price,
+    change_time
+FROM
+    price_history
+WHERE
+    food_id = ANY($1)
+ORDER BY
+    change_time DESC,
+    id DESC;
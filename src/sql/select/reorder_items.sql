@@ -0,0 +1,15 @@
+SELECT
+    food.id AS food_id,
+    food.title,
+    orders_food.count AS requested,
+    food.count AS available
+FROM
+    all_orders_food AS orders_food
+JOIN
+    food
+ON
+    food.id = orders_food.food_id
+WHERE
+    orders_food.order_id = $1
+ORDER BY
+    orders_food.id;
@@ -0,0 +1,40 @@
+WITH
+    sales AS
+    (
+        SELECT
+            food_id,
+            -- Cancelled orders are subtracted.
+            -SUM(delta) AS sold
+        FROM
+            stock_movements
+        WHERE
+            kind IN ('OrderDecrement', 'OrderCancellation')
+        AND
+            "time" >= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+        GROUP BY
+            food_id
+    )
+SELECT
+    food.id,
+    food.title,
+    food.description,
+    -- Do not select 'preview' as it contains large data (JPEG image).
+    food.category_id,
+    food.count,
+    food.is_alcohol,
+    food.price,
+    food.average_rating,
+    food.ratings_count,
+    sales.sold::double precision / $1 AS daily_sales
+FROM
+    food,
+    sales
+WHERE
+    sales.food_id = food.id
+AND
+    sales.sold > 0
+AND
+    -- Runs out within $2 days.
+    food.count < sales.sold::double precision / $1 * $2::integer
+ORDER BY
+    food.count / (sales.sold::double precision / $1);
@@ -0,0 +1,47 @@
+WITH coverage AS
+(
+    SELECT
+        location_id,
+        date_trunc('hour', "time") AS hour,
+        count(DISTINCT rider_id) AS rider_count
+    FROM
+        rider_pings
+    WHERE
+        "time" >= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+    GROUP BY
+        location_id,
+        hour
+),
+demand AS
+(
+    SELECT
+        location_id,
+        date_trunc('hour', create_time) AS hour,
+        count(*) AS order_count
+    FROM
+        all_orders AS orders
+    WHERE
+        fulfillment = 'Delivery'
+    AND
+        location_id IS NOT NULL
+    AND
+        create_time >= CURRENT_TIMESTAMP - make_interval(days => $1::integer)
+    GROUP BY
+        location_id,
+        hour
+)
+-- Hours with orders but without riders are included to reveal staffing gaps.
+SELECT
+    location_id,
+    hour,
+    COALESCE(rider_count, 0) AS rider_count,
+    COALESCE(order_count, 0) AS order_count
+FROM
+    coverage
+FULL JOIN
+    demand
+USING
+    (location_id, hour)
+ORDER BY
+    hour DESC,
+    location_id;
@@ -0,0 +1,11 @@
+SELECT
+    *
+FROM
+    all_orders AS orders
+WHERE
+    rider_id = $1
+AND
+    status = ANY($2)
+ORDER BY
+    create_time,
+    id;
@@ -0,0 +1,13 @@
+SELECT
+    avg(feedbacks.rider_rating)::double precision AS average,
+    count(feedbacks.rider_rating) AS count
+FROM
+    all_feedbacks AS feedbacks
+JOIN
+    all_orders AS orders
+ON
+    orders.id = feedbacks.order_id
+WHERE
+    orders.rider_id = $1
+AND
+    NOT feedbacks.is_hidden;
@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    settlements
+WHERE
+    day BETWEEN $1 AND $2
+ORDER BY
+    day;
@@ -0,0 +1,9 @@
+-- Titles are compared ignoring case and surrounding whitespace.
+SELECT
+    id
+FROM
+    categories
+WHERE
+    lower(trim(title)) = lower(trim($1))
+LIMIT
+    1;
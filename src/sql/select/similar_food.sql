@@ -0,0 +1,11 @@
+-- Titles are compared ignoring case and surrounding whitespace.
+SELECT
+    id
+FROM
+    food
+WHERE
+    category_id = $1
+AND
+    lower(trim(title)) = lower(trim($2))
+LIMIT
+    1;
@@ -0,0 +1,5 @@
+SELECT
+    sla_accept_minutes,
+    sla_delivery_minutes
+FROM
+    settings;
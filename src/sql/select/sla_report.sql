@@ -0,0 +1,23 @@
+-- In-time counts are NULL if there is no corresponding target.
+-- Targets of scheduled orders are counted from the scheduled time.
+SELECT
+    count(*) FILTER (WHERE accept_time IS NOT NULL) AS accepted_count,
+    CASE WHEN $2::integer IS NOT NULL THEN
+        count(*) FILTER (
+            WHERE accept_time <= COALESCE(scheduled_for, create_time) + make_interval(mins => $2::integer)
+        )
+    END AS accepted_in_time_count,
+    count(*) FILTER (WHERE status = 'Delivered') AS delivered_count,
+    CASE WHEN $3::integer IS NOT NULL THEN
+        count(*) FILTER (
+            WHERE status = 'Delivered'
+            AND completed_time <= COALESCE(scheduled_for, create_time) + make_interval(mins => $3::integer)
+        )
+    END AS delivered_in_time_count,
+    count(sla_breach_time) AS breached_count
+FROM
+    all_orders AS orders
+WHERE
+    fulfillment = 'Delivery'
+AND
+    create_time >= CURRENT_TIMESTAMP - make_interval(days => $1::integer);
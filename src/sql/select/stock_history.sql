@@ -0,0 +1,14 @@
+SELECT
+    *
+FROM
+    stock_movements
+WHERE
+    -- NULL means any food.
+    ($1::integer IS NULL OR food_id = $1)
+ORDER BY
+    id
+DESC
+LIMIT
+    $2
+OFFSET
+    $3;
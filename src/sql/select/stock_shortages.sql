@@ -0,0 +1,15 @@
+SELECT
+    food.id AS food_id,
+    food.title,
+    items.count AS requested,
+    food.count AS available
+FROM
+    unnest($1::integer[], $2::integer[]) AS items (food_id, count)
+JOIN
+    food
+ON
+    food.id = items.food_id
+WHERE
+    food.count < items.count
+ORDER BY
+    food.title;
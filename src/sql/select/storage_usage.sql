@@ -0,0 +1,18 @@
+-- Preview of the category with ID $1 or food with ID $2 isn't counted, as it's being replaced.
+SELECT
+    (
+        SELECT
+            COALESCE(sum(octet_length(preview)), 0)
+        FROM
+            categories
+        WHERE
+            id IS DISTINCT FROM $1
+    ) AS category_preview_bytes,
+    (
+        SELECT
+            COALESCE(sum(octet_length(preview)), 0)
+        FROM
+            food
+        WHERE
+            id IS DISTINCT FROM $2
+    ) AS food_preview_bytes;
@@ -0,0 +1,11 @@
+SELECT
+    *
+FROM
+    addresses
+WHERE
+    customer_id = $1
+AND
+    delete_time > CURRENT_TIMESTAMP - make_interval(days => $2::integer)
+ORDER BY
+    delete_time
+DESC;
@@ -0,0 +1,8 @@
+SELECT
+    COUNT(*)
+FROM
+    notifications
+WHERE
+    user_id = $1
+AND
+    read_time IS NULL;
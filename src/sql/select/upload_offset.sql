@@ -0,0 +1,9 @@
+SELECT
+    octet_length(data) AS "offset",
+    size
+FROM
+    uploads
+WHERE
+    token = $1
+AND
+    user_id = $2;
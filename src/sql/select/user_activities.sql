@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    activities
+WHERE
+    user_id = $1
+ORDER BY
+    "time"
+DESC;
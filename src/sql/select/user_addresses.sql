@@ -4,7 +4,9 @@ FROM
     addresses
 WHERE
     customer_id = $1
+AND
+    delete_time IS NULL
 ORDER BY
+    is_default DESC,
     -- Internal tuple ID signifying physical order.
-    ctid
-DESC;
+    ctid DESC;
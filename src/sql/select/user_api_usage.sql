@@ -0,0 +1,15 @@
+SELECT
+    username,
+    role,
+    request_count,
+    last_request_time,
+    -- Requests of the previous minutes aren't counted.
+    CASE
+        WHEN quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+        THEN quota_window_requests
+        ELSE 0
+    END AS current_minute_requests
+FROM
+    users
+WHERE
+    username = $1;
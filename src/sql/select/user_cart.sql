@@ -3,4 +3,8 @@ SELECT
 FROM
     cart
 WHERE
-    customer_id = $1;
+    customer_id = $1
+-- Placeholders in curly braces are replaced according to the sorting parameters.
+ORDER BY
+    {sort_column} {direction},
+    id {direction};
@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    favorite_collections
+WHERE
+    user_id = $1
+ORDER BY
+    create_time;
@@ -4,6 +4,14 @@ FROM
     favorites
 WHERE
     user_id = $1
+AND
+    delete_time IS NULL
+AND
+    ($4::integer IS NULL OR collection_id = $4)
 ORDER BY
     add_time
-DESC;
+DESC
+LIMIT
+    $2
+OFFSET
+    $3;
@@ -4,6 +4,8 @@ FROM
     notifications
 WHERE
     user_id = $1
+AND
+    (NOT $2 OR read_time IS NULL)
 ORDER BY
     sent_time
 DESC;
@@ -5,4 +5,6 @@ FROM
 WHERE
     customer_id = $1
 AND
-    id = $2;
+    id = $2
+AND
+    status = ANY($3);
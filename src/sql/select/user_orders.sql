@@ -1,9 +1,15 @@
 SELECT
     *
 FROM
-    orders
+    all_orders AS orders
 WHERE
     customer_id = $1
+AND
+    status = ANY($2)
 ORDER BY
     create_time
-DESC;
+DESC
+LIMIT
+    $3
+OFFSET
+    $4;
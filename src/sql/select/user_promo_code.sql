@@ -0,0 +1,13 @@
+-- Returns the code only if it can be used.
+SELECT
+    *
+FROM
+    promo_codes
+WHERE
+    customer_id = $1
+AND
+    code = $2
+AND
+    order_id IS NULL
+AND
+    expire_time > CURRENT_TIMESTAMP;
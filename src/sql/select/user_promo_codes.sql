@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    promo_codes
+WHERE
+    customer_id = $1
+ORDER BY
+    create_time
+DESC;
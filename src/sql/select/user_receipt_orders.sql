@@ -0,0 +1,13 @@
+SELECT
+    *
+FROM
+    all_orders AS orders
+WHERE
+    customer_id = $1
+AND
+    EXTRACT(YEAR FROM create_time)::integer = $2
+AND
+    -- Cancelled orders are charged only if there is a fee.
+    (status = 'Delivered' OR (status = 'Cancelled' AND cancellation_fee > 0))
+ORDER BY
+    create_time;
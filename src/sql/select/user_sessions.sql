@@ -0,0 +1,11 @@
+SELECT
+    *,
+    ip_address IS NOT DISTINCT FROM $2::character varying
+        AND user_agent IS NOT DISTINCT FROM $3::text AS is_current
+FROM
+    sessions
+WHERE
+    user_id = $1
+ORDER BY
+    last_seen
+DESC;
@@ -1,4 +1,20 @@
 SELECT
-    *
+    users.*,
+    customer_segments.segment
 FROM
-    users;
+    users
+LEFT JOIN
+    customer_segments
+ON
+    customer_segments.user_id = users.id
+WHERE
+    -- NULL means any role.
+    ($1::"UserRole" IS NULL OR role = $1)
+-- Placeholders in curly braces are replaced according to the sorting parameters.
+ORDER BY
+    {sort_column} {direction} NULLS LAST,
+    id {direction}
+LIMIT
+    $2
+OFFSET
+    $3;
@@ -3,4 +3,4 @@ SELECT
 FROM
     users
 WHERE
-    id = $1;
+    id = ANY($1);
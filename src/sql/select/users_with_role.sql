@@ -0,0 +1,13 @@
+SELECT
+    users.*
+FROM
+    users
+LEFT JOIN
+    customer_segments
+ON
+    customer_segments.user_id = users.id
+WHERE
+    role = $1
+AND
+    -- NULL means any segment.
+    ($2::"CustomerSegment" IS NULL OR customer_segments.segment = $2);
@@ -0,0 +1,39 @@
+-- The customer is notified in the same statement, except for gifts:
+-- their delivery concerns the recipient.
+WITH picked_up_order AS
+(
+    UPDATE
+        orders
+    SET
+        status = 'PickedUp'
+    WHERE
+        id = $1
+    AND
+        rider_id = $2
+    AND
+        status = 'Accepted'
+    RETURNING
+        id,
+        customer_id,
+        gift_recipient_name IS NOT NULL AS is_gift
+),
+notification AS
+(
+    INSERT INTO notifications
+    (
+        user_id,
+        sent_time,
+        title,
+        description
+    )
+    SELECT
+        customer_id,
+        CURRENT_TIMESTAMP,
+        $3,
+        $4
+    FROM
+        picked_up_order
+    WHERE
+        NOT is_gift
+)
+SELECT id FROM picked_up_order;
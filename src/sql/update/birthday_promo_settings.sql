@@ -0,0 +1,11 @@
+INSERT INTO settings
+(
+    is_birthday_promo_enabled,
+    birthday_discount_percent,
+    birthday_promo_days
+)
+VALUES ($1, $2, $3)
+ON CONFLICT (id) DO UPDATE SET
+    is_birthday_promo_enabled = EXCLUDED.is_birthday_promo_enabled,
+    birthday_discount_percent = EXCLUDED.birthday_discount_percent,
+    birthday_promo_days = EXCLUDED.birthday_promo_days;
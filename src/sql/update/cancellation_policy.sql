@@ -0,0 +1,9 @@
+INSERT INTO settings
+(
+    accepted_cancellation_fee_percent,
+    picked_up_cancellation_fee_percent
+)
+VALUES ($1, $2)
+ON CONFLICT (id) DO UPDATE SET
+    accepted_cancellation_fee_percent = EXCLUDED.accepted_cancellation_fee_percent,
+    picked_up_cancellation_fee_percent = EXCLUDED.picked_up_cancellation_fee_percent;
@@ -0,0 +1,115 @@
+-- Available items are returned to the stock, and the assigned rider and,
+-- if the description $7 is passed, the customer are notified in the same statement.
+WITH cancelled_order AS
+(
+    UPDATE
+        orders
+    SET
+        status = 'Cancelled',
+        cancel_time = CURRENT_TIMESTAMP,
+        cancellation_fee = $3,
+        cancellation_reason = $4
+    WHERE
+        id = $1
+    AND
+        status = $2
+    RETURNING
+        id,
+        customer_id,
+        rider_id,
+        location_id
+),
+returned_items AS
+(
+    SELECT
+        orders_food.food_id,
+        orders_food.count
+    FROM
+        orders_food,
+        cancelled_order
+    WHERE
+        orders_food.order_id = cancelled_order.id
+    AND
+        NOT orders_food.is_unavailable
+),
+updated_food AS
+(
+    UPDATE
+        food
+    SET
+        count = food.count + returned_items.count
+    FROM
+        returned_items
+    WHERE
+        food.id = returned_items.food_id
+    RETURNING
+        food.id,
+        food.count,
+        returned_items.count AS returned
+),
+updated_location_stock AS
+(
+    UPDATE
+        location_stock
+    SET
+        count = location_stock.count + returned_items.count
+    FROM
+        returned_items,
+        cancelled_order
+    WHERE
+        location_stock.location_id = cancelled_order.location_id
+    AND
+        location_stock.food_id = returned_items.food_id
+),
+recorded_movements AS
+(
+    INSERT INTO stock_movements
+    (
+        food_id,
+        "time",
+        kind,
+        delta,
+        count_after,
+        order_id
+    )
+    SELECT
+        updated_food.id,
+        CURRENT_TIMESTAMP,
+        'OrderCancellation',
+        updated_food.returned,
+        updated_food.count,
+        cancelled_order.id
+    FROM
+        updated_food,
+        cancelled_order
+),
+notification AS
+(
+    INSERT INTO notifications
+    (
+        user_id,
+        sent_time,
+        title,
+        description
+    )
+    SELECT
+        rider_id,
+        CURRENT_TIMESTAMP,
+        $5::text,
+        $6::text
+    FROM
+        cancelled_order
+    WHERE
+        rider_id IS NOT NULL
+    UNION ALL
+    SELECT
+        customer_id,
+        CURRENT_TIMESTAMP,
+        $5::text,
+        $7::text
+    FROM
+        cancelled_order
+    WHERE
+        $7::text IS NOT NULL
+)
+SELECT id FROM cancelled_order;
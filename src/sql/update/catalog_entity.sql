@@ -0,0 +1,13 @@
+-- Placeholders in curly braces are replaced according to the entity.
+UPDATE
+    {table}
+SET
+    ({columns}) =
+    (
+        SELECT
+            {columns}
+        FROM
+            jsonb_populate_record(NULL::{table}, $2)
+    )
+WHERE
+    id = $1;
@@ -0,0 +1,9 @@
+UPDATE
+    categories
+SET
+    title = $2,
+    description = $3,
+    -- Preview is kept if $4 is false.
+    preview = CASE WHEN $4 THEN $5 ELSE preview END
+WHERE
+    id = $1;
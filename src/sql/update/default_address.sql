@@ -1,5 +1,7 @@
-DELETE FROM
+UPDATE
     addresses
+SET
+    is_default = true
 WHERE
     customer_id = $1
 AND
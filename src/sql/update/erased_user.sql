@@ -0,0 +1,181 @@
+-- Orders are kept for accounting, so the user row, addresses of orders, gift
+-- recipients and comments are anonymized instead of being deleted.
+-- Nothing is changed if the user has orders in progress.
+WITH erased_user AS
+(
+    UPDATE
+        users
+    SET
+        username = $2,
+        password = $3,
+        first_name = NULL,
+        last_name = NULL,
+        -- Column can't be NULL, so a placeholder is used.
+        birth_date = '1900-01-01',
+        erased_time = CURRENT_TIMESTAMP
+    WHERE
+        id = $1
+    AND NOT EXISTS
+    (
+        SELECT
+            1
+        FROM
+            orders
+        WHERE
+            customer_id = $1
+        AND
+            status NOT IN ('Delivered', 'Cancelled')
+    )
+    RETURNING
+        id
+),
+deleted_addresses AS
+(
+    DELETE FROM
+        addresses
+    WHERE
+        customer_id IN (SELECT id FROM erased_user)
+    AND NOT EXISTS
+    (
+        SELECT
+            1
+        FROM
+            all_orders AS orders
+        WHERE
+            address_id = addresses.id
+    )
+),
+scrubbed_addresses AS
+(
+    UPDATE
+        addresses
+    SET
+        locality = '',
+        street = '',
+        house = 0,
+        corps = NULL,
+        apartment = NULL,
+        is_default = false
+    WHERE
+        customer_id IN (SELECT id FROM erased_user)
+    AND EXISTS
+    (
+        SELECT
+            1
+        FROM
+            all_orders AS orders
+        WHERE
+            address_id = addresses.id
+    )
+),
+-- Addresses which gift recipients entered aren't owned by anybody.
+scrubbed_recipient_addresses AS
+(
+    UPDATE
+        addresses
+    SET
+        locality = '',
+        street = '',
+        house = 0,
+        corps = NULL,
+        apartment = NULL
+    WHERE
+        customer_id IS NULL
+    AND id IN
+    (
+        SELECT
+            address_id
+        FROM
+            all_orders AS orders
+        WHERE
+            customer_id IN (SELECT id FROM erased_user)
+    )
+),
+scrubbed_orders AS
+(
+    UPDATE
+        orders
+    SET
+        comment = NULL,
+        gift_recipient_name = NULL,
+        gift_recipient_phone = NULL,
+        gift_message = NULL
+    WHERE
+        customer_id IN (SELECT id FROM erased_user)
+),
+scrubbed_feedbacks AS
+(
+    UPDATE
+        feedbacks
+    SET
+        comment = NULL
+    WHERE
+        order_id IN (SELECT id FROM orders WHERE customer_id IN (SELECT id FROM erased_user))
+),
+scrubbed_archived_orders AS
+(
+    UPDATE
+        orders_archive
+    SET
+        comment = NULL,
+        gift_recipient_name = NULL,
+        gift_recipient_phone = NULL,
+        gift_message = NULL
+    WHERE
+        customer_id IN (SELECT id FROM erased_user)
+),
+scrubbed_archived_feedbacks AS
+(
+    UPDATE
+        feedbacks_archive
+    SET
+        comment = NULL
+    WHERE
+        order_id IN (SELECT id FROM orders_archive WHERE customer_id IN (SELECT id FROM erased_user))
+),
+deleted_cart AS
+(
+    DELETE FROM
+        cart
+    WHERE
+        customer_id IN (SELECT id FROM erased_user)
+),
+deleted_favorites AS
+(
+    DELETE FROM
+        favorites
+    WHERE
+        user_id IN (SELECT id FROM erased_user)
+),
+deleted_collections AS
+(
+    DELETE FROM
+        favorite_collections
+    WHERE
+        user_id IN (SELECT id FROM erased_user)
+),
+deleted_notifications AS
+(
+    DELETE FROM
+        notifications
+    WHERE
+        user_id IN (SELECT id FROM erased_user)
+),
+deleted_activities AS
+(
+    DELETE FROM
+        activities
+    WHERE
+        user_id IN (SELECT id FROM erased_user)
+),
+deleted_sessions AS
+(
+    DELETE FROM
+        sessions
+    WHERE
+        user_id IN (SELECT id FROM erased_user)
+)
+SELECT
+    id
+FROM
+    erased_user;
@@ -0,0 +1,10 @@
+UPDATE
+    favorites
+SET
+    collection_id = $3
+WHERE
+    user_id = $1
+AND
+    id = $2
+AND
+    delete_time IS NULL;
@@ -0,0 +1,7 @@
+UPDATE
+    feedbacks
+SET
+    rating = $2,
+    comment = $3
+WHERE
+    id = $1;
@@ -0,0 +1,7 @@
+INSERT INTO settings
+(
+    feedback_edit_hours
+)
+VALUES ($1)
+ON CONFLICT (id) DO UPDATE SET
+    feedback_edit_hours = EXCLUDED.feedback_edit_hours;
@@ -0,0 +1,79 @@
+-- Placeholder in curly braces is replaced by the list of changed columns.
+-- If the count is changed, the difference with the previous count is recorded
+-- as a correction of the stock. A new price is added to the history.
+WITH previous AS
+(
+    -- Locked, so the stock isn't changed by orders until the count is set.
+    SELECT
+        id,
+        count
+    FROM
+        food
+    WHERE
+        id = $1
+    FOR UPDATE
+),
+updated AS
+(
+    UPDATE
+        food
+    SET
+        {assignments}
+    FROM
+        previous
+    WHERE
+        food.id = previous.id
+    RETURNING
+        food.id,
+        food.count,
+        food.count - previous.count AS delta,
+        food.price
+),
+recorded_movement AS
+(
+    INSERT INTO stock_movements
+    (
+        food_id,
+        "time",
+        kind,
+        delta,
+        count_after,
+        manager_id
+    )
+    SELECT
+        id,
+        CURRENT_TIMESTAMP,
+        'Correction',
+        delta,
+        count,
+        (SELECT id FROM users WHERE username = $2)
+    FROM
+        updated
+    WHERE
+        delta <> 0
+),
+recorded_price AS
+(
+    INSERT INTO price_history (food_id, price)
+    SELECT
+        id,
+        price
+    FROM
+        updated
+    WHERE
+        price IS DISTINCT FROM (
+            SELECT
+                price
+            FROM
+                price_history
+            WHERE
+                food_id = $1
+            ORDER BY
+                change_time DESC,
+                id DESC
+            LIMIT 1)
+)
+SELECT
+    id
+FROM
+    updated;
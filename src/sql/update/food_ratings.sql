@@ -0,0 +1,30 @@
+-- Recalculates ratings of food in the order. Items which were unavailable
+-- and excluded from the order don't get its rating. Hidden feedbacks aren't counted.
+UPDATE
+    food
+SET
+    average_rating = stats.average_rating,
+    ratings_count = stats.ratings_count
+FROM
+    (
+        SELECT
+            orders_food.food_id,
+            avg(feedbacks.rating)::double precision AS average_rating,
+            count(feedbacks.rating)::integer AS ratings_count
+        FROM
+            all_orders_food AS orders_food
+        LEFT JOIN
+            all_feedbacks AS feedbacks
+        ON
+            feedbacks.order_id = orders_food.order_id
+        AND
+            NOT orders_food.is_unavailable
+        AND
+            NOT feedbacks.is_hidden
+        WHERE
+            orders_food.food_id IN (SELECT food_id FROM all_orders_food WHERE order_id = $1)
+        GROUP BY
+            orders_food.food_id
+    ) AS stats
+WHERE
+    food.id = stats.food_id;
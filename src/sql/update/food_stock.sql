@@ -0,0 +1,36 @@
+WITH
+    updated AS
+    (
+        UPDATE
+            food
+        SET
+            count = count + $3
+        WHERE
+            id = $1
+        RETURNING
+            id,
+            count
+    )
+INSERT INTO stock_movements
+(
+    food_id,
+    "time",
+    kind,
+    delta,
+    count_after,
+    order_id,
+    manager_id,
+    comment
+)
+SELECT
+    id,
+    CURRENT_TIMESTAMP,
+    $2,
+    $3,
+    count,
+    $4,
+    (SELECT id FROM users WHERE username = $5),
+    $6
+FROM
+    updated
+RETURNING count_after;
@@ -0,0 +1,18 @@
+-- Only delivery gifts which are waiting for the recipient address can get a link.
+UPDATE
+    orders
+SET
+    gift_address_token = $3,
+    gift_address_expire_time = CURRENT_TIMESTAMP + make_interval(hours => $4)
+WHERE
+    id = $1
+AND
+    customer_id = $2
+AND
+    gift_recipient_name IS NOT NULL
+AND
+    fulfillment = 'Delivery'
+AND
+    address_id IS NULL
+AND
+    status NOT IN ('Delivered', 'Cancelled');
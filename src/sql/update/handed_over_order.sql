@@ -0,0 +1,11 @@
+UPDATE
+    orders
+SET
+    completed_time = CURRENT_TIMESTAMP,
+    status = 'Delivered'
+WHERE
+    id = $1
+AND
+    status = 'ReadyForPickup'
+AND
+    pickup_code = $2;
@@ -0,0 +1,28 @@
+-- Feedback can be archived along with its order.
+WITH hidden AS
+(
+    UPDATE
+        feedbacks
+    SET
+        is_hidden = true,
+        hidden_reason = $2
+    WHERE
+        id = $1
+    RETURNING
+        order_id
+),
+hidden_archived AS
+(
+    UPDATE
+        feedbacks_archive
+    SET
+        is_hidden = true,
+        hidden_reason = $2
+    WHERE
+        id = $1
+    RETURNING
+        order_id
+)
+SELECT order_id FROM hidden
+UNION ALL
+SELECT order_id FROM hidden_archived;
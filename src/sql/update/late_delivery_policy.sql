@@ -0,0 +1,13 @@
+INSERT INTO settings
+(
+    promised_delivery_minutes,
+    late_delivery_tolerance_minutes,
+    late_delivery_discount_percent,
+    late_delivery_promo_days
+)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (id) DO UPDATE SET
+    promised_delivery_minutes = EXCLUDED.promised_delivery_minutes,
+    late_delivery_tolerance_minutes = EXCLUDED.late_delivery_tolerance_minutes,
+    late_delivery_discount_percent = EXCLUDED.late_delivery_discount_percent,
+    late_delivery_promo_days = EXCLUDED.late_delivery_promo_days;
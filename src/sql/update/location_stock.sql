@@ -0,0 +1,25 @@
+-- Sets the count and returns the difference with the previous one.
+WITH
+    previous AS
+    (
+        SELECT
+            count
+        FROM
+            location_stock
+        WHERE
+            location_id = $1
+        AND
+            food_id = $2
+    )
+INSERT INTO location_stock
+(
+    location_id,
+    food_id,
+    count
+)
+VALUES ($1, $2, $3)
+ON CONFLICT (location_id, food_id) DO UPDATE
+SET
+    count = EXCLUDED.count
+RETURNING
+    $3::integer - COALESCE((SELECT count FROM previous), 0);
@@ -0,0 +1,9 @@
+INSERT INTO settings
+(
+    is_maintenance,
+    maintenance_message
+)
+VALUES ($1, $2)
+ON CONFLICT (id) DO UPDATE SET
+    is_maintenance = EXCLUDED.is_maintenance,
+    maintenance_message = EXCLUDED.maintenance_message;
@@ -0,0 +1,168 @@
+-- Moves orders (including archived ones), addresses, favorites with their collections and promo codes of the source customer to the target
+-- one, deletes the source account and records the merge in a single statement.
+-- Favorites which the target already has are deleted together with the source account.
+WITH source AS
+(
+    SELECT
+        id,
+        username
+    FROM
+        users
+    WHERE
+        id = $1
+    AND
+        id <> $2
+    AND
+        role = 'Customer'
+),
+target AS
+(
+    SELECT
+        id
+    FROM
+        users
+    WHERE
+        id = $2
+    AND
+        role = 'Customer'
+),
+moved_orders AS
+(
+    UPDATE
+        orders
+    SET
+        customer_id = target.id
+    FROM
+        source,
+        target
+    WHERE
+        orders.customer_id = source.id
+    RETURNING
+        orders.id
+),
+moved_archived_orders AS
+(
+    UPDATE
+        orders_archive
+    SET
+        customer_id = target.id
+    FROM
+        source,
+        target
+    WHERE
+        orders_archive.customer_id = source.id
+    RETURNING
+        orders_archive.id
+),
+moved_addresses AS
+(
+    UPDATE
+        addresses
+    SET
+        customer_id = target.id,
+        -- Default address of the target is kept.
+        is_default = addresses.is_default AND NOT EXISTS
+        (
+            SELECT
+                1
+            FROM
+                addresses AS target_addresses
+            WHERE
+                target_addresses.customer_id = target.id
+            AND
+                target_addresses.is_default
+        )
+    FROM
+        source,
+        target
+    WHERE
+        addresses.customer_id = source.id
+    RETURNING
+        addresses.id
+),
+moved_collections AS
+(
+    UPDATE
+        favorite_collections
+    SET
+        user_id = target.id
+    FROM
+        source,
+        target
+    WHERE
+        favorite_collections.user_id = source.id
+    RETURNING
+        favorite_collections.id
+),
+moved_favorites AS
+(
+    UPDATE
+        favorites
+    SET
+        user_id = target.id
+    FROM
+        source,
+        target
+    WHERE
+        favorites.user_id = source.id
+    AND NOT EXISTS
+    (
+        SELECT
+            1
+        FROM
+            favorites AS target_favorites
+        WHERE
+            target_favorites.user_id = target.id
+        AND
+            target_favorites.food_id = favorites.food_id
+    )
+    RETURNING
+        favorites.id
+),
+moved_promo_codes AS
+(
+    UPDATE
+        promo_codes
+    SET
+        customer_id = target.id
+    FROM
+        source,
+        target
+    WHERE
+        promo_codes.customer_id = source.id
+),
+deleted_user AS
+(
+    DELETE FROM
+        users
+    USING
+        source,
+        target
+    WHERE
+        users.id = source.id
+)
+INSERT INTO user_merges
+(
+    source_username,
+    target_user_id,
+    manager_id,
+    merge_time,
+    order_count,
+    address_count,
+    favorite_count
+)
+SELECT
+    source.username,
+    target.id,
+    $3,
+    CURRENT_TIMESTAMP,
+    (SELECT count(*) FROM moved_orders) + (SELECT count(*) FROM moved_archived_orders),
+    (SELECT count(*) FROM moved_addresses),
+    (SELECT count(*) FROM moved_favorites)
+FROM
+    source,
+    target
+RETURNING
+    order_count,
+    address_count,
+    favorite_count;
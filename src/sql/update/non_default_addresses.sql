@@ -0,0 +1,10 @@
+UPDATE
+    addresses
+SET
+    is_default = false
+WHERE
+    customer_id = $1
+AND
+    is_default
+AND
+    id != $2;
@@ -0,0 +1,7 @@
+INSERT INTO settings
+(
+    order_capacity
+)
+VALUES ($1)
+ON CONFLICT (id) DO UPDATE SET
+    order_capacity = EXCLUDED.order_capacity;
@@ -0,0 +1,13 @@
+INSERT INTO settings
+(
+    opening_time,
+    closing_time,
+    min_schedule_minutes,
+    dispatch_minutes
+)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (id) DO UPDATE SET
+    opening_time = EXCLUDED.opening_time,
+    closing_time = EXCLUDED.closing_time,
+    min_schedule_minutes = EXCLUDED.min_schedule_minutes,
+    dispatch_minutes = EXCLUDED.dispatch_minutes;
@@ -0,0 +1,42 @@
+-- Moves the oldest queued orders to the 'Created' status while there are free slots.
+UPDATE
+    orders
+SET
+    status = 'Created'
+WHERE
+    id IN
+    (
+        SELECT
+            id
+        FROM
+            orders
+        WHERE
+            status = 'Queued'
+        ORDER BY
+            create_time,
+            id
+        -- NULL means no limit.
+        LIMIT
+        (
+            SELECT
+                CASE
+                    WHEN order_capacity IS NULL THEN NULL
+                    ELSE greatest(
+                        order_capacity - (
+                            SELECT
+                                count(*)
+                            FROM
+                                orders
+                            WHERE
+                                status IN ('Created', 'Accepted', 'PickedUp', 'ReadyForPickup')
+                        ),
+                        0
+                    )
+                END
+            FROM
+                settings
+        )
+    )
+RETURNING
+    id,
+    customer_id;
@@ -0,0 +1,10 @@
+UPDATE
+    notifications
+SET
+    read_time = CURRENT_TIMESTAMP
+WHERE
+    user_id = $1
+AND
+    id = $2
+AND
+    read_time IS NULL;
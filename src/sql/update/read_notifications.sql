@@ -0,0 +1,8 @@
+UPDATE
+    notifications
+SET
+    read_time = CURRENT_TIMESTAMP
+WHERE
+    user_id = $1
+AND
+    read_time IS NULL;
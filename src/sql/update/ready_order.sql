@@ -0,0 +1,13 @@
+UPDATE
+    orders
+SET
+    status = 'ReadyForPickup'
+WHERE
+    id = $1
+AND
+    status = 'Created'
+AND
+    fulfillment = 'Pickup'
+RETURNING
+    customer_id,
+    pickup_code;
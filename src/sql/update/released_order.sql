@@ -0,0 +1,16 @@
+-- Returns the order taken by a rider back to the pool.
+-- NULL rider ID allows releasing an order of any rider.
+UPDATE
+    orders
+SET
+    rider_id = NULL,
+    status = 'Created',
+    accept_time = NULL
+WHERE
+    id = $1
+AND
+    ($2::integer IS NULL OR rider_id = $2)
+AND
+    status IN ('Accepted', 'PickedUp')
+RETURNING
+    id;
@@ -0,0 +1,8 @@
+UPDATE
+    incidents
+SET
+    resolve_time = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+AND
+    resolve_time IS NULL;
@@ -0,0 +1,10 @@
+UPDATE
+    addresses
+SET
+    delete_time = NULL
+WHERE
+    customer_id = $1
+AND
+    id = $2
+AND
+    delete_time > CURRENT_TIMESTAMP - make_interval(days => $3::integer);
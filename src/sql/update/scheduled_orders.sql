@@ -0,0 +1,9 @@
+-- Queues scheduled orders whose dispatch window has opened.
+UPDATE
+    orders
+SET
+    status = 'Queued'
+WHERE
+    status = 'Scheduled'
+AND
+    scheduled_for - make_interval(mins => $1) <= CURRENT_TIMESTAMP;
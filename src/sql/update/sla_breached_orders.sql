@@ -0,0 +1,28 @@
+-- Flags delivery orders in progress which aren't accepted or delivered in time.
+-- Each order is flagged once. Targets of scheduled orders are counted from the scheduled time.
+UPDATE
+    orders
+SET
+    sla_breach_time = CURRENT_TIMESTAMP
+WHERE
+    sla_breach_time IS NULL
+AND
+    fulfillment = 'Delivery'
+AND
+    status NOT IN ('Delivered', 'Cancelled')
+AND
+    (
+        (
+            accept_time IS NULL
+        AND
+            COALESCE(scheduled_for, create_time) + make_interval(mins => $1::integer) < CURRENT_TIMESTAMP
+        )
+    OR
+        COALESCE(scheduled_for, create_time) + make_interval(mins => $2::integer) < CURRENT_TIMESTAMP
+    )
+RETURNING
+    id,
+    -- Otherwise the delivery target is breached.
+    accept_time IS NULL
+        AND COALESCE(scheduled_for, create_time) + make_interval(mins => $1::integer) < CURRENT_TIMESTAMP
+        AS is_accept_breached;
@@ -0,0 +1,9 @@
+INSERT INTO settings
+(
+    sla_accept_minutes,
+    sla_delivery_minutes
+)
+VALUES ($1, $2)
+ON CONFLICT (id) DO UPDATE SET
+    sla_accept_minutes = EXCLUDED.sla_accept_minutes,
+    sla_delivery_minutes = EXCLUDED.sla_delivery_minutes;
@@ -1,8 +1,40 @@
-UPDATE
-    orders
-SET
-    completed_time = CURRENT_TIMESTAMP
-WHERE
-    id = $1
-AND
-    rider_id = $2;
+-- The customer is notified in the same statement, except for gifts:
+-- their delivery concerns the recipient.
+WITH delivered_order AS
+(
+    UPDATE
+        orders
+    SET
+        completed_time = CURRENT_TIMESTAMP,
+        status = 'Delivered'
+    WHERE
+        id = $1
+    AND
+        rider_id = $2
+    AND
+        status = 'PickedUp'
+    RETURNING
+        id,
+        customer_id,
+        gift_recipient_name IS NOT NULL AS is_gift
+),
+notification AS
+(
+    INSERT INTO notifications
+    (
+        user_id,
+        sent_time,
+        title,
+        description
+    )
+    SELECT
+        customer_id,
+        CURRENT_TIMESTAMP,
+        $3,
+        $4
+    FROM
+        delivered_order
+    WHERE
+        NOT is_gift
+)
+SELECT id FROM delivered_order;
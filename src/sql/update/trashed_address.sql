@@ -0,0 +1,11 @@
+UPDATE
+    addresses
+SET
+    delete_time = CURRENT_TIMESTAMP,
+    is_default = false
+WHERE
+    customer_id = $1
+AND
+    id = $2
+AND
+    delete_time IS NULL;
@@ -0,0 +1,10 @@
+UPDATE
+    favorites
+SET
+    delete_time = CURRENT_TIMESTAMP
+WHERE
+    user_id = $1
+AND
+    id = $2
+AND
+    delete_time IS NULL;
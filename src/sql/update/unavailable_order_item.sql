@@ -0,0 +1,44 @@
+-- The customer is notified in the same statement. Placeholders of the
+-- description $3 are substituted with the title of the food and the order ID.
+WITH unavailable_item AS
+(
+    UPDATE
+        orders_food
+    SET
+        is_unavailable = TRUE
+    FROM
+        orders,
+        food
+    WHERE
+        orders_food.id = $1
+    AND
+        NOT orders_food.is_unavailable
+    AND
+        orders_food.order_id = orders.id
+    AND
+        orders.status IN ('Queued', 'Created', 'Accepted', 'PickedUp')
+    AND
+        orders_food.food_id = food.id
+    RETURNING
+        orders.id AS order_id,
+        orders.customer_id,
+        food.title AS food_title
+),
+notification AS
+(
+    INSERT INTO notifications
+    (
+        user_id,
+        sent_time,
+        title,
+        description
+    )
+    SELECT
+        customer_id,
+        CURRENT_TIMESTAMP,
+        $2,
+        format($3::text, food_title, order_id)
+    FROM
+        unavailable_item
+)
+SELECT order_id FROM unavailable_item;
@@ -1,8 +1,43 @@
-UPDATE
-    orders
-SET
-    rider_id = $1
-WHERE
-    id = $2
-AND
-    rider_id IS NULL;
+-- The customer is notified in the same statement, except for gifts:
+-- their delivery concerns the recipient.
+WITH taken_order AS
+(
+    UPDATE
+        orders
+    SET
+        rider_id = $1,
+        status = 'Accepted',
+        accept_time = CURRENT_TIMESTAMP
+    WHERE
+        id = $2
+    AND
+        status = 'Created'
+    AND
+        fulfillment = 'Delivery'
+    AND
+        address_id IS NOT NULL
+    RETURNING
+        id,
+        customer_id,
+        gift_recipient_name IS NOT NULL AS is_gift
+),
+notification AS
+(
+    INSERT INTO notifications
+    (
+        user_id,
+        sent_time,
+        title,
+        description
+    )
+    SELECT
+        customer_id,
+        CURRENT_TIMESTAMP,
+        $3,
+        $4
+    FROM
+        taken_order
+    WHERE
+        NOT is_gift
+)
+SELECT id FROM taken_order;
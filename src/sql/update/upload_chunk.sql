@@ -0,0 +1,13 @@
+-- Chunk is appended only if the offset matches, so retried chunks aren't duplicated.
+UPDATE
+    uploads
+SET
+    data = data || $4
+WHERE
+    token = $1
+AND
+    user_id = $2
+AND
+    octet_length(data) = $3
+RETURNING
+    octet_length(data) AS "offset";
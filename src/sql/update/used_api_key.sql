@@ -0,0 +1,17 @@
+UPDATE
+    api_keys
+SET
+    last_used_time = CURRENT_TIMESTAMP,
+    request_count = request_count + 1,
+    -- Requests are counted per calendar minute.
+    quota_window_requests = CASE
+        WHEN quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+        THEN quota_window_requests + 1
+        ELSE 1
+    END,
+    quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+WHERE
+    key_hash = $1
+RETURNING
+    scope,
+    quota_window_requests;
@@ -0,0 +1,7 @@
+-- Placeholder in curly braces is replaced by the list of changed columns.
+UPDATE
+    users
+SET
+    {assignments}
+WHERE
+    username = $1;
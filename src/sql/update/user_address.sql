@@ -0,0 +1,14 @@
+UPDATE
+    addresses
+SET
+    locality = $3,
+    street = $4,
+    house = $5,
+    corps = $6,
+    apartment = $7
+WHERE
+    customer_id = $1
+AND
+    id = $2
+AND
+    delete_time IS NULL;
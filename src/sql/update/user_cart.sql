@@ -0,0 +1,8 @@
+UPDATE
+    cart
+SET
+    count = $3
+WHERE
+    customer_id = $1
+AND
+    id = $2;
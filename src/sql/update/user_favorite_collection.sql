@@ -0,0 +1,8 @@
+UPDATE
+    favorite_collections
+SET
+    title = $3
+WHERE
+    user_id = $1
+AND
+    id = $2;
@@ -0,0 +1,6 @@
+UPDATE
+    users
+SET
+    password = $2
+WHERE
+    username = $1;
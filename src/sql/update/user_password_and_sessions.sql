@@ -0,0 +1,30 @@
+-- Sessions are authenticated using the password, so all of them except the one
+-- of the current device are deleted together with changing the password.
+WITH updated AS
+(
+    UPDATE
+        users
+    SET
+        password = $4
+    WHERE
+        id = $1
+    RETURNING
+        id
+),
+deleted_sessions AS
+(
+    DELETE FROM
+        sessions
+    WHERE
+        user_id IN (SELECT id FROM updated)
+    AND NOT
+    (
+        ip_address IS NOT DISTINCT FROM $2::character varying
+        AND
+        user_agent IS NOT DISTINCT FROM $3::text
+    )
+)
+SELECT
+    id
+FROM
+    updated;
@@ -0,0 +1,16 @@
+UPDATE
+    users
+SET
+    request_count = request_count + 1,
+    last_request_time = CURRENT_TIMESTAMP,
+    -- Requests are counted per calendar minute.
+    quota_window_requests = CASE
+        WHEN quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+        THEN quota_window_requests + 1
+        ELSE 1
+    END,
+    quota_window_start = date_trunc('minute', LOCALTIMESTAMP)
+WHERE
+    username = $1
+RETURNING
+    quota_window_requests;
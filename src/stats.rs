@@ -0,0 +1,291 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Execution statistics of GraphQL operations and root fields collected by a schema
+//! extension, and of HTTP requests collected by a middleware.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_graphql::{
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest,
+        NextRequest, NextResolve, ResolveInfo,
+    },
+    parser::types::{DocumentOperations, ExecutableDocument},
+    Request, Response, ServerResult, Value, Variables,
+};
+use async_trait::async_trait;
+
+use crate::types::OperationStats;
+
+/// Operations with names beyond the limit are counted together, as clients
+/// choose the names and could fill the memory otherwise.
+const MAX_TRACKED_OPERATIONS: usize = 500;
+const ANONYMOUS_OPERATION: &str = "anonymous";
+const OTHER_OPERATIONS: &str = "other";
+
+/// Type and value of an exported metric. Each family has a metric of each kind.
+type Metric = (&'static str, fn(&Totals) -> f64);
+
+const METRICS: [Metric; 4] = [
+    ("counter", |totals| totals.count as f64),
+    ("counter", |totals| totals.error_count as f64),
+    ("counter", |totals| totals.duration.as_secs_f64()),
+    ("gauge", |totals| totals.max_duration.as_secs_f64()),
+];
+/// Names of the metrics in the order of [METRICS].
+const OPERATION_METRICS: [&str; 4] = [
+    "graphql_operations_total",
+    "graphql_operation_errors_total",
+    "graphql_operation_duration_seconds_sum",
+    "graphql_operation_duration_seconds_max",
+];
+const FIELD_METRICS: [&str; 4] = [
+    "graphql_root_fields_total",
+    "graphql_root_field_errors_total",
+    "graphql_root_field_duration_seconds_sum",
+    "graphql_root_field_duration_seconds_max",
+];
+/// Errors are responses with the 5xx status.
+const REQUEST_METRICS: [&str; 4] = [
+    "http_requests_total",
+    "http_request_errors_total",
+    "http_request_duration_seconds_sum",
+    "http_request_duration_seconds_max",
+];
+
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    count: u64,
+    error_count: u64,
+    duration: Duration,
+    max_duration: Duration,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// By operation names.
+    operations: HashMap<String, Totals>,
+    /// By parent types and names of the root fields, e.g. per-mutation totals.
+    /// Nested fields aren't tracked as they are resolved too often.
+    fields: HashMap<(String, String), Totals>,
+    /// By methods, route patterns and response statuses.
+    requests: HashMap<(String, String, u16), Totals>,
+}
+
+/// Totals since the server start. Cloned instances share the data.
+#[derive(Clone, Default)]
+pub struct ExecutionStats(Arc<Mutex<Registry>>);
+
+impl ExecutionStats {
+    /// Returns statistics of all operations, the most time-consuming first.
+    pub fn operations(&self) -> Vec<OperationStats> {
+        let mut operations: Vec<_> = self
+            .0
+            .lock()
+            .unwrap()
+            .operations
+            .iter()
+            .map(|(name, totals)| OperationStats {
+                operation_name: name.clone(),
+                count: totals.count as i64,
+                error_count: totals.error_count as i64,
+                error_rate: totals.error_count as f64 / totals.count as f64,
+                average_ms: totals.duration.as_secs_f64() * 1000.0 / totals.count as f64,
+                max_ms: totals.max_duration.as_secs_f64() * 1000.0,
+            })
+            .collect();
+        operations.sort_by(|a, b| {
+            (b.average_ms * b.count as f64).total_cmp(&(a.average_ms * a.count as f64))
+        });
+        operations
+    }
+
+    /// Formats statistics using the Prometheus text format.
+    pub fn to_prometheus(&self) -> String {
+        let registry = self.0.lock().unwrap();
+        let mut output = String::new();
+        write_family(
+            &mut output,
+            OPERATION_METRICS,
+            registry
+                .operations
+                .iter()
+                .map(|(name, totals)| (format!("operation=\"{}\"", escape_label(name)), totals)),
+        );
+        write_family(
+            &mut output,
+            FIELD_METRICS,
+            registry.fields.iter().map(|((parent_type, name), totals)| {
+                (format!("type=\"{parent_type}\",field=\"{name}\""), totals)
+            }),
+        );
+        write_family(
+            &mut output,
+            REQUEST_METRICS,
+            registry
+                .requests
+                .iter()
+                .map(|((method, path, status), totals)| {
+                    (
+                        format!(
+                            "method=\"{method}\",path=\"{}\",status=\"{status}\"",
+                            escape_label(path)
+                        ),
+                        totals,
+                    )
+                }),
+        );
+        output
+    }
+
+    /// Records the HTTP request. `path` must be the route pattern
+    /// rather than the actual path to not create a series per entity.
+    pub fn record_request(&self, method: &str, path: &str, status: u16, duration: Duration) {
+        let key = (method.to_string(), path.to_string(), status);
+        self.0
+            .lock()
+            .unwrap()
+            .requests
+            .entry(key)
+            .or_default()
+            .add(duration, status >= 500);
+    }
+
+    fn record(&self, name: String, duration: Duration, is_error: bool) {
+        let operations = &mut self.0.lock().unwrap().operations;
+        let name = if operations.len() >= MAX_TRACKED_OPERATIONS && !operations.contains_key(&name)
+        {
+            OTHER_OPERATIONS.to_string()
+        } else {
+            name
+        };
+        operations.entry(name).or_default().add(duration, is_error);
+    }
+
+    fn record_field(&self, parent_type: &str, name: &str, duration: Duration, is_error: bool) {
+        self.0
+            .lock()
+            .unwrap()
+            .fields
+            .entry((parent_type.to_string(), name.to_string()))
+            .or_default()
+            .add(duration, is_error);
+    }
+}
+
+impl Totals {
+    fn add(&mut self, duration: Duration, is_error: bool) {
+        self.count += 1;
+        self.error_count += u64::from(is_error);
+        self.duration += duration;
+        self.max_duration = self.max_duration.max(duration);
+    }
+}
+
+impl ExtensionFactory for ExecutionStats {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(StatsExtension {
+            stats: self.clone(),
+            requested_name: Mutex::default(),
+            document_name: Mutex::default(),
+        })
+    }
+}
+
+/// Created for each request.
+struct StatsExtension {
+    stats: ExecutionStats,
+    /// Specified by the client to choose the operation from the document.
+    requested_name: Mutex<Option<String>>,
+    /// Name of the operation if it's the only one in the document.
+    document_name: Mutex<Option<String>>,
+}
+
+#[async_trait]
+impl Extension for StatsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let start = Instant::now();
+        let response = next.run(ctx).await;
+        let name = self
+            .requested_name
+            .lock()
+            .unwrap()
+            .take()
+            .or_else(|| self.document_name.lock().unwrap().take())
+            .unwrap_or_else(|| ANONYMOUS_OPERATION.to_string());
+        self.stats.record(name, start.elapsed(), response.is_err());
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.path_node.parent.is_some() {
+            return next.run(ctx, info).await;
+        }
+        let (parent_type, name) = (info.parent_type, info.name);
+        let start = Instant::now();
+        let result = next.run(ctx, info).await;
+        self.stats
+            .record_field(parent_type, name, start.elapsed(), result.is_err());
+        result
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        *self.requested_name.lock().unwrap() = request.operation_name.clone();
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        if let DocumentOperations::Multiple(operations) = &document.operations {
+            if operations.len() == 1 {
+                *self.document_name.lock().unwrap() =
+                    operations.keys().next().map(ToString::to_string);
+            }
+        }
+        Ok(document)
+    }
+}
+
+/// Writes the metrics of the family, each series is labeled with `labels`.
+fn write_family<'a>(
+    output: &mut String,
+    names: [&str; 4],
+    series: impl Iterator<Item = (String, &'a Totals)> + Clone,
+) {
+    for (metric, (kind, value)) in names.iter().zip(METRICS) {
+        writeln!(output, "# TYPE {metric} {kind}").unwrap();
+        for (labels, totals) in series.clone() {
+            writeln!(output, "{metric}{{{labels}}} {}", value(totals)).unwrap();
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,128 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Termination of TLS by the server itself, so a reverse proxy isn't required.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use actix_web::{
+    http::{header, uri::Authority},
+    web::{self, ServiceConfig},
+    HttpRequest, HttpResponse,
+};
+use anyhow::{anyhow, Context};
+use rustls::{crypto::ring, pki_types::PrivateKeyDer, ServerConfig};
+use serde::Deserialize;
+
+/// HTTPS is served instead of HTTP if both files are specified.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file with the certificate chain, starting with the server certificate.
+    pub cert_file: Option<PathBuf>,
+    /// PEM file with the private key of the server certificate.
+    pub key_file: Option<PathBuf>,
+    /// If set, plain HTTP requests to this port are redirected to HTTPS.
+    pub redirect_port: Option<u16>,
+}
+
+impl TlsConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match (&self.cert_file, &self.key_file) {
+            (Some(_), None) | (None, Some(_)) => Err(anyhow!(
+                "server.tls.cert_file and server.tls.key_file must be specified together"
+            )),
+            (None, None) if self.redirect_port.is_some() => Err(anyhow!(
+                "server.tls.redirect_port requires the certificate and the key"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `None` if TLS isn't configured.
+    pub fn load(&self) -> anyhow::Result<Option<ServerConfig>> {
+        let (Some(cert_file), Some(key_file)) = (&self.cert_file, &self.key_file) else {
+            return Ok(None);
+        };
+        let certs = rustls_pemfile::certs(&mut open(cert_file)?)
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("invalid certificate file {}", cert_file.display()))?;
+        if certs.is_empty() {
+            return Err(anyhow!(
+                "there are no certificates in {}",
+                cert_file.display()
+            ));
+        }
+        let key: PrivateKeyDer = rustls_pemfile::private_key(&mut open(key_file)?)
+            .with_context(|| format!("invalid key file {}", key_file.display()))?
+            .ok_or_else(|| anyhow!("there is no private key in {}", key_file.display()))?;
+        // The provider is explicit, as the process-wide one is ambiguous if
+        // another dependency enables the other rustls backend.
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("certificate doesn't match the private key")?;
+        Ok(Some(config))
+    }
+}
+
+fn open(path: &Path) -> anyhow::Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
+    Ok(BufReader::new(file))
+}
+
+/// Redirects every request to the same path on `https_port`.
+pub fn configure_redirect(config: &mut ServiceConfig, https_port: u16) {
+    config.default_service(web::to(move |req: HttpRequest| async move {
+        redirect(&req, https_port)
+    }));
+}
+
+fn redirect(req: &HttpRequest, https_port: u16) -> HttpResponse {
+    let Some(host) = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok())
+    else {
+        return HttpResponse::BadRequest().body("Host header is required");
+    };
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    HttpResponse::PermanentRedirect()
+        .insert_header((
+            header::LOCATION,
+            redirect_location(host.host(), https_port, path),
+        ))
+        .finish()
+}
+
+fn redirect_location(host: &str, https_port: u16, path: &str) -> String {
+    match https_port {
+        443 => format!("https://{host}{path}"),
+        port => format!("https://{host}:{port}{path}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_location_replaces_port() {
+        assert_eq!(
+            redirect_location("example.com", 443, "/sign_up?birth_date=1990-01-01"),
+            "https://example.com/sign_up?birth_date=1990-01-01"
+        );
+        assert_eq!(redirect_location("[::1]", 8443, "/"), "https://[::1]:8443/");
+    }
+}
@@ -2,23 +2,247 @@
 // Contacts: <nikita.dudko.95@gmail.com>
 // Licensed under the MIT License.
 
-use std::cmp::Ordering;
+use std::{fmt::Display, ops::RangeInclusive};
 
-use async_graphql::{Enum, InputObject, SimpleObject};
-use chrono::{NaiveDate, NaiveDateTime};
+use async_graphql::{
+    connection::{self, CursorType},
+    ComplexObject, Context, Enum, InputObject, Json, MaybeUndefined, SimpleObject,
+};
+use base64::Engine;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use postgres_types::{FromSql, ToSql};
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
 
+use crate::{
+    error::AppError,
+    is_manager,
+    loader::{
+        self, AddressLoader, CategoryLoader, FoodLoader, LocationStockLoader, PriceHistoryLoader,
+        UserLoader,
+    },
+    receipt,
+};
+
 pub type ID = i32;
 
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+/// Number of days deleted addresses and favorites can be restored.
+pub const TRASH_RETENTION_DAYS: i32 = 30;
+pub const MAX_PAGE_SIZE: i64 = 100;
+/// Maximum number of characters in titles, names and other short strings.
+pub const MAX_TITLE_LENGTH: usize = 128;
+/// Maximum number of characters in descriptions and comments.
+pub const MAX_TEXT_LENGTH: usize = 4096;
+/// Customers see price changes for this number of days.
+pub const PRICE_HISTORY_DAYS: i64 = 30;
+/// Prices are stored as `numeric(7, 2)`.
+const MAX_PRICE: Decimal = Decimal::from_parts(9_999_999, 0, 0, false, 2);
+
+#[derive(Clone, Copy, InputObject)]
+pub struct Pagination {
+    /// Can't be greater than 100.
+    #[graphql(default_with = "DEFAULT_PAGE_SIZE")]
+    pub limit: i64,
+    #[graphql(default)]
+    pub offset: i64,
+}
+
+impl Pagination {
+    pub fn limit(&self) -> i64 {
+        self.limit.clamp(0, MAX_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.max(0)
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_PAGE_SIZE,
+            offset: 0,
+        }
+    }
+}
+
+/// Checks input before it's passed to the database,
+/// so clients get a field error instead of a constraint failure.
+pub trait Validate {
+    fn validate(&self) -> Result<(), AppError>;
+}
+
+/// Error of the input field, `message` is prefixed with the field name.
+pub fn invalid_input(field: &str, message: &str) -> AppError {
+    AppError::Validation {
+        field: Some(field.to_string()),
+        message: format!("{field} {message}"),
+    }
+}
+
+pub fn check_title(field: &str, value: &str) -> Result<(), AppError> {
+    if value.trim().is_empty() {
+        return Err(invalid_input(field, "must not be empty"));
+    }
+    check_length(field, Some(value), MAX_TITLE_LENGTH)
+}
+
+pub fn check_length(field: &str, value: Option<&str>, max_length: usize) -> Result<(), AppError> {
+    if value.is_some_and(|value| value.chars().count() > max_length) {
+        return Err(invalid_input(
+            field,
+            &format!("must not be longer than {max_length} characters"),
+        ));
+    }
+    Ok(())
+}
+
+pub fn check_range<T: PartialOrd + Display>(
+    field: &str,
+    value: T,
+    range: RangeInclusive<T>,
+) -> Result<(), AppError> {
+    if !range.contains(&value) {
+        return Err(invalid_input(
+            field,
+            &format!("must be between {} and {}", range.start(), range.end()),
+        ));
+    }
+    Ok(())
+}
+
+fn check_min<T: PartialOrd + Display>(field: &str, value: T, min: T) -> Result<(), AppError> {
+    if value < min {
+        return Err(invalid_input(field, &format!("must be at least {min}")));
+    }
+    Ok(())
+}
+
+fn check_birth_date(birth_date: NaiveDate) -> Result<(), AppError> {
+    if birth_date >= Utc::now().date_naive() {
+        return Err(invalid_input("birthDate", "must be in the past"));
+    }
+    Ok(())
+}
+
+/// Position of an item in a sorted list.
+pub struct Cursor {
+    /// Value of the sort key.
+    pub key: CursorKey,
+    pub id: ID,
+}
+
+impl CursorType for Cursor {
+    type Error = &'static str;
+
+    fn decode_cursor(cursor: &str) -> Result<Self, Self::Error> {
+        let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .ok_or("invalid cursor")?;
+        let mut parts = decoded.splitn(3, ':');
+        let (Some(id), Some(kind), Some(key)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err("invalid cursor");
+        };
+        Ok(Self {
+            key: CursorKey::parse(kind, key).ok_or("invalid cursor")?,
+            id: id.parse().map_err(|_| "invalid cursor")?,
+        })
+    }
+
+    /// Makes an opaque string that can be passed to a client.
+    fn encode_cursor(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}:{}",
+            self.id,
+            self.key.kind(),
+            self.key
+        ))
+    }
+}
+
+/// Type of the key is encoded in the cursor, so a forged key is rejected
+/// while decoding instead of failing to be cast by the database.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CursorKey {
+    Text(String),
+    Integer(i32),
+    Numeric(Decimal),
+    Double(f64),
+    Timestamp(NaiveDateTime),
+}
+
+impl CursorKey {
+    const TIMESTAMP_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S%.f";
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "t",
+            Self::Integer(_) => "i",
+            Self::Numeric(_) => "n",
+            Self::Double(_) => "d",
+            Self::Timestamp(_) => "ts",
+        }
+    }
+
+    fn parse(kind: &str, key: &str) -> Option<Self> {
+        Some(match kind {
+            "t" => Self::Text(key.to_string()),
+            "i" => Self::Integer(key.parse().ok()?),
+            "n" => Self::Numeric(key.parse().ok()?),
+            "d" => Self::Double(key.parse().ok().filter(|key: &f64| key.is_finite())?),
+            "ts" => {
+                Self::Timestamp(NaiveDateTime::parse_from_str(key, Self::TIMESTAMP_FORMAT).ok()?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// SQL type to which the key is cast in the page statements.
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "text",
+            Self::Integer(_) => "integer",
+            Self::Numeric(_) => "numeric",
+            Self::Double(_) => "double precision",
+            Self::Timestamp(_) => "timestamp",
+        }
+    }
+}
+
+impl Display for CursorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(key) => f.write_str(key),
+            Self::Integer(key) => key.fmt(f),
+            Self::Numeric(key) => key.fmt(f),
+            Self::Double(key) => key.fmt(f),
+            Self::Timestamp(key) => key.format(Self::TIMESTAMP_FORMAT).fmt(f),
+        }
+    }
+}
+
+pub type Connection<T> = connection::Connection<Cursor, T>;
+
 #[derive(Clone, Copy, PartialEq, Eq, Enum)]
 pub enum SortOrder {
     Ascending,
     Descending,
 }
 
+impl SortOrder {
+    pub fn sql(&self) -> &'static str {
+        match self {
+            Self::Ascending => "ASC",
+            Self::Descending => "DESC",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
 pub enum UserRole {
     Customer,
@@ -50,6 +274,36 @@ pub struct User {
     pub birth_date: NaiveDate,
     #[serde(skip)]
     pub role: UserRole,
+    /// Provided only by the `users` query for customers.
+    #[serde(skip)]
+    #[graphql(skip_input)]
+    pub segment: Option<CustomerSegment>,
+}
+
+/// Customers who are likely the same person: same full name and birth date.
+#[derive(SimpleObject)]
+pub struct DuplicateUsers {
+    /// Sorted by ID, so the first one is the oldest account.
+    pub users: Vec<User>,
+}
+
+/// Data moved from the merged account to the surviving one.
+#[derive(SimpleObject)]
+pub struct UserMerge {
+    pub order_count: i32,
+    pub address_count: i32,
+    /// Favorites which the surviving account already had aren't counted.
+    pub favorite_count: i32,
+}
+
+impl From<Row> for UserMerge {
+    fn from(row: Row) -> Self {
+        Self {
+            order_count: row.get("order_count"),
+            address_count: row.get("address_count"),
+            favorite_count: row.get("favorite_count"),
+        }
+    }
 }
 
 impl From<Row> for User {
@@ -62,144 +316,1307 @@ impl From<Row> for User {
             last_name: row.get("last_name"),
             birth_date: row.get("birth_date"),
             role: row.get("role"),
+            segment: row.try_get("segment").ok().flatten(),
+        }
+    }
+}
+
+impl Validate for User {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("username", &self.username)?;
+        check_length("firstName", self.first_name.as_deref(), MAX_TITLE_LENGTH)?;
+        check_length("lastName", self.last_name.as_deref(), MAX_TITLE_LENGTH)?;
+        check_birth_date(self.birth_date)
+    }
+}
+
+/// Fields which aren't specified are left unchanged.
+#[derive(InputObject)]
+#[graphql(name = "UserPatchInput")]
+pub struct UserPatch {
+    /// Set to `null` to clear the first name.
+    pub first_name: MaybeUndefined<String>,
+    /// Set to `null` to clear the last name.
+    pub last_name: MaybeUndefined<String>,
+    pub birth_date: Option<NaiveDate>,
+}
+
+impl UserPatch {
+    /// Returns pairs of column names and new values.
+    pub fn columns(&self) -> Vec<(&'static str, &(dyn ToSql + Sync))> {
+        let mut columns: Vec<(_, &(dyn ToSql + Sync))> = Vec::new();
+        match &self.first_name {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => columns.push(("first_name", &None::<String>)),
+            MaybeUndefined::Value(first_name) => columns.push(("first_name", first_name)),
+        }
+        match &self.last_name {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => columns.push(("last_name", &None::<String>)),
+            MaybeUndefined::Value(last_name) => columns.push(("last_name", last_name)),
+        }
+        if let Some(birth_date) = &self.birth_date {
+            columns.push(("birth_date", birth_date));
+        }
+        columns
+    }
+}
+
+impl Validate for UserPatch {
+    fn validate(&self) -> Result<(), AppError> {
+        check_length(
+            "firstName",
+            self.first_name.value().map(String::as_str),
+            MAX_TITLE_LENGTH,
+        )?;
+        check_length(
+            "lastName",
+            self.last_name.value().map(String::as_str),
+            MAX_TITLE_LENGTH,
+        )?;
+        self.birth_date.map_or(Ok(()), check_birth_date)
+    }
+}
+
+/// Computed from delivered orders of a customer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum CustomerSegment {
+    /// Has less than two orders.
+    New,
+    Regular,
+    /// Hasn't ordered for 60 days.
+    Lapsed,
+    /// Has at least 10 orders during the last 90 days.
+    Vip,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum ActivityKind {
+    SignUp,
+    /// Successful authentication from an IP address and user agent that weren't seen before.
+    NewDeviceLogin,
+    FailedLogin,
+    PasswordChange,
+    AddressAdded,
+    AddressDeleted,
+    /// A manager performed a request on behalf of the customer.
+    Impersonated,
+    /// The manager performed a request on behalf of a customer.
+    Impersonating,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct Activity {
+    pub id: ID,
+    pub time: NaiveDateTime,
+    pub kind: ActivityKind,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// Set for impersonation: the operation, the access mode and the other user.
+    pub details: Option<String>,
+}
+
+impl From<Row> for Activity {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            time: row.get("time"),
+            kind: row.get("kind"),
+            ip_address: row.get("ip_address"),
+            user_agent: row.get("user_agent"),
+            details: row.get("details"),
+        }
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct Session {
+    pub id: ID,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    /// Whether the session is opened from the device which sent the request.
+    pub is_current: bool,
+}
+
+impl From<Row> for Session {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            ip_address: row.get("ip_address"),
+            user_agent: row.get("user_agent"),
+            created: row.get("created"),
+            last_seen: row.get("last_seen"),
+            is_current: row.get("is_current"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum ApiKeyScope {
+    /// Read-only access to categories and food.
+    CatalogRead,
+}
+
+/// Service disruption shown on the status page until it's resolved.
+#[derive(Clone, Serialize, SimpleObject, InputObject)]
+#[serde(rename_all = "camelCase")]
+#[graphql(input_name = "IncidentInput")]
+pub struct Incident {
+    #[graphql(skip_input)]
+    pub id: ID,
+    pub title: String,
+    pub description: Option<String>,
+    #[graphql(skip_input)]
+    pub start_time: NaiveDateTime,
+}
+
+impl From<Row> for Incident {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            title: row.get("title"),
+            description: row.get("description"),
+            start_time: row.get("start_time"),
+        }
+    }
+}
+
+impl Validate for Incident {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)?;
+        check_length("description", self.description.as_deref(), MAX_TEXT_LENGTH)
+    }
+}
+
+/// Coarse service health for the public status page.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+    /// Orders are accepted: within opening hours and not in the maintenance mode.
+    pub is_open: bool,
+    /// Average delivery time during the last hour, `None` if nothing was delivered.
+    pub estimated_delivery_minutes: Option<i32>,
+    pub incidents: Vec<Incident>,
+}
+
+#[derive(Clone, Default, SimpleObject)]
+pub struct Maintenance {
+    pub is_enabled: bool,
+    /// Shown to users instead of the default message.
+    pub message: Option<String>,
+}
+
+impl From<Row> for Maintenance {
+    fn from(row: Row) -> Self {
+        Self {
+            is_enabled: row.get("is_maintenance"),
+            message: row.get("maintenance_message"),
+        }
+    }
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "BirthdayPromoSettingsInput")]
+pub struct BirthdayPromoSettings {
+    pub is_enabled: bool,
+    pub discount_percent: i16,
+    /// Number of days the promo code can be used.
+    pub valid_days: i32,
+}
+
+impl Default for BirthdayPromoSettings {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            discount_percent: 10,
+            valid_days: 7,
+        }
+    }
+}
+
+impl From<Row> for BirthdayPromoSettings {
+    fn from(row: Row) -> Self {
+        Self {
+            is_enabled: row.get("is_birthday_promo_enabled"),
+            discount_percent: row.get("birthday_discount_percent"),
+            valid_days: row.get("birthday_promo_days"),
+        }
+    }
+}
+
+impl Validate for BirthdayPromoSettings {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range("discountPercent", self.discount_percent, 1..=100)?;
+        check_min("validDays", self.valid_days, 1)
+    }
+}
+
+/// Rules of cancelling orders by customers. Orders which aren't accepted yet
+/// are cancelled for free, delivered orders can't be cancelled.
+#[derive(Clone, Default, SimpleObject, InputObject)]
+#[graphql(input_name = "CancellationPolicyInput")]
+pub struct CancellationPolicy {
+    /// Percent of the order total charged after the order was accepted by a rider
+    /// or became ready for pickup.
+    pub accepted_fee_percent: i16,
+    /// Percent charged after the order was picked up by a rider.
+    /// Cancellation isn't allowed if it's `null`.
+    pub picked_up_fee_percent: Option<i16>,
+}
+
+impl CancellationPolicy {
+    /// Returns `None` if the order with such status can't be cancelled by the customer.
+    pub fn fee_percent(&self, status: OrderStatus) -> Option<i16> {
+        match status {
+            OrderStatus::Scheduled | OrderStatus::Queued | OrderStatus::Created => Some(0),
+            OrderStatus::Accepted | OrderStatus::ReadyForPickup => Some(self.accepted_fee_percent),
+            OrderStatus::PickedUp => self.picked_up_fee_percent,
+            OrderStatus::Delivered | OrderStatus::Cancelled => None,
+        }
+    }
+}
+
+impl Validate for CancellationPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range("acceptedFeePercent", self.accepted_fee_percent, 0..=100)?;
+        self.picked_up_fee_percent.map_or(Ok(()), |percent| {
+            check_range("pickedUpFeePercent", percent, 0..=100)
+        })
+    }
+}
+
+impl From<Row> for CancellationPolicy {
+    fn from(row: Row) -> Self {
+        Self {
+            accepted_fee_percent: row.get("accepted_cancellation_fee_percent"),
+            picked_up_fee_percent: row.get("picked_up_cancellation_fee_percent"),
+        }
+    }
+}
+
+/// Customers get a promo code when an order is delivered later than promised.
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "LateDeliveryPolicyInput")]
+pub struct LateDeliveryPolicy {
+    /// Delivery time promised when an order is made.
+    /// Late deliveries aren't compensated if it's `null`.
+    pub promised_minutes: Option<i32>,
+    /// Delay which isn't compensated.
+    pub tolerance_minutes: i32,
+    pub discount_percent: i16,
+    /// Number of days the promo code can be used.
+    pub valid_days: i32,
+}
+
+impl Default for LateDeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            promised_minutes: None,
+            tolerance_minutes: 0,
+            discount_percent: 10,
+            valid_days: 14,
+        }
+    }
+}
+
+impl From<Row> for LateDeliveryPolicy {
+    fn from(row: Row) -> Self {
+        Self {
+            promised_minutes: row.get("promised_delivery_minutes"),
+            tolerance_minutes: row.get("late_delivery_tolerance_minutes"),
+            discount_percent: row.get("late_delivery_discount_percent"),
+            valid_days: row.get("late_delivery_promo_days"),
+        }
+    }
+}
+
+impl Validate for LateDeliveryPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(minutes) = self.promised_minutes {
+            check_min("promisedMinutes", minutes, 1)?;
+        }
+        check_min("toleranceMinutes", self.tolerance_minutes, 0)?;
+        check_range("discountPercent", self.discount_percent, 1..=100)?;
+        check_min("validDays", self.valid_days, 1)
+    }
+}
+
+/// Targets for delivery orders counted from their creation or the scheduled time.
+/// Managers are notified about orders breaching them.
+#[derive(Clone, Default, SimpleObject, InputObject)]
+#[graphql(input_name = "SlaPolicyInput")]
+pub struct SlaPolicy {
+    /// Order should be taken by a rider within this time. No target if it's `null`.
+    pub accept_minutes: Option<i32>,
+    /// Order should be delivered within this time. No target if it's `null`.
+    pub delivery_minutes: Option<i32>,
+}
+
+impl From<Row> for SlaPolicy {
+    fn from(row: Row) -> Self {
+        Self {
+            accept_minutes: row.get("sla_accept_minutes"),
+            delivery_minutes: row.get("sla_delivery_minutes"),
+        }
+    }
+}
+
+impl Validate for SlaPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(minutes) = self.accept_minutes {
+            check_min("acceptMinutes", minutes, 1)?;
+        }
+        if let Some(minutes) = self.delivery_minutes {
+            check_min("deliveryMinutes", minutes, 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules of placing orders in advance.
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "OrderSchedulingInput")]
+pub struct OrderScheduling {
+    /// Orders can be scheduled only within opening hours.
+    /// Always open if both opening and closing times are `null`.
+    pub opening_time: Option<NaiveTime>,
+    /// Can be earlier than the opening time if the business works past midnight.
+    pub closing_time: Option<NaiveTime>,
+    /// Orders must be scheduled at least this far ahead.
+    pub min_schedule_minutes: i32,
+    /// Scheduled orders are queued and shown to riders this long before the scheduled time.
+    pub dispatch_minutes: i32,
+}
+
+impl OrderScheduling {
+    pub fn is_open_at(&self, time: NaiveTime) -> bool {
+        match (self.opening_time, self.closing_time) {
+            (Some(opening), Some(closing)) if opening <= closing => {
+                opening <= time && time < closing
+            }
+            (Some(opening), Some(closing)) => opening <= time || time < closing,
+            _ => true,
+        }
+    }
+}
+
+impl Default for OrderScheduling {
+    fn default() -> Self {
+        Self {
+            opening_time: None,
+            closing_time: None,
+            min_schedule_minutes: 60,
+            dispatch_minutes: 45,
+        }
+    }
+}
+
+impl From<Row> for OrderScheduling {
+    fn from(row: Row) -> Self {
+        Self {
+            opening_time: row.get("opening_time"),
+            closing_time: row.get("closing_time"),
+            min_schedule_minutes: row.get("min_schedule_minutes"),
+            dispatch_minutes: row.get("dispatch_minutes"),
+        }
+    }
+}
+
+impl Validate for OrderScheduling {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.opening_time.is_some() != self.closing_time.is_some() {
+            return Err(invalid_input(
+                "closingTime",
+                "must be set together with the opening time",
+            ));
+        }
+        if self.opening_time.is_some() && self.opening_time == self.closing_time {
+            return Err(invalid_input(
+                "closingTime",
+                "must differ from the opening time",
+            ));
+        }
+        check_min("minScheduleMinutes", self.min_schedule_minutes, 0)?;
+        check_min("dispatchMinutes", self.dispatch_minutes, 0)
+    }
+}
+
+/// SLA attainment of delivery orders made within the reported period.
+/// In-time counts and attainments are `null` if there is no corresponding target.
+#[derive(SimpleObject)]
+pub struct SlaReport {
+    pub accepted_count: i64,
+    pub accepted_in_time_count: Option<i64>,
+    /// Share of accepted orders which were accepted in time.
+    pub accept_attainment: Option<f64>,
+    pub delivered_count: i64,
+    pub delivered_in_time_count: Option<i64>,
+    /// Share of delivered orders which were delivered in time.
+    pub delivery_attainment: Option<f64>,
+    /// Orders about which managers were notified.
+    pub breached_count: i64,
+}
+
+impl SlaReport {
+    /// Attainments are calculated from the counts.
+    pub fn new(
+        accepted_count: i64,
+        accepted_in_time_count: Option<i64>,
+        delivered_count: i64,
+        delivered_in_time_count: Option<i64>,
+        breached_count: i64,
+    ) -> Self {
+        let attainment = |in_time: Option<i64>, total: i64| {
+            in_time
+                .filter(|_| total != 0)
+                .map(|in_time| in_time as f64 / total as f64)
+        };
+        Self {
+            accepted_count,
+            accepted_in_time_count,
+            accept_attainment: attainment(accepted_in_time_count, accepted_count),
+            delivered_count,
+            delivered_in_time_count,
+            delivery_attainment: attainment(delivered_in_time_count, delivered_count),
+            breached_count,
+        }
+    }
+}
+
+impl From<Row> for SlaReport {
+    fn from(row: Row) -> Self {
+        Self::new(
+            row.get("accepted_count"),
+            row.get("accepted_in_time_count"),
+            row.get("delivered_count"),
+            row.get("delivered_in_time_count"),
+            row.get("breached_count"),
+        )
+    }
+}
+
+/// Compensation of late deliveries granted within the reported period.
+#[derive(SimpleObject)]
+pub struct LateDeliveryReport {
+    pub issued_count: i64,
+    /// Number of granted promo codes which were used.
+    pub used_count: i64,
+    /// Sum of discounts given by the used promo codes.
+    pub discount_total: Decimal,
+}
+
+impl From<Row> for LateDeliveryReport {
+    fn from(row: Row) -> Self {
+        Self {
+            issued_count: row.get("issued_count"),
+            used_count: row.get("used_count"),
+            discount_total: row.get("discount_total"),
+        }
+    }
+}
+
+/// Totals of orders finished during the day.
+#[derive(SimpleObject)]
+pub struct Settlement {
+    pub day: NaiveDate,
+    pub delivered_count: i32,
+    pub cancelled_count: i32,
+    /// Price of the delivered items before discounts.
+    pub gross: Decimal,
+    pub discounts: Decimal,
+    /// Cancellation fees.
+    pub fees: Decimal,
+    /// Gross minus discounts plus fees.
+    pub net: Decimal,
+}
+
+impl Settlement {
+    pub const CSV_HEADER: &'static str =
+        "day,delivered_count,cancelled_count,gross,discounts,fees,net";
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.day,
+            self.delivered_count,
+            self.cancelled_count,
+            self.gross,
+            self.discounts,
+            self.fees,
+            self.net
+        )
+    }
+}
+
+impl From<Row> for Settlement {
+    fn from(row: Row) -> Self {
+        let gross: Decimal = row.get("gross");
+        let discounts: Decimal = row.get("discounts");
+        let fees: Decimal = row.get("fees");
+        Self {
+            day: row.get("day"),
+            delivered_count: row.get("delivered_count"),
+            cancelled_count: row.get("cancelled_count"),
+            gross,
+            discounts,
+            fees,
+            net: gross - discounts + fees,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum PromoCodeReason {
+    Birthday,
+    LateDelivery,
+}
+
+#[derive(SimpleObject)]
+pub struct PromoCode {
+    pub id: ID,
+    pub code: String,
+    pub reason: PromoCodeReason,
+    pub discount_percent: i16,
+    pub create_time: NaiveDateTime,
+    pub expire_time: NaiveDateTime,
+    /// Order for which the code was used.
+    pub order_id: Option<ID>,
+    /// Order delivered late for which the code was granted.
+    pub compensated_order_id: Option<ID>,
+}
+
+impl From<Row> for PromoCode {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            code: row.get("code"),
+            reason: row.get("reason"),
+            discount_percent: row.get("discount_percent"),
+            create_time: row.get("create_time"),
+            expire_time: row.get("expire_time"),
+            order_id: row.get("order_id"),
+            compensated_order_id: row.get("compensated_order_id"),
+        }
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct ApiKey {
+    pub id: ID,
+    pub title: String,
+    pub scope: ApiKeyScope,
+    pub create_time: NaiveDateTime,
+    pub last_used_time: Option<NaiveDateTime>,
+    pub request_count: i64,
+    /// Counted against the `API_KEY_REQUESTS_PER_MINUTE` quota.
+    pub current_minute_requests: i32,
+}
+
+impl From<Row> for ApiKey {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            title: row.get("title"),
+            scope: row.get("scope"),
+            create_time: row.get("create_time"),
+            last_used_time: row.get("last_used_time"),
+            request_count: row.get("request_count"),
+            current_minute_requests: row.get("current_minute_requests"),
+        }
+    }
+}
+
+/// Executions of GraphQL operations with the same name since the server start.
+#[derive(SimpleObject)]
+pub struct OperationStats {
+    /// "anonymous" for operations without a name and "other" for the ones
+    /// beyond the limit of tracked operations.
+    pub operation_name: String,
+    pub count: i64,
+    /// Number of executions with errors.
+    pub error_count: i64,
+    pub error_rate: f64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Requests sent by the user to the authenticated endpoints.
+#[derive(SimpleObject)]
+pub struct ApiUsage {
+    pub username: String,
+    pub role: UserRole,
+    pub request_count: i64,
+    pub last_request_time: Option<NaiveDateTime>,
+    /// Counted against the `USER_REQUESTS_PER_MINUTE` quota.
+    pub current_minute_requests: i32,
+}
+
+impl From<Row> for ApiUsage {
+    fn from(row: Row) -> Self {
+        Self {
+            username: row.get("username"),
+            role: row.get("role"),
+            request_count: row.get("request_count"),
+            last_request_time: row.get("last_request_time"),
+            current_minute_requests: row.get("current_minute_requests"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Enum)]
+pub enum SortUsersBy {
+    Username,
+    FirstName,
+    LastName,
+}
+
+impl SortUsersBy {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Self::Username => "username",
+            Self::FirstName => "first_name",
+            Self::LastName => "last_name",
+        }
+    }
+}
+
+#[derive(Clone, Default, SimpleObject, InputObject)]
+#[graphql(input_name = "NotificationInput")]
+pub struct Notification {
+    #[graphql(skip_input)]
+    pub id: ID,
+    #[graphql(skip_input)]
+    pub sent_time: NaiveDateTime,
+    pub title: String,
+    pub description: Option<String>,
+    /// `None` if the notification is unread.
+    #[graphql(skip_input)]
+    pub read_time: Option<NaiveDateTime>,
+}
+
+impl From<Row> for Notification {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            sent_time: row.get("sent_time"),
+            title: row.get("title"),
+            description: row.get("description"),
+            read_time: row.get("read_time"),
+        }
+    }
+}
+
+impl Validate for Notification {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)?;
+        check_length("description", self.description.as_deref(), MAX_TEXT_LENGTH)
+    }
+}
+
+#[derive(Clone, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "AddressInput")]
+pub struct Address {
+    #[serde(skip)]
+    #[graphql(skip_input)]
+    pub id: ID,
+    pub locality: String,
+    pub street: String,
+    pub house: i32,
+    pub corps: Option<String>,
+    pub apartment: Option<String>,
+    /// Preselected during checkout. Only one address of a customer can be default.
+    #[serde(skip)]
+    #[graphql(skip_input)]
+    pub is_default: bool,
+    /// Set while the address is in the trash.
+    #[serde(skip)]
+    #[graphql(skip_input)]
+    pub delete_time: Option<NaiveDateTime>,
+}
+
+impl From<Row> for Address {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            locality: row.get("locality"),
+            street: row.get("street"),
+            house: row.get("house"),
+            corps: row.get("corps"),
+            apartment: row.get("apartment"),
+            is_default: row.get("is_default"),
+            delete_time: row.get("delete_time"),
+        }
+    }
+}
+
+impl Validate for Address {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("locality", &self.locality)?;
+        check_title("street", &self.street)?;
+        check_min("house", self.house, 1)?;
+        check_length("corps", self.corps.as_deref(), 16)?;
+        check_length("apartment", self.apartment.as_deref(), 16)
+    }
+}
+
+impl Address {
+    /// Returns the address in a form suitable for searching on a map.
+    /// Apartment is omitted as it doesn't affect the route.
+    pub fn to_search_query(&self) -> String {
+        let mut house = self.house.to_string();
+        if let Some(corps) = &self.corps {
+            house += &format!("к{corps}");
+        }
+        format!("{}, {} {house}", self.locality, self.street)
+    }
+}
+
+/// Links which open navigation to a delivery address in a map application.
+#[derive(SimpleObject)]
+pub struct NavigationInfo {
+    /// URI with the `geo` scheme handled by navigation applications on Android.
+    pub geo_uri: String,
+    /// Opens Google Maps with directions on any platform.
+    pub google_maps_url: String,
+}
+
+impl From<&Address> for NavigationInfo {
+    fn from(address: &Address) -> Self {
+        let query = utf8_percent_encode(&address.to_search_query(), NON_ALPHANUMERIC).to_string();
+        Self {
+            geo_uri: format!("geo:0,0?q={query}"),
+            google_maps_url: format!(
+                "https://www.google.com/maps/dir/?api=1&destination={query}&travelmode=driving"
+            ),
+        }
+    }
+}
+
+/// Space taken by the previews stored in the database.
+#[derive(SimpleObject)]
+pub struct StorageUsage {
+    pub category_preview_bytes: i64,
+    pub food_preview_bytes: i64,
+    pub total_bytes: i64,
+    /// Uploads exceeding it are rejected. Unlimited if it's `null`.
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "CategoryInput")]
+pub struct Category {
+    #[graphql(skip_input)]
+    pub id: ID,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+impl From<Row> for Category {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            title: row.get("title"),
+            description: row.get("description"),
+        }
+    }
+}
+
+impl Validate for Category {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)?;
+        check_length("description", self.description.as_deref(), MAX_TEXT_LENGTH)
+    }
+}
+
+/// Full catalog as a single document. Categories and food are matched by titles
+/// on import, so the document can be restored into another environment.
+#[derive(Serialize, Deserialize)]
+pub struct CatalogDocument {
+    /// In UTC.
+    pub export_time: NaiveDateTime,
+    pub categories: Vec<CatalogDocumentCategory>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CatalogDocumentCategory {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Relative URL of the preview image. Images aren't imported.
+    #[serde(default)]
+    pub preview_url: Option<String>,
+    #[serde(default)]
+    pub food: Vec<CatalogDocumentFood>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CatalogDocumentFood {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub count: i32,
+    pub is_alcohol: bool,
+    pub price: Decimal,
+    /// Relative URL of the preview image. Images aren't imported.
+    #[serde(default)]
+    pub preview_url: Option<String>,
+}
+
+#[derive(Default, SimpleObject)]
+pub struct CatalogImportSummary {
+    pub created_categories: i32,
+    pub updated_categories: i32,
+    pub created_food: i32,
+    pub updated_food: i32,
+}
+
+/// Conditions which food must satisfy. Default value doesn't filter anything.
+#[derive(Clone, Copy, Default)]
+pub struct FoodFilter {
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub exclude_alcohol: bool,
+    pub in_stock_only: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct CategoryWithFood {
+    pub category: Category,
+    pub food: Vec<IndexedFood>,
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "FoodInput")]
+pub struct IndexedFood {
+    #[graphql(skip_input)]
+    pub id: ID,
+    pub title: String,
+    pub description: Option<String>,
+    pub category_id: ID,
+    pub count: i32,
+    pub is_alcohol: bool,
+    pub price: Decimal,
+    /// Average rating of orders containing the food, `null` if there are no ratings.
+    #[graphql(skip_input)]
+    pub average_rating: Option<f64>,
+    #[graphql(skip_input)]
+    pub ratings_count: i32,
+}
+
+impl From<Row> for IndexedFood {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            title: row.get("title"),
+            description: row.get("description"),
+            category_id: row.get("category_id"),
+            count: row.get("count"),
+            is_alcohol: row.get("is_alcohol"),
+            price: row.get("price"),
+            average_rating: row.get("average_rating"),
+            ratings_count: row.get("ratings_count"),
+        }
+    }
+}
+
+impl Validate for IndexedFood {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)?;
+        check_length("description", self.description.as_deref(), MAX_TEXT_LENGTH)?;
+        check_min("count", self.count, 0)?;
+        check_range("price", self.price, Decimal::ZERO..=MAX_PRICE)
+    }
+}
+
+/// Fields which aren't specified are left unchanged.
+#[derive(InputObject)]
+#[graphql(name = "FoodPatchInput")]
+pub struct FoodPatch {
+    pub title: Option<String>,
+    /// Set to `null` to clear the description.
+    pub description: MaybeUndefined<String>,
+    pub category_id: Option<ID>,
+    pub count: Option<i32>,
+    pub is_alcohol: Option<bool>,
+    pub price: Option<Decimal>,
+}
+
+impl FoodPatch {
+    /// Returns pairs of column names and new values.
+    /// Count isn't included, as its change is also recorded as a stock movement.
+    pub fn columns(&self) -> Vec<(&'static str, &(dyn ToSql + Sync))> {
+        let mut columns: Vec<(_, &(dyn ToSql + Sync))> = Vec::new();
+        if let Some(title) = &self.title {
+            columns.push(("title", title));
+        }
+        match &self.description {
+            MaybeUndefined::Undefined => {}
+            MaybeUndefined::Null => columns.push(("description", &None::<String>)),
+            MaybeUndefined::Value(description) => columns.push(("description", description)),
+        }
+        if let Some(category_id) = &self.category_id {
+            columns.push(("category_id", category_id));
+        }
+        if let Some(is_alcohol) = &self.is_alcohol {
+            columns.push(("is_alcohol", is_alcohol));
+        }
+        if let Some(price) = &self.price {
+            columns.push(("price", price));
+        }
+        columns
+    }
+}
+
+impl Validate for FoodPatch {
+    fn validate(&self) -> Result<(), AppError> {
+        if let Some(title) = &self.title {
+            check_title("title", title)?;
+        }
+        check_length(
+            "description",
+            self.description.value().map(String::as_str),
+            MAX_TEXT_LENGTH,
+        )?;
+        if let Some(count) = self.count {
+            check_min("count", count, 0)?;
+        }
+        if let Some(price) = self.price {
+            check_range("price", price, Decimal::ZERO..=MAX_PRICE)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum StockMovementKind {
+    OrderDecrement,
+    /// Items of a cancelled or deleted order were returned to the stock.
+    OrderCancellation,
+    Restock,
+    /// Count was set by a manager.
+    Correction,
+    /// Count was changed by a manager for the reason in the comment
+    /// (damaged or lost items and so on).
+    Adjustment,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct StockMovement {
+    pub id: ID,
+    pub food_id: ID,
+    pub time: NaiveDateTime,
+    pub kind: StockMovementKind,
+    /// Negative if the count was decreased.
+    pub delta: i32,
+    pub count_after: i32,
+    pub order_id: Option<ID>,
+    pub manager_id: Option<ID>,
+    pub comment: Option<String>,
+}
+
+impl From<Row> for StockMovement {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            food_id: row.get("food_id"),
+            time: row.get("time"),
+            kind: row.get("kind"),
+            delta: row.get("delta"),
+            count_after: row.get("count_after"),
+            order_id: row.get("order_id"),
+            manager_id: row.get("manager_id"),
+            comment: row.get("comment"),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ReorderSuggestion {
+    pub food: Food,
+    /// Average number of items sold per day.
+    pub daily_sales: f64,
+    /// Days until the food runs out at the current sales rate.
+    pub days_left: f64,
+    pub suggested_quantity: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum CatalogEntity {
+    Category,
+    Food,
+}
+
+impl CatalogEntity {
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Category => "categories",
+            Self::Food => "food",
+        }
+    }
+
+    /// Columns which can be restored from a snapshot (except ID, preview
+    /// and count of food as it's changed only through stock movements).
+    pub fn columns(&self) -> &'static str {
+        match self {
+            Self::Category => "title, description",
+            Self::Food => "title, description, category_id, is_alcohol, price",
+        }
+    }
+
+    /// Replaces `{table}` and `{columns}` placeholders in the SQL statement.
+    pub fn fill_placeholders(&self, statement: &str) -> String {
+        statement
+            .replace("{table}", self.table())
+            .replace("{columns}", self.columns())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum CatalogAction {
+    Created,
+    Updated,
+    Deleted,
+    /// Entity was restored to the state preceding another change.
+    Reverted,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct CatalogChange {
+    pub id: ID,
+    pub time: NaiveDateTime,
+    /// `None` if the manager was deleted.
+    pub manager_id: Option<ID>,
+    pub entity: CatalogEntity,
+    pub entity_id: ID,
+    pub action: CatalogAction,
+    /// Entity fields (except preview). `None` if the entity didn't exist.
+    pub before: Option<Json<serde_json::Value>>,
+    pub after: Option<Json<serde_json::Value>>,
+}
+
+impl From<Row> for CatalogChange {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            time: row.get("time"),
+            manager_id: row.get("manager_id"),
+            entity: row.get("entity"),
+            entity_id: row.get("entity_id"),
+            action: row.get("action"),
+            before: row.get::<_, Option<_>>("before").map(Json),
+            after: row.get::<_, Option<_>>("after").map(Json),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Enum)]
+pub enum SortFoodBy {
+    Title,
+    Count,
+    Price,
+    /// Food without ratings is treated as rated 0.
+    Rating,
+}
+
+impl SortFoodBy {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Count => "count",
+            Self::Price => "price",
+            Self::Rating => "COALESCE(average_rating, 0)",
+        }
+    }
+
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            Self::Title => "text",
+            Self::Count => "integer",
+            Self::Price => "numeric",
+            Self::Rating => "double precision",
+        }
+    }
+
+    pub fn cursor(&self, food: &IndexedFood) -> Cursor {
+        Cursor {
+            key: match self {
+                Self::Title => CursorKey::Text(food.title.clone()),
+                Self::Count => CursorKey::Integer(food.count),
+                Self::Price => CursorKey::Numeric(food.price),
+                Self::Rating => CursorKey::Double(food.average_rating.unwrap_or_default()),
+            },
+            id: food.id,
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Enum)]
-pub enum SortUsersBy {
-    Username,
-    FirstName,
-    LastName,
+#[derive(Clone, SimpleObject)]
+#[graphql(complex)]
+pub struct Food {
+    pub indexed_food: IndexedFood,
 }
 
-impl SortUsersBy {
-    pub fn cmp(&self, lhs: &User, rhs: &User) -> Ordering {
-        match self {
-            Self::Username => lhs.username.cmp(&rhs.username),
-            Self::FirstName => lhs.first_name.cmp(&rhs.first_name),
-            Self::LastName => lhs.last_name.cmp(&rhs.last_name),
+#[ComplexObject]
+impl Food {
+    async fn category(&self, ctx: &Context<'_>) -> Result<Category, AppError> {
+        loader::load::<CategoryLoader>(ctx, self.indexed_food.category_id).await
+    }
+
+    /// Stock of the food at each location where it's present.
+    async fn availability(&self, ctx: &Context<'_>) -> Result<Vec<LocationStock>, AppError> {
+        loader::load_or_default::<LocationStockLoader>(ctx, self.indexed_food.id).await
+    }
+
+    /// Price changes, the latest ones go first. Only managers see changes
+    /// made more than 30 days ago.
+    async fn price_history(&self, ctx: &Context<'_>) -> Result<Vec<PriceChange>, AppError> {
+        let mut history =
+            loader::load_or_default::<PriceHistoryLoader>(ctx, self.indexed_food.id).await?;
+        if !is_manager(ctx) {
+            let since = price_history_start();
+            history.retain(|change| change.change_time >= since);
         }
+        Ok(history)
+    }
+
+    /// Price before the latest change if it was reduced within the last 30 days,
+    /// e.g. to show a "price dropped" badge.
+    async fn previous_price(&self, ctx: &Context<'_>) -> Result<Option<Decimal>, AppError> {
+        let history =
+            loader::load_or_default::<PriceHistoryLoader>(ctx, self.indexed_food.id).await?;
+        Ok(match history.as_slice() {
+            [latest, previous, ..]
+                if latest.change_time >= price_history_start()
+                    && previous.price > self.indexed_food.price =>
+            {
+                Some(previous.price)
+            }
+            _ => None,
+        })
     }
 }
 
-#[derive(SimpleObject, InputObject)]
-#[graphql(input_name = "NotificationInput")]
-pub struct Notification {
-    #[graphql(skip_input)]
-    pub id: ID,
-    #[graphql(skip_input)]
-    pub sent_time: NaiveDateTime,
-    pub title: String,
-    pub description: Option<String>,
+#[derive(Clone, SimpleObject)]
+pub struct PriceChange {
+    pub price: Decimal,
+    pub change_time: NaiveDateTime,
 }
 
-impl From<Row> for Notification {
+impl From<Row> for PriceChange {
     fn from(row: Row) -> Self {
         Self {
-            id: row.get("id"),
-            sent_time: row.get("sent_time"),
-            title: row.get("title"),
-            description: row.get("description"),
+            price: row.get("price"),
+            change_time: row.get("change_time"),
         }
     }
 }
 
-#[derive(SimpleObject, InputObject)]
-#[graphql(input_name = "AddressInput")]
-pub struct Address {
+fn price_history_start() -> NaiveDateTime {
+    Utc::now().naive_utc() - chrono::Duration::days(PRICE_HISTORY_DAYS)
+}
+
+/// Store or warehouse which fulfills orders.
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "LocationInput")]
+pub struct Location {
     #[graphql(skip_input)]
     pub id: ID,
-    pub locality: String,
-    pub street: String,
-    pub house: i32,
-    pub corps: Option<String>,
-    pub apartment: Option<String>,
+    pub title: String,
+    /// Delivery addresses with these localities are served by the location.
+    pub localities: Vec<String>,
 }
 
-impl From<Row> for Address {
+impl From<Row> for Location {
     fn from(row: Row) -> Self {
         Self {
             id: row.get("id"),
-            locality: row.get("locality"),
-            street: row.get("street"),
-            house: row.get("house"),
-            corps: row.get("corps"),
-            apartment: row.get("apartment"),
+            title: row.get("title"),
+            localities: row.get("localities"),
         }
     }
 }
 
-#[derive(Clone, SimpleObject, InputObject)]
-#[graphql(input_name = "CategoryInput")]
-pub struct Category {
-    #[graphql(skip_input)]
-    pub id: ID,
-    pub title: String,
-    pub description: Option<String>,
+impl Validate for Location {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)?;
+        self.localities
+            .iter()
+            .try_for_each(|locality| check_title("localities", locality))
+    }
 }
 
-impl From<Row> for Category {
+/// Riders online and delivery orders made in the zone served by the location within an hour.
+#[derive(SimpleObject)]
+pub struct RiderCoverage {
+    pub location_id: ID,
+    /// Start of the hour.
+    pub hour: NaiveDateTime,
+    pub rider_count: i64,
+    pub order_count: i64,
+}
+
+impl From<Row> for RiderCoverage {
     fn from(row: Row) -> Self {
         Self {
-            id: row.get("id"),
-            title: row.get("title"),
-            description: row.get("description"),
+            location_id: row.get("location_id"),
+            hour: row.get("hour"),
+            rider_count: row.get("rider_count"),
+            order_count: row.get("order_count"),
         }
     }
 }
 
-#[derive(SimpleObject, InputObject)]
-#[graphql(input_name = "FoodInput")]
-pub struct IndexedFood {
-    #[graphql(skip_input)]
-    pub id: ID,
-    pub title: String,
-    pub description: Option<String>,
-    pub category_id: ID,
+#[derive(Clone, SimpleObject)]
+pub struct LocationStock {
+    pub location_id: ID,
+    pub food_id: ID,
     pub count: i32,
-    pub is_alcohol: bool,
-    pub price: Decimal,
 }
 
-impl From<Row> for IndexedFood {
+impl From<Row> for LocationStock {
     fn from(row: Row) -> Self {
         Self {
-            id: row.get("id"),
-            title: row.get("title"),
-            description: row.get("description"),
-            category_id: row.get("category_id"),
+            location_id: row.get("location_id"),
+            food_id: row.get("food_id"),
             count: row.get("count"),
-            is_alcohol: row.get("is_alcohol"),
-            price: row.get("price"),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Enum)]
-pub enum SortFoodBy {
-    Title,
-    Count,
-    Price,
+/// Item which can't be ordered due to insufficient stock.
+#[derive(Clone, Debug, Serialize, SimpleObject)]
+#[serde(rename_all = "camelCase")]
+pub struct StockShortage {
+    pub food_id: ID,
+    pub title: String,
+    pub requested: i32,
+    pub available: i32,
 }
 
-impl SortFoodBy {
-    pub fn cmp(&self, lhs: &IndexedFood, rhs: &IndexedFood) -> Ordering {
-        match self {
-            Self::Title => lhs.title.cmp(&rhs.title),
-            Self::Count => lhs.count.cmp(&rhs.count),
-            Self::Price => lhs.price.partial_cmp(&rhs.price).unwrap_or(Ordering::Equal),
+impl From<Row> for StockShortage {
+    fn from(row: Row) -> Self {
+        Self {
+            food_id: row.get("food_id"),
+            title: row.get("title"),
+            requested: row.get("requested"),
+            available: row.get("available"),
         }
     }
 }
 
+/// Outcome of repeating a past order.
 #[derive(SimpleObject)]
-pub struct Food {
-    pub category: Category,
-    pub indexed_food: IndexedFood,
+pub struct Reorder {
+    /// Number of items added into the cart.
+    pub added_count: i32,
+    /// Items which weren't added. Items of deleted food are removed from orders,
+    /// so they don't appear here.
+    pub skipped: Vec<StockShortage>,
 }
 
-#[derive(SimpleObject, InputObject)]
+#[derive(Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "CartItemInput")]
 pub struct IndexedCartItem {
     #[graphql(skip_input)]
@@ -221,6 +1638,12 @@ impl From<Row> for IndexedCartItem {
     }
 }
 
+impl Validate for IndexedCartItem {
+    fn validate(&self) -> Result<(), AppError> {
+        check_min("count", self.count, 1)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Enum)]
 pub enum SortCartBy {
     Count,
@@ -228,10 +1651,10 @@ pub enum SortCartBy {
 }
 
 impl SortCartBy {
-    pub fn cmp(&self, lhs: &IndexedCartItem, rhs: &IndexedCartItem) -> Ordering {
+    pub fn column(&self) -> &'static str {
         match self {
-            Self::Count => lhs.count.cmp(&rhs.count),
-            Self::AddTime => lhs.add_time.cmp(&rhs.add_time),
+            Self::Count => "count",
+            Self::AddTime => "add_time",
         }
     }
 }
@@ -249,7 +1672,7 @@ pub struct Cart {
     pub total_price: Decimal,
 }
 
-#[derive(SimpleObject, InputObject)]
+#[derive(Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "FavoriteInput")]
 pub struct IndexedFavorite {
     #[graphql(skip_input)]
@@ -257,6 +1680,10 @@ pub struct IndexedFavorite {
     pub food_id: ID,
     #[graphql(skip_input)]
     pub add_time: NaiveDateTime,
+    /// Set while the favorite is in the trash.
+    #[graphql(skip_input)]
+    pub delete_time: Option<NaiveDateTime>,
+    pub collection_id: Option<ID>,
 }
 
 impl From<Row> for IndexedFavorite {
@@ -265,30 +1692,172 @@ impl From<Row> for IndexedFavorite {
             id: row.get("id"),
             food_id: row.get("food_id"),
             add_time: row.get("add_time"),
+            delete_time: row.get("delete_time"),
+            collection_id: row.get("collection_id"),
+        }
+    }
+}
+
+/// Named list of favorites, e.g. "Weekly groceries".
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "FavoriteCollectionInput")]
+pub struct FavoriteCollection {
+    #[graphql(skip_input)]
+    pub id: ID,
+    pub title: String,
+    #[graphql(skip_input)]
+    pub create_time: NaiveDateTime,
+}
+
+impl From<Row> for FavoriteCollection {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.get("id"),
+            title: row.get("title"),
+            create_time: row.get("create_time"),
         }
     }
 }
 
+impl Validate for FavoriteCollection {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("title", &self.title)
+    }
+}
+
 #[derive(SimpleObject)]
+#[graphql(complex)]
 pub struct Favorite {
-    pub food: Food,
     pub indexed_favorite: IndexedFavorite,
 }
 
-#[derive(SimpleObject, InputObject)]
+#[ComplexObject]
+impl Favorite {
+    async fn food(&self, ctx: &Context<'_>) -> Result<Food, AppError> {
+        loader::load::<FoodLoader>(ctx, self.indexed_favorite.food_id).await
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum OrderStatus {
+    /// Order is placed in advance and waits for its dispatch window.
+    Scheduled,
+    /// Capacity of active orders was reached, the order waits for a free slot.
+    Queued,
+    #[default]
+    Created,
+    /// Rider took the order.
+    Accepted,
+    PickedUp,
+    /// Pickup order is waiting for the customer at the counter.
+    ReadyForPickup,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// Returns the status that follows the current one during normal order flow.
+    pub fn next(&self, fulfillment: FulfillmentType) -> Option<Self> {
+        match (fulfillment, self) {
+            (FulfillmentType::Delivery, Self::Created) => Some(Self::Accepted),
+            (FulfillmentType::Delivery, Self::Accepted) => Some(Self::PickedUp),
+            (FulfillmentType::Delivery, Self::PickedUp) => Some(Self::Delivered),
+            (FulfillmentType::Pickup, Self::Created) => Some(Self::ReadyForPickup),
+            (FulfillmentType::Pickup, Self::ReadyForPickup) => Some(Self::Delivered),
+            _ => None,
+        }
+    }
+
+    pub fn can_transition_to(&self, status: Self, fulfillment: FulfillmentType) -> bool {
+        match status {
+            Self::Cancelled => {
+                matches!(
+                    self,
+                    Self::Scheduled
+                        | Self::Queued
+                        | Self::Created
+                        | Self::Accepted
+                        | Self::ReadyForPickup
+                )
+            }
+            _ => self.next(fulfillment) == Some(status),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct QueuePosition {
+    /// Starts from 1.
+    pub position: i32,
+    /// Based on how long orders were completed during the last day.
+    /// Unknown if the capacity was removed and the order isn't promoted yet.
+    pub estimated_acceptance_time: Option<NaiveDateTime>,
+}
+
+impl From<Row> for QueuePosition {
+    fn from(row: Row) -> Self {
+        Self {
+            position: row.get("position"),
+            estimated_acceptance_time: row.get("estimated_acceptance_time"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromSql, ToSql, Enum)]
+pub enum FulfillmentType {
+    #[default]
+    Delivery,
+    /// Customer picks up the order at the counter. Address and rider aren't assigned.
+    Pickup,
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "OrderInput")]
 pub struct IndexedOrder {
     #[graphql(skip_input)]
     pub id: ID,
     #[graphql(skip_input)]
     pub customer_id: ID,
-    pub address_id: ID,
+    /// Required for delivery unless the order is a gift: then the recipient
+    /// can enter it using a link. Ignored for pickup.
+    pub address_id: Option<ID>,
     #[graphql(skip_input)]
     pub create_time: NaiveDateTime,
     #[graphql(skip_input)]
     pub rider_id: Option<ID>,
     #[graphql(skip_input)]
     pub completed_time: Option<NaiveDateTime>,
+    #[graphql(skip_input)]
+    pub status: OrderStatus,
+    #[graphql(default)]
+    pub fulfillment: FulfillmentType,
+    /// Provided only for pickup orders.
+    #[graphql(skip_input)]
+    pub pickup_code: Option<String>,
+    /// Applied using a promo code.
+    #[graphql(skip_input)]
+    pub discount_percent: i16,
+    /// Required for pickup if there are locations. Chosen automatically for delivery.
+    pub location_id: Option<ID>,
+    /// Charged from the customer who cancelled the order.
+    #[graphql(skip_input)]
+    pub cancellation_fee: Option<Decimal>,
+    #[graphql(skip_input)]
+    pub cancellation_reason: Option<String>,
+    /// Delivery is promised by this time if late deliveries are compensated.
+    #[graphql(skip_input)]
+    pub promised_time: Option<NaiveDateTime>,
+    /// Specified to deliver the order to another person. Only for delivery.
+    pub gift: Option<Gift>,
+    /// Exposed by [Order] only to the customer, the assigned rider and managers.
+    #[graphql(skip_output)]
+    pub comment: Option<String>,
+    /// Order is delivered or ready for pickup by this time instead of ASAP.
+    /// Must be within opening hours, see [OrderScheduling].
+    pub scheduled_for: Option<NaiveDateTime>,
+    /// Address was entered by the gift recipient, so it's hidden from the customer.
+    #[graphql(skip)]
+    pub is_recipient_address: bool,
 }
 
 impl From<Row> for IndexedOrder {
@@ -300,7 +1869,54 @@ impl From<Row> for IndexedOrder {
             create_time: row.get("create_time"),
             rider_id: row.get("rider_id"),
             completed_time: row.get("completed_time"),
+            status: row.get("status"),
+            fulfillment: row.get("fulfillment"),
+            pickup_code: row.get("pickup_code"),
+            discount_percent: row.get("discount_percent"),
+            location_id: row.get("location_id"),
+            cancellation_fee: row.get("cancellation_fee"),
+            cancellation_reason: row.get("cancellation_reason"),
+            promised_time: row.get("promised_time"),
+            gift: row
+                .get::<_, Option<String>>("gift_recipient_name")
+                .map(|recipient_name| Gift {
+                    recipient_name,
+                    recipient_phone: row.get("gift_recipient_phone"),
+                    message: row.get("gift_message"),
+                }),
+            comment: row.get("comment"),
+            scheduled_for: row.get("scheduled_for"),
+            is_recipient_address: row.get::<_, Option<String>>("gift_address_token").is_some(),
+        }
+    }
+}
+
+impl Validate for IndexedOrder {
+    fn validate(&self) -> Result<(), AppError> {
+        check_length("comment", self.comment.as_deref(), MAX_TEXT_LENGTH)?;
+        self.gift.as_ref().map_or(Ok(()), Validate::validate)
+    }
+}
+
+/// Prices are hidden from the delivery docket of a gift order.
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "GiftInput")]
+pub struct Gift {
+    pub recipient_name: String,
+    /// Rider calls the recipient instead of the customer.
+    pub recipient_phone: String,
+    /// Printed on the receipt.
+    pub message: Option<String>,
+}
+
+impl Validate for Gift {
+    fn validate(&self) -> Result<(), AppError> {
+        check_title("recipientName", &self.recipient_name)?;
+        if self.recipient_phone.is_empty() {
+            return Err(invalid_input("recipientPhone", "must not be empty"));
         }
+        check_length("recipientPhone", Some(&self.recipient_phone), 32)?;
+        check_length("message", self.message.as_deref(), MAX_TEXT_LENGTH)
     }
 }
 
@@ -309,36 +1925,121 @@ pub enum OrdersFilter {
     All,
     InProgress,
     Completed,
+    Cancelled,
+    /// Placed in advance and not dispatched yet.
+    Scheduled,
 }
 
 impl OrdersFilter {
-    pub fn fits(&self, order: &IndexedOrder) -> bool {
+    pub fn statuses(&self) -> Vec<OrderStatus> {
         match self {
-            Self::All => true,
-            Self::InProgress => order.rider_id.is_some() && order.completed_time.is_none(),
-            Self::Completed => order.completed_time.is_some(),
+            Self::All => vec![
+                OrderStatus::Scheduled,
+                OrderStatus::Queued,
+                OrderStatus::Created,
+                OrderStatus::Accepted,
+                OrderStatus::PickedUp,
+                OrderStatus::ReadyForPickup,
+                OrderStatus::Delivered,
+                OrderStatus::Cancelled,
+            ],
+            Self::InProgress => vec![
+                OrderStatus::Accepted,
+                OrderStatus::PickedUp,
+                OrderStatus::ReadyForPickup,
+            ],
+            Self::Completed => vec![OrderStatus::Delivered],
+            Self::Cancelled => vec![OrderStatus::Cancelled],
+            Self::Scheduled => vec![OrderStatus::Scheduled],
         }
     }
 }
 
 #[derive(SimpleObject)]
+#[graphql(complex)]
 pub struct Order {
-    pub customer: User,
-    pub address: Address,
-    pub rider: Option<User>,
     pub items: Vec<OrderItem>,
+    /// Discount is already applied.
     pub total_price: Decimal,
     pub feedback: Option<Feedback>,
     pub indexed_order: IndexedOrder,
 }
 
-#[derive(SimpleObject, InputObject)]
+#[ComplexObject]
+impl Order {
+    async fn customer(&self, ctx: &Context<'_>) -> Result<User, AppError> {
+        loader::load::<UserLoader>(ctx, self.indexed_order.customer_id).await
+    }
+
+    /// Address entered by the gift recipient is hidden from the customer.
+    async fn address(&self, ctx: &Context<'_>) -> Result<Option<Address>, AppError> {
+        match self.visible_address_id(ctx) {
+            Some(id) => loader::load::<AddressLoader>(ctx, id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn rider(&self, ctx: &Context<'_>) -> Result<Option<User>, AppError> {
+        match self.indexed_order.rider_id {
+            Some(id) => loader::load::<UserLoader>(ctx, id).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Delivery instructions like a door code. Hidden from riders
+    /// until the order is assigned to them.
+    async fn comment(&self, ctx: &Context<'_>) -> Option<&str> {
+        let user = ctx.data_opt::<User>()?;
+        let order = &self.indexed_order;
+        let is_allowed = user.role == UserRole::Manager
+            || user.id == order.customer_id
+            || Some(user.id) == order.rider_id;
+        order.comment.as_deref().filter(|_| is_allowed)
+    }
+
+    /// Text attached to the package. Prices are omitted for gifts.
+    async fn docket(&self) -> String {
+        receipt::docket(self)
+    }
+
+    /// Provided only while the delivery order is in progress.
+    async fn navigation_info(&self, ctx: &Context<'_>) -> Result<Option<NavigationInfo>, AppError> {
+        let Some(address_id) = self.visible_address_id(ctx) else {
+            return Ok(None);
+        };
+        if !OrdersFilter::InProgress
+            .statuses()
+            .contains(&self.indexed_order.status)
+        {
+            return Ok(None);
+        }
+        loader::load::<AddressLoader>(ctx, address_id)
+            .await
+            .map(|address| Some(NavigationInfo::from(&address)))
+    }
+}
+
+impl Order {
+    fn visible_address_id(&self, ctx: &Context<'_>) -> Option<ID> {
+        let order = &self.indexed_order;
+        let is_hidden = order.is_recipient_address
+            && ctx
+                .data_opt::<User>()
+                .is_some_and(|user| user.id == order.customer_id);
+        order.address_id.filter(|_| !is_hidden)
+    }
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "OrderItemInput")]
 pub struct IndexedOrderItem {
     #[graphql(skip_input)]
     pub id: ID,
     pub food_id: ID,
     pub count: i32,
+    /// Item went out of stock after ordering and is excluded from the order total.
+    #[graphql(skip_input)]
+    pub is_unavailable: bool,
 }
 
 impl From<Row> for IndexedOrderItem {
@@ -347,6 +2048,7 @@ impl From<Row> for IndexedOrderItem {
             id: row.get("id"),
             food_id: row.get("food_id"),
             count: row.get("count"),
+            is_unavailable: row.get("is_unavailable"),
         }
     }
 }
@@ -358,16 +2060,24 @@ pub struct OrderItem {
     pub total_price: Decimal,
 }
 
-#[derive(SimpleObject, InputObject)]
+#[derive(Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "FeedbackInput")]
 pub struct Feedback {
     #[graphql(skip_input)]
     pub id: ID,
-    #[graphql(skip_output)]
     pub order_id: ID,
     /// From 0 to 5.
     pub rating: Option<i16>,
     pub comment: Option<String>,
+    /// Rating of the rider from 0 to 5. Only for delivered orders.
+    pub rider_rating: Option<i16>,
+    #[graphql(skip_input)]
+    pub create_time: NaiveDateTime,
+    /// Hidden feedbacks aren't counted in ratings.
+    #[graphql(skip_input)]
+    pub is_hidden: bool,
+    #[graphql(skip_input)]
+    pub hidden_reason: Option<String>,
 }
 
 impl From<Row> for Feedback {
@@ -377,6 +2087,114 @@ impl From<Row> for Feedback {
             order_id: row.get("order_id"),
             rating: row.get("rating"),
             comment: row.get("comment"),
+            rider_rating: row.get("rider_rating"),
+            create_time: row.get("create_time"),
+            is_hidden: row.get("is_hidden"),
+            hidden_reason: row.get("hidden_reason"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Enum)]
+pub enum FeedbacksFilter {
+    All,
+    Visible,
+    Hidden,
+}
+
+impl FeedbacksFilter {
+    /// Returns the required value of the hidden flag, `None` means any.
+    pub fn is_hidden(&self) -> Option<bool> {
+        match self {
+            Self::All => None,
+            Self::Visible => Some(false),
+            Self::Hidden => Some(true),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct RiderRatingSummary {
+    /// `null` if the rider wasn't rated yet.
+    pub average: Option<f64>,
+    pub count: i64,
+}
+
+impl From<Row> for RiderRatingSummary {
+    fn from(row: Row) -> Self {
+        Self {
+            average: row.get("average"),
+            count: row.get("count"),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct RatingCount {
+    /// `null` for feedbacks with a comment only.
+    pub rating: Option<i16>,
+    pub count: i64,
+}
+
+impl From<Row> for RatingCount {
+    fn from(row: Row) -> Self {
+        Self {
+            rating: row.get("rating"),
+            count: row.get("count"),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct KeywordCount {
+    /// Word or pair of adjacent words.
+    pub keyword: String,
+    /// Number of comments containing the keyword.
+    pub count: i64,
+}
+
+/// Feedbacks on the orders completed within the analyzed period.
+#[derive(SimpleObject)]
+pub struct FeedbackAnalytics {
+    pub ratings: Vec<RatingCount>,
+    /// Most frequent first.
+    pub keywords: Vec<KeywordCount>,
+}
+
+impl Validate for Feedback {
+    fn validate(&self) -> Result<(), AppError> {
+        self.rating
+            .map_or(Ok(()), |rating| check_range("rating", rating, 0..=5))?;
+        self.rider_rating
+            .map_or(Ok(()), |rating| check_range("riderRating", rating, 0..=5))?;
+        check_length("comment", self.comment.as_deref(), MAX_TEXT_LENGTH)
+    }
+}
+
+#[derive(Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "FeedbackPolicyInput")]
+pub struct FeedbackPolicy {
+    /// Customers can edit or delete their feedbacks within this time after leaving them.
+    /// Managers can do it at any time.
+    pub edit_hours: i32,
+}
+
+impl Default for FeedbackPolicy {
+    fn default() -> Self {
+        Self { edit_hours: 24 }
+    }
+}
+
+impl From<Row> for FeedbackPolicy {
+    fn from(row: Row) -> Self {
+        Self {
+            edit_hours: row.get("feedback_edit_hours"),
         }
     }
 }
+
+impl Validate for FeedbackPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        check_min("editHours", self.edit_hours, 0)
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Helpers of the end-to-end tests. Each test gets an own database which is created
+//! on a PostgreSQL container and dropped afterwards, so Docker is required.
+//! An existing server can be used instead by setting `TEST_DB_CONNECTION_STRING`.
+//! The connection string must be in the `key=value` format and belong to a user
+//! who can create databases and roles: the migrations make the `gogo` role
+//! the owner of the tables, so it's created if it doesn't exist.
+
+use std::{env, sync::Arc, thread};
+
+use actix_http::Request;
+use actix_web::{
+    dev::{Service, ServiceResponse},
+    http::{header, StatusCode},
+    rt::System,
+    test,
+    web::Data,
+    App,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use gogo_delivery::{
+    build_schema,
+    config::DatabaseConfig,
+    db::{self, Client},
+    persisted::{PersistedQueries, PersistedQueriesConfig},
+    rest::{self, SchemaOptions, IMPERSONATE_USER_HEADER},
+    scan::UploadScanner,
+    stats::ExecutionStats,
+    types::{UserRole, ID},
+};
+use serde_json::{json, Value};
+use testcontainers_modules::{
+    postgres::Postgres,
+    testcontainers::{runners::AsyncRunner, ContainerAsync, ImageExt},
+};
+use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+const PASSWORD: &str = "test-password";
+/// Image of the container, `DROP DATABASE ... WITH (FORCE)` requires PostgreSQL 13.
+const POSTGRES_TAG: &str = "15-alpine";
+
+/// Created using [TestApp::sign_up] or [TestApp::add_user].
+pub struct TestUser {
+    pub id: ID,
+    pub username: String,
+    pub password: String,
+}
+
+impl TestUser {
+    fn authorization(&self) -> String {
+        let credentials = STANDARD.encode(format!("{}:{}", self.username, self.password));
+        format!("Basic {credentials}")
+    }
+}
+
+pub trait AppService:
+    Service<Request, Response = ServiceResponse, Error = actix_web::Error>
+{
+}
+
+impl<S> AppService for S where
+    S: Service<Request, Response = ServiceResponse, Error = actix_web::Error>
+{
+}
+
+/// Service configured like the server, but without the middleware.
+pub struct TestApp<S> {
+    service: S,
+    db: Arc<Client>,
+    // Dropped last, when there are no connections to the database.
+    _database: TestDatabase,
+    _container: Option<ContainerAsync<Postgres>>,
+}
+
+/// Panics if the container can't be started.
+pub async fn spawn_app() -> TestApp<impl AppService> {
+    let (admin_connection_string, container) = match env::var("TEST_DB_CONNECTION_STRING") {
+        Ok(connection_string) => (connection_string, None),
+        Err(_) => {
+            let container = Postgres::default()
+                .with_tag(POSTGRES_TAG)
+                .start()
+                .await
+                .expect("unable to start the PostgreSQL container");
+            let host = container.get_host().await.unwrap();
+            let port = container.get_host_port_ipv4(5432).await.unwrap();
+            let connection_string =
+                format!("host={host} port={port} user=postgres password=postgres");
+            (connection_string, Some(container))
+        }
+    };
+    let database = TestDatabase::create(admin_connection_string).await;
+    let db = db::Client::connect(&database.connection_string(), &DatabaseConfig::default())
+        .await
+        .expect("unable to connect to the test database");
+    db.migrate(false)
+        .await
+        .expect("unable to apply the migrations");
+    let db = Arc::new(db);
+    let failures = db.check_statements().await;
+    assert!(failures.is_empty(), "statements don't match: {failures:?}");
+
+    let schema_options = SchemaOptions::default();
+    let schema = build_schema(
+        db.clone(),
+        schema_options,
+        UploadScanner::default(),
+        ExecutionStats::default(),
+    );
+    let service = test::init_service(
+        App::new()
+            .app_data(Data::new(schema))
+            .app_data(Data::new(Arc::clone(&db)))
+            .app_data(Data::new(
+                PersistedQueries::new(&PersistedQueriesConfig::default()).unwrap(),
+            ))
+            .configure(|config| rest::configure_service(config, schema_options)),
+    )
+    .await;
+    TestApp {
+        service,
+        db,
+        _database: database,
+        _container: container,
+    }
+}
+
+impl<S: AppService> TestApp<S> {
+    /// Signs up a customer using the REST endpoint.
+    pub async fn sign_up(&self, username: &str) -> TestUser {
+        let mut user = TestUser {
+            id: 0,
+            username: username.to_string(),
+            password: PASSWORD.to_string(),
+        };
+        let (status, body) = self.try_sign_up(&user).await;
+        assert_eq!(status, StatusCode::OK, "unable to sign up: {body}");
+        user.id = body.parse().expect("ID of the user is returned");
+        user
+    }
+
+    /// Returns the status and the body of the response.
+    pub async fn try_sign_up(&self, user: &TestUser) -> (StatusCode, String) {
+        let req = test::TestRequest::post()
+            .uri("/sign_up?birth_date=1990-01-01")
+            .insert_header((header::AUTHORIZATION, user.authorization()))
+            .to_request();
+        let response = test::call_service(&self.service, req).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Signs up a user and assigns the role.
+    pub async fn add_user(&self, username: &str, role: UserRole) -> TestUser {
+        let user = self.sign_up(username).await;
+        if role != UserRole::Customer {
+            assert!(self.db.set_user_role(username, role).await.unwrap());
+        }
+        user
+    }
+
+    /// Returns the status and the JSON response to the GraphQL request.
+    pub async fn execute(
+        &self,
+        user: &TestUser,
+        query: &str,
+        variables: Value,
+    ) -> (StatusCode, Value) {
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((header::AUTHORIZATION, user.authorization()))
+            .set_json(json!({ "query": query, "variables": variables }));
+        self.send(req).await
+    }
+
+    /// Executes the read-only named operation on behalf of the customer.
+    pub async fn execute_impersonated(
+        &self,
+        manager: &TestUser,
+        customer: &TestUser,
+        operation_name: &str,
+        query: &str,
+    ) -> (StatusCode, Value) {
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((header::AUTHORIZATION, manager.authorization()))
+            .insert_header((IMPERSONATE_USER_HEADER, customer.username.as_str()))
+            .set_json(json!({ "query": query, "operationName": operation_name }));
+        self.send(req).await
+    }
+
+    async fn send(&self, req: test::TestRequest) -> (StatusCode, Value) {
+        let response = test::call_service(&self.service, req.to_request()).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        let response = serde_json::from_slice(&body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()));
+        (status, response)
+    }
+
+    /// Panics if the request fails, otherwise returns the data.
+    pub async fn execute_ok(&self, user: &TestUser, query: &str, variables: Value) -> Value {
+        let (status, response) = self.execute(user, query, variables).await;
+        assert_eq!(status, StatusCode::OK, "{response}");
+        assert!(response.get("errors").is_none(), "{response}");
+        response["data"].clone()
+    }
+
+    /// Returns messages of the errors, panics if there are none.
+    pub async fn execute_err(&self, user: &TestUser, query: &str, variables: Value) -> Vec<String> {
+        let (_, response) = self.execute(user, query, variables).await;
+        let errors = response["errors"]
+            .as_array()
+            .unwrap_or_else(|| panic!("request didn't fail: {response}"));
+        errors
+            .iter()
+            .map(|err| err["message"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+struct TestDatabase {
+    admin_connection_string: String,
+    name: String,
+}
+
+impl TestDatabase {
+    async fn create(admin_connection_string: String) -> Self {
+        let name = format!("gogo_test_{}", Uuid::new_v4().simple());
+        execute_admin(
+            &admin_connection_string,
+            // Tests which run concurrently may create the role at the same time.
+            "DO $$ BEGIN
+                 CREATE ROLE gogo;
+             EXCEPTION WHEN duplicate_object OR unique_violation THEN NULL;
+             END $$",
+        )
+        .await;
+        // Can't be executed in the same batch, as it would be run in a transaction.
+        execute_admin(&admin_connection_string, &format!("CREATE DATABASE {name}")).await;
+        Self {
+            admin_connection_string,
+            name,
+        }
+    }
+
+    fn connection_string(&self) -> String {
+        // The last value of a parameter takes effect.
+        format!("{} dbname={}", self.admin_connection_string, self.name)
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let admin_connection_string = self.admin_connection_string.clone();
+        let statement = format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.name);
+        // Runtime of the test can't be blocked, so the database is dropped using another one.
+        let result = thread::spawn(move || {
+            System::new().block_on(execute_admin(&admin_connection_string, &statement))
+        })
+        .join();
+        if result.is_err() && !thread::panicking() {
+            panic!("unable to drop the test database {}", self.name);
+        }
+    }
+}
+
+async fn execute_admin(connection_string: &str, statement: &str) {
+    let (client, connection) = tokio_postgres::connect(connection_string, NoTls)
+        .await
+        .expect("unable to connect to the test server");
+    actix_web::rt::spawn(connection);
+    client
+        .batch_execute(statement)
+        .await
+        .unwrap_or_else(|e| panic!("unable to execute \"{statement}\": {e}"));
+}
@@ -0,0 +1,247 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! End-to-end tests of the GraphQL API against a migrated PostgreSQL database.
+
+mod common;
+
+use actix_web::http::StatusCode;
+use gogo_delivery::types::UserRole;
+use serde_json::{json, Value};
+
+use common::{spawn_app, TestUser};
+
+#[actix_web::test]
+async fn signed_up_customer_is_authenticated() {
+    let app = spawn_app().await;
+    let customer = app.sign_up("customer").await;
+    let data = app
+        .execute_ok(&customer, "{ currentUser { id username role } }", json!({}))
+        .await;
+    assert_eq!(
+        data["currentUser"],
+        json!({ "id": customer.id, "username": "customer", "role": "CUSTOMER" })
+    );
+
+    let (status, _) = app.try_sign_up(&customer).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let impostor = TestUser {
+        password: "wrong-password".to_string(),
+        ..customer
+    };
+    let (status, _) = app
+        .execute(&impostor, "{ currentUser { id } }", json!({}))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn manager_adds_food_visible_to_customers() {
+    let app = spawn_app().await;
+    let manager = app.add_user("manager", UserRole::Manager).await;
+    let customer = app.sign_up("customer").await;
+
+    let errors = app
+        .execute_err(
+            &customer,
+            "mutation { addCategory(category: { title: \"Soups\" }) }",
+            json!({}),
+        )
+        .await;
+    assert!(!errors.is_empty());
+
+    let category_id = add_category(&app, &manager, "Soups").await;
+    let food_id = add_food(&app, &manager, &category_id, "Borscht", "4.50").await;
+    let data = app
+        .execute_ok(
+            &customer,
+            "query($categoryId: Int!) {
+                foodInCategory(categoryId: $categoryId, sortBy: TITLE, sortOrder: ASCENDING) {
+                    id title price count
+                }
+            }",
+            json!({ "categoryId": category_id }),
+        )
+        .await;
+    assert_eq!(
+        data["foodInCategory"],
+        json!([{ "id": food_id, "title": "Borscht", "price": "4.50", "count": 10 }])
+    );
+    let data = app
+        .execute_ok(
+            &customer,
+            "query($id: Int!) { food(id: $id) { category { title } } }",
+            json!({ "id": food_id }),
+        )
+        .await;
+    assert_eq!(data["food"]["category"]["title"], "Soups");
+}
+
+#[actix_web::test]
+async fn order_is_delivered_by_rider() {
+    let app = spawn_app().await;
+    let manager = app.add_user("manager", UserRole::Manager).await;
+    let rider = app.add_user("rider", UserRole::Rider).await;
+    let customer = app.sign_up("customer").await;
+    let category_id = add_category(&app, &manager, "Soups").await;
+    let food_id = add_food(&app, &manager, &category_id, "Borscht", "4.50").await;
+
+    let address_id = app
+        .execute_ok(
+            &customer,
+            "mutation {
+                addUserAddress(address: { locality: \"Minsk\", street: \"Main\", house: 1 })
+            }",
+            json!({}),
+        )
+        .await["addUserAddress"]
+        .clone();
+    app.execute_ok(
+        &customer,
+        "mutation($foodId: Int!) { addUserCartItem(item: { foodId: $foodId, count: 2 }) }",
+        json!({ "foodId": food_id }),
+    )
+    .await;
+    let order_id = app
+        .execute_ok(
+            &customer,
+            "mutation($addressId: Int!) {
+                makeOrderFromUserCart(order: { addressId: $addressId })
+            }",
+            json!({ "addressId": address_id }),
+        )
+        .await["makeOrderFromUserCart"]
+        .clone();
+
+    let data = app
+        .execute_ok(
+            &rider,
+            "{ availableOrders { indexedOrder { id status } totalPrice } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        data["availableOrders"],
+        json!([{
+            "indexedOrder": { "id": order_id, "status": "CREATED" },
+            "totalPrice": "9.00"
+        }])
+    );
+    let variables = json!({ "id": order_id });
+    for (mutation, result) in [
+        ("takeOrder(id: $id)", json!(true)),
+        ("advanceOrderStatus(id: $id)", json!("PICKED_UP")),
+        ("completeOrder(id: $id)", json!(true)),
+    ] {
+        let data = app
+            .execute_ok(
+                &rider,
+                &format!("mutation($id: Int!) {{ {mutation} }}"),
+                variables.clone(),
+            )
+            .await;
+        assert_eq!(first_value(&data), &result, "{mutation}");
+    }
+
+    let data = app
+        .execute_ok(
+            &customer,
+            "{ userOrders(filter: ALL) { indexedOrder { id status } } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        data["userOrders"],
+        json!([{ "indexedOrder": { "id": order_id, "status": "DELIVERED" } }])
+    );
+}
+
+#[actix_web::test]
+async fn impersonation_is_recorded_for_both_users() {
+    let app = spawn_app().await;
+    let manager = app.add_user("manager", UserRole::Manager).await;
+    let customer = app.sign_up("customer").await;
+
+    let (status, response) = app
+        .execute_impersonated(
+            &manager,
+            &customer,
+            "CurrentUser",
+            "query CurrentUser { currentUser { username } }",
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{response}");
+    assert_eq!(response["data"]["currentUser"]["username"], "customer");
+    let (_, response) = app
+        .execute_impersonated(
+            &manager,
+            &customer,
+            "AddAddress",
+            "mutation AddAddress { addUserAddress(address: { locality: \"Minsk\", street: \"Main\", house: 1 }) }",
+        )
+        .await;
+    assert!(response.get("errors").is_some(), "{response}");
+
+    let query = "{ accountActivity { kind details } }";
+    let data = app.execute_ok(&customer, query, json!({})).await;
+    assert_eq!(
+        data["accountActivity"][0],
+        json!({
+            "kind": "IMPERSONATED",
+            "details": "read-only \"CurrentUser\" by manager \"manager\""
+        })
+    );
+    let data = app.execute_ok(&manager, query, json!({})).await;
+    assert_eq!(
+        data["accountActivity"][0],
+        json!({
+            "kind": "IMPERSONATING",
+            "details": "read-only \"CurrentUser\" as customer \"customer\""
+        })
+    );
+}
+
+async fn add_category<S>(app: &common::TestApp<S>, manager: &TestUser, title: &str) -> Value
+where
+    S: common::AppService,
+{
+    app.execute_ok(
+        manager,
+        "mutation($title: String!) { addCategory(category: { title: $title }) }",
+        json!({ "title": title }),
+    )
+    .await["addCategory"]
+        .clone()
+}
+
+async fn add_food<S>(
+    app: &common::TestApp<S>,
+    manager: &TestUser,
+    category_id: &Value,
+    title: &str,
+    price: &str,
+) -> Value
+where
+    S: common::AppService,
+{
+    app.execute_ok(
+        manager,
+        "mutation($food: FoodInput!) { addFood(food: $food) }",
+        json!({ "food": {
+            "title": title,
+            "categoryId": category_id,
+            "count": 10,
+            "isAlcohol": false,
+            "price": price
+        } }),
+    )
+    .await["addFood"]
+        .clone()
+}
+
+fn first_value(data: &Value) -> &Value {
+    data.as_object()
+        .and_then(|data| data.values().next())
+        .unwrap()
+}
@@ -0,0 +1,921 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! [Datastore] which keeps everything in memory. Only the methods used by the tests
+//! are implemented, they follow the semantics of the statements executed by
+//! [gogo_delivery::db::Client].
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use async_graphql::{connection::Edge, OutputType};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use gogo_delivery::{
+    datastore::Datastore,
+    db::{Error, Result},
+    sha256,
+    types::*,
+    Device,
+};
+use rust_decimal::Decimal;
+
+/// Declares the methods which aren't used by the tests, they fail with
+/// [Error::Invalid] if called.
+macro_rules! memory_datastore {
+    (
+        { $($implemented:tt)* }
+        $(async fn $name:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;)*
+    ) => {
+        #[async_trait]
+        impl Datastore for MemoryDatastore {
+            $($implemented)*
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> $ret {
+                    let _ = ($($arg,)*);
+                    Err(Error::Invalid(
+                        concat!(stringify!($name), " isn't supported in memory").to_string(),
+                    ))
+                }
+            )*
+        }
+    };
+}
+
+#[derive(Default)]
+pub struct MemoryDatastore {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The last ID issued for each table.
+    sequences: HashMap<&'static str, ID>,
+    users: Vec<User>,
+    /// Entities paired with ID of the owner.
+    addresses: Vec<(ID, Address)>,
+    cart: Vec<(ID, IndexedCartItem)>,
+    categories: Vec<Category>,
+    food: Vec<IndexedFood>,
+    orders: Vec<(IndexedOrder, Vec<IndexedOrderItem>)>,
+}
+
+impl MemoryDatastore {
+    /// Adds a user with the username, the password (not hashed) and the role.
+    pub fn add_user(&self, username: &str, password: &str, role: UserRole) -> ID {
+        let mut state = self.state();
+        let id = state.next_id("users");
+        state.users.push(User {
+            id,
+            username: username.to_string(),
+            password: sha256(password),
+            first_name: None,
+            last_name: None,
+            birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+            role,
+            segment: None,
+        });
+        id
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // State is consistent between statements, so a panicked test doesn't break others.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+memory_datastore! {
+    {
+        async fn is_credentials_valid(&self, username: &str, password: &str) -> Result<bool> {
+            let password = sha256(password);
+            Ok(self
+                .state()
+                .users
+                .iter()
+                .any(|user| user.username == username && user.password == password))
+        }
+
+        async fn user_by_name(&self, username: &str) -> Result<User> {
+            self.state()
+                .find_user(username)
+                .cloned()
+                .ok_or_else(|| Error::NotFound(format!("there is no user \"{username}\"")))
+        }
+
+        /// Activity isn't queried by the tests, so it's not stored.
+        async fn add_user_activity(
+            &self,
+            _username: &str,
+            _kind: ActivityKind,
+            _device: &Device,
+            _details: Option<&str>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        /// There are no sessions, so only the password is changed.
+        async fn revoke_other_user_sessions(
+            &self,
+            username: &str,
+            _current_device: &Device,
+            new_password: &str,
+        ) -> Result<bool> {
+            let mut state = self.state();
+            let user_id = state.user_id(username)?;
+            let user = state.users.iter_mut().find(|user| user.id == user_id).unwrap();
+            user.password = sha256(new_password);
+            Ok(true)
+        }
+
+        async fn add_user_address(&self, username: &str, address: Address) -> Result<ID> {
+            let mut state = self.state();
+            let user_id = state.user_id(username)?;
+            let id = state.next_id("addresses");
+            let is_default = !owned_by(&state.addresses, user_id).any(|address| address.is_default);
+            let address = Address {
+                id,
+                is_default,
+                delete_time: None,
+                ..address
+            };
+            state.addresses.push((user_id, address));
+            Ok(id)
+        }
+
+        async fn similar_category_id(&self, title: &str) -> Result<Option<ID>> {
+            let title = normalize(title);
+            Ok(self
+                .state()
+                .categories
+                .iter()
+                .find(|category| normalize(&category.title) == title)
+                .map(|category| category.id))
+        }
+
+        async fn add_category(
+            &self,
+            _manager_username: &str,
+            category: &Category,
+            _preview: Option<Vec<u8>>,
+        ) -> Result<ID> {
+            let mut state = self.state();
+            let id = state.next_id("categories");
+            state.categories.push(Category {
+                id,
+                ..category.clone()
+            });
+            Ok(id)
+        }
+
+        /// Food references its category, so the category can't be deleted while it has food.
+        async fn delete_category(&self, _manager_username: &str, id: ID) -> Result<bool> {
+            let mut state = self.state();
+            if state.food.iter().any(|food| food.category_id == id) {
+                return Err(Error::Conflict(
+                    "entity is referenced by another one".to_string(),
+                ));
+            }
+            let count = state.categories.len();
+            state.categories.retain(|category| category.id != id);
+            Ok(state.categories.len() != count)
+        }
+
+        async fn similar_food_id(&self, category_id: ID, title: &str) -> Result<Option<ID>> {
+            let title = normalize(title);
+            Ok(self
+                .state()
+                .food
+                .iter()
+                .find(|food| food.category_id == category_id && normalize(&food.title) == title)
+                .map(|food| food.id))
+        }
+
+        async fn food_by_id(&self, id: ID) -> Result<Option<Food>> {
+            Ok(self.state().find_food(id).map(|food| Food {
+                indexed_food: food.clone(),
+            }))
+        }
+
+        async fn food_in_category(
+            &self,
+            category_id: ID,
+            filter: FoodFilter,
+            sort_by: SortFoodBy,
+            sort_order: SortOrder,
+            pagination: Pagination,
+        ) -> Result<Vec<IndexedFood>> {
+            let mut food: Vec<IndexedFood> = self
+                .state()
+                .food
+                .iter()
+                .filter(|food| {
+                    food.category_id == category_id
+                        && filter.min_price.is_none_or(|price| food.price >= price)
+                        && filter.max_price.is_none_or(|price| food.price <= price)
+                        && !(filter.exclude_alcohol && food.is_alcohol)
+                        && !(filter.in_stock_only && food.count <= 0)
+                })
+                .cloned()
+                .collect();
+            food.sort_by(|a, b| {
+                directed(
+                    compare_cursors(&sort_by.cursor(a), &sort_by.cursor(b)),
+                    sort_order,
+                )
+            });
+            Ok(paginate(food, pagination))
+        }
+
+        async fn food_connection(
+            &self,
+            category_id: ID,
+            sort_by: SortFoodBy,
+            sort_order: SortOrder,
+            first: i64,
+            after: Option<Cursor>,
+        ) -> Result<Connection<IndexedFood>> {
+            let food = self
+                .state()
+                .food
+                .iter()
+                .filter(|food| food.category_id == category_id)
+                .cloned()
+                .collect();
+            Ok(page(food, sort_order, first, after, |food| {
+                sort_by.cursor(food)
+            }))
+        }
+
+        async fn add_food(
+            &self,
+            _manager_username: &str,
+            food: &IndexedFood,
+            _preview: Option<Vec<u8>>,
+        ) -> Result<ID> {
+            let mut state = self.state();
+            if !state
+                .categories
+                .iter()
+                .any(|category| category.id == food.category_id)
+            {
+                return Err(foreign_key_violation());
+            }
+            if food.count < 0 {
+                return Err(check_violation());
+            }
+            let id = state.next_id("food");
+            state.food.push(IndexedFood {
+                id,
+                average_rating: None,
+                ratings_count: 0,
+                ..food.clone()
+            });
+            Ok(id)
+        }
+
+        /// Deleted food is removed from carts.
+        async fn delete_food(&self, _manager_username: &str, id: ID) -> Result<bool> {
+            let mut state = self.state();
+            let count = state.food.len();
+            state.food.retain(|food| food.id != id);
+            state.cart.retain(|(_, item)| item.food_id != id);
+            Ok(state.food.len() != count)
+        }
+
+        async fn user_cart(
+            &self,
+            username: &str,
+            sort_by: SortCartBy,
+            sort_order: SortOrder,
+        ) -> Result<Cart> {
+            let state = self.state();
+            let user_id = state.user_id(username)?;
+            Ok(state.cart(user_id, sort_by, sort_order))
+        }
+
+        /// If the food is already in the cart, increments count of the existing item.
+        /// Returns ID of the item and whether it was inserted.
+        async fn add_user_cart_item(
+            &self,
+            username: &str,
+            item: &IndexedCartItem,
+        ) -> Result<(ID, bool)> {
+            let mut state = self.state();
+            let user_id = state.user_id(username)?;
+            if let Some(existing) = state
+                .cart
+                .iter_mut()
+                .find(|(owner, existing)| *owner == user_id && existing.food_id == item.food_id)
+                .map(|(_, existing)| existing)
+            {
+                if existing.count + item.count <= 0 {
+                    return Err(check_violation());
+                }
+                existing.count += item.count;
+                return Ok((existing.id, false));
+            }
+            if state.find_food(item.food_id).is_none() {
+                return Err(foreign_key_violation());
+            }
+            if item.count <= 0 {
+                return Err(check_violation());
+            }
+            let id = state.next_id("cart");
+            state.cart.push((
+                user_id,
+                IndexedCartItem {
+                    id,
+                    add_time: now(),
+                    ..item.clone()
+                },
+            ));
+            Ok((id, true))
+        }
+
+        /// The newest orders first.
+        async fn user_orders(
+            &self,
+            username: &str,
+            filter: OrdersFilter,
+            pagination: Pagination,
+        ) -> Result<Vec<Order>> {
+            let state = self.state();
+            let user_id = state.user_id(username)?;
+            let statuses = filter.statuses();
+            let mut orders: Vec<_> = state
+                .orders
+                .iter()
+                .filter(|(order, _)| {
+                    order.customer_id == user_id && statuses.contains(&order.status)
+                })
+                .collect();
+            orders.sort_by_key(|(order, _)| Reverse((order.create_time, order.id)));
+            Ok(paginate(orders, pagination)
+                .into_iter()
+                .map(|(order, items)| state.to_order(order, items))
+                .collect())
+        }
+
+        async fn available_orders(
+            &self,
+            sort_order: SortOrder,
+            limit: i64,
+            location_id: Option<ID>,
+        ) -> Result<Vec<Order>> {
+            let state = self.state();
+            let mut orders: Vec<_> = state
+                .orders
+                .iter()
+                .filter(|(order, _)| {
+                    order.rider_id.is_none()
+                        && order.status == OrderStatus::Created
+                        && order.fulfillment == FulfillmentType::Delivery
+                        && order.address_id.is_some()
+                        && location_id.is_none_or(|id| order.location_id == Some(id))
+                })
+                .collect();
+            orders.sort_by(|(a, _), (b, _)| {
+                directed((a.create_time, a.id).cmp(&(b.create_time, b.id)), sort_order)
+            });
+            Ok(orders
+                .into_iter()
+                .take(limit.clamp(0, MAX_PAGE_SIZE) as usize)
+                .map(|(order, items)| state.to_order(order, items))
+                .collect())
+        }
+
+        /// Only ASAP delivery to an own address is supported.
+        async fn make_order_from_user_cart(
+            &self,
+            username: &str,
+            order: IndexedOrder,
+            promo_code: Option<&str>,
+        ) -> Result<ID> {
+            if order.fulfillment != FulfillmentType::Delivery
+                || order.gift.is_some()
+                || order.scheduled_for.is_some()
+                || promo_code.is_some()
+            {
+                return Err(Error::Invalid(
+                    "only ASAP delivery without a promo code is supported in memory".to_string(),
+                ));
+            }
+            let mut state = self.state();
+            let user_id = state.user_id(username)?;
+            let address_id = order.address_id.filter(|&id| {
+                owned_by(&state.addresses, user_id)
+                    .any(|address| address.id == id && address.delete_time.is_none())
+            });
+            if address_id.is_none() {
+                return Err(check_violation());
+            }
+            let cart_items = state
+                .cart(user_id, SortCartBy::AddTime, SortOrder::Ascending)
+                .items;
+            if cart_items.is_empty() {
+                return Err(Error::Invalid("user cart is empty".to_string()));
+            }
+            let mut shortages: Vec<StockShortage> = cart_items
+                .iter()
+                .filter(|item| item.food.indexed_food.count < item.indexed_cart_item.count)
+                .map(|item| StockShortage {
+                    food_id: item.indexed_cart_item.food_id,
+                    title: item.food.indexed_food.title.clone(),
+                    requested: item.indexed_cart_item.count,
+                    available: item.food.indexed_food.count,
+                })
+                .collect();
+            if !shortages.is_empty() {
+                shortages.sort_by(|a, b| a.title.cmp(&b.title));
+                return Err(Error::OutOfStock(shortages));
+            }
+
+            let order_id = state.next_id("orders");
+            let mut items = Vec::with_capacity(cart_items.len());
+            for cart_item in &cart_items {
+                let IndexedCartItem { food_id, count, .. } = cart_item.indexed_cart_item;
+                let food = state.food.iter_mut().find(|food| food.id == food_id).unwrap();
+                food.count -= count;
+                items.push(IndexedOrderItem {
+                    id: state.next_id("orders_food"),
+                    food_id,
+                    count,
+                    is_unavailable: false,
+                });
+            }
+            let order = IndexedOrder {
+                id: order_id,
+                customer_id: user_id,
+                address_id,
+                create_time: now(),
+                status: OrderStatus::Created,
+                ..order
+            };
+            state.orders.push((order, items));
+            state.cart.retain(|(owner, _)| *owner != user_id);
+            Ok(order_id)
+        }
+
+        async fn take_order(&self, username: &str, id: ID) -> Result<bool> {
+            let mut state = self.state();
+            let rider_id = state.user_id(username)?;
+            Ok(state.advance_order(id, OrderStatus::Created, None, rider_id))
+        }
+
+        async fn complete_order(&self, username: &str, id: ID) -> Result<bool> {
+            let mut state = self.state();
+            let rider_id = state.user_id(username)?;
+            Ok(state.advance_order(id, OrderStatus::PickedUp, Some(rider_id), rider_id))
+        }
+
+        async fn advance_order_status(&self, username: &str, id: ID) -> Result<OrderStatus> {
+            let mut state = self.state();
+            let rider_id = state.user_id(username)?;
+            let (order, _) = state
+                .orders
+                .iter()
+                .find(|(order, _)| order.id == id)
+                .ok_or_else(|| Error::NotFound("there is no order with such ID".to_string()))?;
+            let status = order.status;
+            let next_status = status.next(order.fulfillment).ok_or_else(|| {
+                Error::Conflict(format!("order with status {status:?} can't be advanced"))
+            })?;
+            let assigned_rider = Some(rider_id).filter(|_| status != OrderStatus::Created);
+            if !state.advance_order(id, status, assigned_rider, rider_id) {
+                return Err(Error::Conflict(
+                    "order isn't assigned to the rider or its status was changed".to_string(),
+                ));
+            }
+            Ok(next_status)
+        }
+
+        async fn categories_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Category>> {
+            Ok(self
+                .state()
+                .categories
+                .iter()
+                .filter(|category| ids.contains(&category.id))
+                .map(|category| (category.id, category.clone()))
+                .collect())
+        }
+    }
+
+    async fn find_user_by_name(&self, username: &str) -> Result<Option<User>>;
+    async fn user_by_id(&self, id: ID) -> Result<Option<User>>;
+    async fn users(
+        &self,
+        role: Option<UserRole>,
+        sort_by: SortUsersBy,
+        sort_order: SortOrder,
+        pagination: Pagination,
+    ) -> Result<Vec<User>>;
+    async fn update_user(&self, username: &str, patch: &UserPatch) -> Result<bool>;
+    async fn set_user_password(&self, username: &str, password: &str) -> Result<bool>;
+    async fn erase_user(&self, username: &str) -> Result<()>;
+    async fn set_user_role(&self, username: &str, role: UserRole) -> Result<bool>;
+    async fn user_activities(&self, username: &str) -> Result<Vec<Activity>>;
+    async fn user_sessions(&self, username: &str, current_device: &Device) -> Result<Vec<Session>>;
+    async fn user_notifications(
+        &self,
+        username: &str,
+        unread_only: bool,
+    ) -> Result<Vec<Notification>>;
+    async fn delete_user_notification(&self, username: &str, id: ID) -> Result<bool>;
+    async fn unread_user_notifications_count(&self, username: &str) -> Result<i64>;
+    async fn read_user_notification(&self, username: &str, id: ID) -> Result<bool>;
+    async fn read_user_notifications(&self, username: &str) -> Result<bool>;
+    async fn add_user_notification(&self, user_id: ID, notification: &Notification) -> Result<ID>;
+    async fn add_notifications(
+        &self,
+        target_users_role: UserRole,
+        target_segment: Option<CustomerSegment>,
+        notification: Notification,
+    ) -> Result<Vec<ID>>;
+    async fn maintenance(&self) -> Result<Maintenance>;
+    async fn set_maintenance(&self, maintenance: &Maintenance) -> Result<()>;
+    async fn birthday_promo_settings(&self) -> Result<BirthdayPromoSettings>;
+    async fn set_birthday_promo_settings(&self, settings: &BirthdayPromoSettings) -> Result<()>;
+    async fn late_delivery_policy(&self) -> Result<LateDeliveryPolicy>;
+    async fn set_late_delivery_policy(&self, policy: &LateDeliveryPolicy) -> Result<()>;
+    async fn sla_policy(&self) -> Result<SlaPolicy>;
+    async fn set_sla_policy(&self, policy: &SlaPolicy) -> Result<()>;
+    async fn sla_report(&self, days: i32) -> Result<SlaReport>;
+    async fn incidents(&self) -> Result<Vec<Incident>>;
+    async fn add_incident(&self, incident: &Incident) -> Result<ID>;
+    async fn resolve_incident(&self, id: ID) -> Result<bool>;
+    async fn order_scheduling(&self) -> Result<OrderScheduling>;
+    async fn set_order_scheduling(&self, scheduling: &OrderScheduling) -> Result<()>;
+    async fn order_capacity(&self) -> Result<Option<i32>>;
+    async fn set_order_capacity(&self, capacity: Option<i32>) -> Result<()>;
+    async fn order_queue_position(
+        &self,
+        username: &str,
+        order_id: ID,
+    ) -> Result<Option<QueuePosition>>;
+    async fn late_delivery_report(&self, days: i32) -> Result<LateDeliveryReport>;
+    async fn settlements(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Settlement>>;
+    async fn user_promo_codes(&self, username: &str) -> Result<Vec<PromoCode>>;
+    async fn api_keys(&self) -> Result<Vec<ApiKey>>;
+    async fn add_api_key(&self, title: &str, key: &str, scope: ApiKeyScope) -> Result<ID>;
+    async fn api_usage(&self, pagination: Pagination) -> Result<Vec<ApiUsage>>;
+    async fn user_api_usage(&self, username: &str) -> Result<ApiUsage>;
+    async fn delete_api_key(&self, id: ID) -> Result<bool>;
+    async fn user_addresses(&self, username: &str) -> Result<Vec<Address>>;
+    async fn update_user_address(&self, username: &str, id: ID, address: &Address) -> Result<bool>;
+    async fn set_default_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    async fn delete_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    async fn trashed_user_addresses(&self, username: &str) -> Result<Vec<Address>>;
+    async fn restore_user_address(&self, username: &str, id: ID) -> Result<bool>;
+    async fn categories(&self, pagination: Option<Pagination>) -> Result<Vec<Category>>;
+    async fn category_by_id(&self, id: ID) -> Result<Option<Category>>;
+    async fn export_catalog(&self) -> Result<CatalogDocument>;
+    async fn import_catalog(
+        &self,
+        manager_username: &str,
+        document: &CatalogDocument,
+    ) -> Result<CatalogImportSummary>;
+    async fn update_category(
+        &self,
+        manager_username: &str,
+        id: ID,
+        category: &Category,
+        preview: Option<Option<Vec<u8>>>,
+    ) -> Result<bool>;
+    async fn update_food(&self, manager_username: &str, id: ID, patch: &FoodPatch) -> Result<bool>;
+    async fn locations(&self) -> Result<Vec<Location>>;
+    async fn add_location(&self, location: &Location) -> Result<ID>;
+    async fn set_location_stock(
+        &self,
+        manager_username: &str,
+        location_id: ID,
+        food_id: ID,
+        count: i32,
+    ) -> Result<i32>;
+    async fn location_stock(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<LocationStock>>>;
+    async fn price_history(&self, food_ids: &[ID]) -> Result<HashMap<ID, Vec<PriceChange>>>;
+    async fn restock_food(
+        &self,
+        manager_username: &str,
+        id: ID,
+        quantity: i32,
+        comment: Option<&str>,
+    ) -> Result<Option<i32>>;
+    async fn adjust_food_stock(
+        &self,
+        manager_username: &str,
+        id: ID,
+        delta: i32,
+        reason: &str,
+    ) -> Result<Option<i32>>;
+    async fn reorder_suggestions(
+        &self,
+        days: i32,
+        lookback_days: i32,
+        cover_days: i32,
+    ) -> Result<Vec<ReorderSuggestion>>;
+    async fn stock_history(
+        &self,
+        food_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<StockMovement>>;
+    async fn catalog_history(
+        &self,
+        entity: Option<CatalogEntity>,
+        entity_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<CatalogChange>>;
+    async fn revert_catalog_change(&self, manager_username: &str, id: ID) -> Result<ID>;
+    async fn storage_usage(&self) -> Result<StorageUsage>;
+    async fn take_upload(&self, username: &str, token: &str) -> Result<Vec<u8>>;
+    async fn is_user_favorite(&self, username: &str, food_id: ID) -> Result<bool>;
+    async fn user_favorites(
+        &self,
+        username: &str,
+        collection_id: Option<ID>,
+        pagination: Pagination,
+    ) -> Result<Vec<Favorite>>;
+    async fn add_user_favorite(&self, username: &str, favorite: &IndexedFavorite) -> Result<ID>;
+    async fn delete_user_favorite(&self, username: &str, id: ID) -> Result<bool>;
+    async fn move_user_favorite(
+        &self,
+        username: &str,
+        id: ID,
+        collection_id: Option<ID>,
+    ) -> Result<bool>;
+    async fn user_favorite_collections(&self, username: &str) -> Result<Vec<FavoriteCollection>>;
+    async fn add_user_favorite_collection(
+        &self,
+        username: &str,
+        collection: &FavoriteCollection,
+    ) -> Result<ID>;
+    async fn update_user_favorite_collection(
+        &self,
+        username: &str,
+        id: ID,
+        collection: &FavoriteCollection,
+    ) -> Result<bool>;
+    async fn delete_user_favorite_collection(&self, username: &str, id: ID) -> Result<bool>;
+    async fn delete_user_favorites(&self, username: &str, ids: &[ID]) -> Result<u64>;
+    async fn add_favorites_to_cart(&self, username: &str, ids: &[ID]) -> Result<u64>;
+    async fn trashed_user_favorites(&self, username: &str) -> Result<Vec<Favorite>>;
+    async fn restore_user_favorite(&self, username: &str, id: ID) -> Result<bool>;
+    async fn is_in_user_cart(&self, username: &str, food_id: ID) -> Result<bool>;
+    async fn reorder(&self, username: &str, order_id: ID) -> Result<Reorder>;
+    async fn update_user_cart_item(&self, username: &str, id: ID, count: i32) -> Result<bool>;
+    async fn delete_user_cart_item(&self, username: &str, id: ID) -> Result<bool>;
+    async fn orders(&self, filter: OrdersFilter, pagination: Pagination) -> Result<Vec<Order>>;
+    async fn rider_active_orders(&self, username: &str) -> Result<Vec<Order>>;
+    async fn orders_connection(
+        &self,
+        username: Option<&str>,
+        filter: OrdersFilter,
+        first: i64,
+        after: Option<Cursor>,
+    ) -> Result<Connection<Order>>;
+    async fn create_gift_address_link(&self, username: &str, order_id: ID) -> Result<String>;
+    async fn add_rider_ping(&self, username: &str, location_id: ID) -> Result<()>;
+    async fn rider_coverage(&self, days: i32) -> Result<Vec<RiderCoverage>>;
+    async fn release_order(
+        &self,
+        id: ID,
+        rider_username: Option<&str>,
+        released_by: &str,
+    ) -> Result<bool>;
+    async fn mark_order_ready_for_pickup(&self, id: ID) -> Result<bool>;
+    async fn hand_over_order(&self, id: ID, pickup_code: &str) -> Result<bool>;
+    async fn cancellation_policy(&self) -> Result<CancellationPolicy>;
+    async fn set_cancellation_policy(&self, policy: &CancellationPolicy) -> Result<()>;
+    async fn cancellation_fee(&self, id: ID, customer_username: &str) -> Result<Decimal>;
+    async fn cancel_order(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+        confirmed_fee: Option<Decimal>,
+        reason: Option<&str>,
+    ) -> Result<Decimal>;
+    async fn mark_order_item_unavailable(&self, id: ID) -> Result<bool>;
+    async fn add_user_feedback(&self, username: &str, feedback: &Feedback) -> Result<ID>;
+    async fn update_user_feedback(
+        &self,
+        id: ID,
+        customer_username: Option<&str>,
+        rating: Option<i16>,
+        comment: Option<&str>,
+    ) -> Result<()>;
+    async fn delete_user_feedback(&self, id: ID, customer_username: Option<&str>) -> Result<()>;
+    async fn feedback_policy(&self) -> Result<FeedbackPolicy>;
+    async fn set_feedback_policy(&self, policy: &FeedbackPolicy) -> Result<()>;
+    async fn feedbacks(
+        &self,
+        filter: FeedbacksFilter,
+        pagination: Pagination,
+    ) -> Result<Vec<Feedback>>;
+    async fn hide_feedback(&self, id: ID, reason: &str) -> Result<()>;
+    async fn rider_rating_summary(&self, username: &str) -> Result<RiderRatingSummary>;
+    async fn duplicate_users(&self) -> Result<Vec<DuplicateUsers>>;
+    async fn merge_users(
+        &self,
+        source_id: ID,
+        target_id: ID,
+        manager_username: &str,
+    ) -> Result<UserMerge>;
+    async fn users_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, User>>;
+    async fn addresses_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Address>>;
+    async fn food_by_ids(&self, ids: &[ID]) -> Result<HashMap<ID, Food>>;
+    async fn feedback_analytics(
+        &self,
+        days: i32,
+        max_rating: Option<i16>,
+        keyword_limit: usize,
+    ) -> Result<FeedbackAnalytics>;
+}
+
+impl State {
+    fn next_id(&mut self, table: &'static str) -> ID {
+        let id = self.sequences.entry(table).or_default();
+        *id += 1;
+        *id
+    }
+
+    fn find_user(&self, username: &str) -> Option<&User> {
+        self.users.iter().find(|user| user.username == username)
+    }
+
+    /// ID of the current user, who must exist.
+    fn user_id(&self, username: &str) -> Result<ID> {
+        self.find_user(username)
+            .map(|user| user.id)
+            .ok_or_else(|| Error::UnknownUser(username.to_string()))
+    }
+
+    fn find_food(&self, id: ID) -> Option<&IndexedFood> {
+        self.food.iter().find(|food| food.id == id)
+    }
+
+    fn cart(&self, user_id: ID, sort_by: SortCartBy, sort_order: SortOrder) -> Cart {
+        let mut items: Vec<&IndexedCartItem> = owned_by(&self.cart, user_id).collect();
+        items.sort_by(|a, b| {
+            let ordering = match sort_by {
+                SortCartBy::Count => a.count.cmp(&b.count),
+                SortCartBy::AddTime => a.add_time.cmp(&b.add_time),
+            };
+            directed(ordering.then(a.id.cmp(&b.id)), sort_order)
+        });
+        let items: Vec<CartItem> = items
+            .into_iter()
+            .filter_map(|item| {
+                let indexed_food = self.find_food(item.food_id)?.clone();
+                Some(CartItem {
+                    total_price: indexed_food.price * Decimal::from(item.count),
+                    food: Food { indexed_food },
+                    indexed_cart_item: item.clone(),
+                })
+            })
+            .collect();
+        Cart {
+            total_price: items.iter().map(|item| item.total_price).sum(),
+            items,
+        }
+    }
+
+    /// Changes status of the order to the next one if the order has `status` and
+    /// is assigned to `assigned_rider`. Taken orders are assigned to `rider_id`.
+    fn advance_order(
+        &mut self,
+        id: ID,
+        status: OrderStatus,
+        assigned_rider: Option<ID>,
+        rider_id: ID,
+    ) -> bool {
+        let order = self
+            .orders
+            .iter_mut()
+            .map(|(order, _)| order)
+            .find(|order| {
+                order.id == id
+                    && order.status == status
+                    && order.rider_id == assigned_rider
+                    && order.fulfillment == FulfillmentType::Delivery
+            });
+        let Some(order) = order else {
+            return false;
+        };
+        let Some(next_status) = status.next(order.fulfillment) else {
+            return false;
+        };
+        order.status = next_status;
+        order.rider_id = Some(rider_id);
+        if next_status == OrderStatus::Delivered {
+            order.completed_time = Some(now());
+        }
+        true
+    }
+
+    /// Items are sorted like by the database: the latest ones first.
+    fn to_order(&self, order: &IndexedOrder, items: &[IndexedOrderItem]) -> Order {
+        let items: Vec<OrderItem> = items
+            .iter()
+            .rev()
+            .filter_map(|item| {
+                let indexed_food = self.find_food(item.food_id)?.clone();
+                Some(OrderItem {
+                    total_price: indexed_food.price * Decimal::from(item.count),
+                    food: Food { indexed_food },
+                    indexed_item: item.clone(),
+                })
+            })
+            .collect();
+        Order {
+            total_price: items
+                .iter()
+                .filter(|item| !item.indexed_item.is_unavailable)
+                .map(|item| item.total_price)
+                .sum(),
+            items,
+            feedback: None,
+            indexed_order: order.clone(),
+        }
+    }
+}
+
+fn foreign_key_violation() -> Error {
+    Error::NotFound("referenced entity doesn't exist".to_string())
+}
+
+fn check_violation() -> Error {
+    Error::Invalid("input violates a constraint".to_string())
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn normalize(title: &str) -> String {
+    title.trim().to_lowercase()
+}
+
+fn owned_by<T>(entities: &[(ID, T)], user_id: ID) -> impl Iterator<Item = &T> {
+    entities
+        .iter()
+        .filter(move |(owner, _)| *owner == user_id)
+        .map(|(_, entity)| entity)
+}
+
+fn paginate<T>(entities: impl IntoIterator<Item = T>, pagination: Pagination) -> Vec<T> {
+    entities
+        .into_iter()
+        .skip(pagination.offset() as usize)
+        .take(pagination.limit() as usize)
+        .collect()
+}
+
+/// Returns the nodes which follow `after` in the order of their cursors.
+fn page<T: OutputType>(
+    mut nodes: Vec<T>,
+    sort_order: SortOrder,
+    first: i64,
+    after: Option<Cursor>,
+    cursor: impl Fn(&T) -> Cursor,
+) -> Connection<T> {
+    nodes.sort_by(|a, b| directed(compare_cursors(&cursor(a), &cursor(b)), sort_order));
+    let first = first.clamp(0, MAX_PAGE_SIZE) as usize;
+    let mut nodes: Vec<T> = nodes
+        .into_iter()
+        .filter(|node| {
+            after.as_ref().is_none_or(|after| {
+                directed(compare_cursors(&cursor(node), after), sort_order) == Ordering::Greater
+            })
+        })
+        .collect();
+    let has_next_page = nodes.len() > first;
+    nodes.truncate(first);
+    let mut connection = Connection::new(after.is_some(), has_next_page);
+    connection
+        .edges
+        .extend(nodes.into_iter().map(|node| Edge::new(cursor(&node), node)));
+    connection
+}
+
+fn compare_cursors(a: &Cursor, b: &Cursor) -> Ordering {
+    let ordering = match (&a.key, &b.key) {
+        (CursorKey::Text(a), CursorKey::Text(b)) => a.cmp(b),
+        (CursorKey::Integer(a), CursorKey::Integer(b)) => a.cmp(b),
+        (CursorKey::Numeric(a), CursorKey::Numeric(b)) => a.cmp(b),
+        (CursorKey::Double(a), CursorKey::Double(b)) => a.total_cmp(b),
+        (CursorKey::Timestamp(a), CursorKey::Timestamp(b)) => a.cmp(b),
+        // Cursors of different types are rejected by the server.
+        _ => Ordering::Equal,
+    };
+    ordering.then(a.id.cmp(&b.id))
+}
+
+fn directed(ordering: Ordering, sort_order: SortOrder) -> Ordering {
+    match sort_order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright © 2023 Nikita Dudko. All rights reserved.
+// Contacts: <nikita.dudko.95@gmail.com>
+// Licensed under the MIT License.
+
+//! Tests of the GraphQL resolvers executed against [MemoryDatastore],
+//! so they don't require PostgreSQL.
+
+mod memory;
+
+use std::sync::Arc;
+
+use async_graphql::{Request, Variables};
+use gogo_delivery::{
+    build_schema,
+    datastore::Datastore,
+    rest::SchemaOptions,
+    scan::UploadScanner,
+    stats::ExecutionStats,
+    types::{User, UserRole},
+    AppSchema, Device,
+};
+use serde_json::{json, Value};
+
+use memory::MemoryDatastore;
+
+struct TestSchema {
+    schema: AppSchema,
+    datastore: Arc<MemoryDatastore>,
+}
+
+impl TestSchema {
+    fn new() -> Self {
+        let datastore = Arc::new(MemoryDatastore::default());
+        let schema = build_schema(
+            datastore.clone(),
+            SchemaOptions::default(),
+            UploadScanner::default(),
+            ExecutionStats::default(),
+        );
+        Self { schema, datastore }
+    }
+
+    async fn add_user(&self, username: &str, role: UserRole) -> User {
+        self.datastore.add_user(username, "test-password", role);
+        self.datastore.user_by_name(username).await.unwrap()
+    }
+
+    /// Returns the JSON response to the request executed on behalf of the user.
+    async fn execute(&self, user: &User, query: &str, variables: Value) -> Value {
+        let request = Request::new(query)
+            .variables(Variables::from_json(variables))
+            .data(user.clone())
+            .data(Device::default());
+        let response = self.schema.execute(request).await;
+        serde_json::to_value(response).unwrap()
+    }
+
+    /// Panics if the request fails, otherwise returns the data.
+    async fn execute_ok(&self, user: &User, query: &str, variables: Value) -> Value {
+        let response = self.execute(user, query, variables).await;
+        assert!(response.get("errors").is_none(), "{response}");
+        response["data"].clone()
+    }
+
+    async fn add_category(&self, manager: &User, title: &str) -> Value {
+        self.execute_ok(
+            manager,
+            "mutation($title: String!) { addCategory(category: { title: $title }) }",
+            json!({ "title": title }),
+        )
+        .await["addCategory"]
+            .clone()
+    }
+
+    async fn add_food(
+        &self,
+        manager: &User,
+        category_id: &Value,
+        title: &str,
+        price: &str,
+    ) -> Value {
+        self.execute_ok(
+            manager,
+            "mutation($food: FoodInput!) { addFood(food: $food) }",
+            json!({ "food": {
+                "title": title,
+                "categoryId": category_id,
+                "count": 10,
+                "isAlcohol": false,
+                "price": price
+            } }),
+        )
+        .await["addFood"]
+            .clone()
+    }
+}
+
+#[actix_web::test]
+async fn only_managers_change_catalog() {
+    let schema = TestSchema::new();
+    let manager = schema.add_user("manager", UserRole::Manager).await;
+    let customer = schema.add_user("customer", UserRole::Customer).await;
+
+    let response = schema
+        .execute(
+            &customer,
+            "mutation { addCategory(category: { title: \"Soups\" }) }",
+            json!({}),
+        )
+        .await;
+    assert!(response.get("errors").is_some(), "{response}");
+
+    let category_id = schema.add_category(&manager, "Soups").await;
+    let food_id = schema
+        .add_food(&manager, &category_id, "Borscht", "4.50")
+        .await;
+    let data = schema
+        .execute_ok(
+            &customer,
+            "query($categoryId: Int!, $id: Int!) {
+                foodInCategory(categoryId: $categoryId, sortBy: TITLE, sortOrder: ASCENDING) {
+                    id title price count
+                }
+                food(id: $id) { category { title } }
+            }",
+            json!({ "categoryId": category_id, "id": food_id }),
+        )
+        .await;
+    assert_eq!(
+        data["foodInCategory"],
+        json!([{ "id": food_id, "title": "Borscht", "price": "4.50", "count": 10 }])
+    );
+    assert_eq!(data["food"]["category"]["title"], "Soups");
+}
+
+#[actix_web::test]
+async fn food_is_paginated() {
+    let schema = TestSchema::new();
+    let manager = schema.add_user("manager", UserRole::Manager).await;
+    let category_id = schema.add_category(&manager, "Drinks").await;
+    for (title, price) in [("Cola", "1.50"), ("Juice", "2.00"), ("Water", "1.00")] {
+        schema.add_food(&manager, &category_id, title, price).await;
+    }
+
+    let data = schema
+        .execute_ok(
+            &manager,
+            "query($categoryId: Int!) {
+                foodInCategory(
+                    categoryId: $categoryId, sortBy: PRICE, sortOrder: DESCENDING,
+                    pagination: { limit: 2, offset: -5 }
+                ) { title }
+            }",
+            json!({ "categoryId": category_id }),
+        )
+        .await;
+    assert_eq!(
+        data["foodInCategory"],
+        json!([{ "title": "Juice" }, { "title": "Cola" }])
+    );
+
+    let query = "query($categoryId: Int!, $after: String) {
+        foodConnection(
+            categoryId: $categoryId, sortBy: TITLE, sortOrder: ASCENDING, first: 2, after: $after
+        ) {
+            nodes { title }
+            pageInfo { hasNextPage endCursor }
+        }
+    }";
+    let data = schema
+        .execute_ok(&manager, query, json!({ "categoryId": category_id }))
+        .await;
+    let connection = &data["foodConnection"];
+    assert_eq!(
+        connection["nodes"],
+        json!([{ "title": "Cola" }, { "title": "Juice" }])
+    );
+    assert_eq!(connection["pageInfo"]["hasNextPage"], true);
+
+    let after = connection["pageInfo"]["endCursor"].clone();
+    let data = schema
+        .execute_ok(
+            &manager,
+            query,
+            json!({ "categoryId": category_id, "after": after }),
+        )
+        .await;
+    let connection = &data["foodConnection"];
+    assert_eq!(connection["nodes"], json!([{ "title": "Water" }]));
+    assert_eq!(connection["pageInfo"]["hasNextPage"], false);
+}
+
+#[actix_web::test]
+async fn order_is_delivered_by_rider() {
+    let schema = TestSchema::new();
+    let manager = schema.add_user("manager", UserRole::Manager).await;
+    let rider = schema.add_user("rider", UserRole::Rider).await;
+    let customer = schema.add_user("customer", UserRole::Customer).await;
+    let category_id = schema.add_category(&manager, "Soups").await;
+    let food_id = schema
+        .add_food(&manager, &category_id, "Borscht", "4.50")
+        .await;
+
+    let address_id = schema
+        .execute_ok(
+            &customer,
+            "mutation {
+                addUserAddress(address: { locality: \"Minsk\", street: \"Main\", house: 1 })
+            }",
+            json!({}),
+        )
+        .await["addUserAddress"]
+        .clone();
+    schema
+        .execute_ok(
+            &customer,
+            "mutation($foodId: Int!) { addUserCartItem(item: { foodId: $foodId, count: 2 }) }",
+            json!({ "foodId": food_id }),
+        )
+        .await;
+    let order_id = schema
+        .execute_ok(
+            &customer,
+            "mutation($addressId: Int!) {
+                makeOrderFromUserCart(order: { addressId: $addressId })
+            }",
+            json!({ "addressId": address_id }),
+        )
+        .await["makeOrderFromUserCart"]
+        .clone();
+
+    let data = schema
+        .execute_ok(
+            &customer,
+            "query($id: Int!) {
+                userCart(sortBy: ADD_TIME, sortOrder: ASCENDING) { totalPrice }
+                food(id: $id) { indexedFood { count } }
+            }",
+            json!({ "id": food_id }),
+        )
+        .await;
+    assert_eq!(data["userCart"]["totalPrice"], "0");
+    assert_eq!(data["food"]["indexedFood"]["count"], 8);
+
+    let data = schema
+        .execute_ok(
+            &rider,
+            "{ availableOrders { indexedOrder { id status } totalPrice } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        data["availableOrders"],
+        json!([{
+            "indexedOrder": { "id": order_id, "status": "CREATED" },
+            "totalPrice": "9.00"
+        }])
+    );
+    let variables = json!({ "id": order_id });
+    for (mutation, result) in [
+        ("takeOrder(id: $id)", json!(true)),
+        ("advanceOrderStatus(id: $id)", json!("PICKED_UP")),
+        ("completeOrder(id: $id)", json!(true)),
+    ] {
+        let data = schema
+            .execute_ok(
+                &rider,
+                &format!("mutation($id: Int!) {{ {mutation} }}"),
+                variables.clone(),
+            )
+            .await;
+        assert_eq!(data.as_object().unwrap().values().next(), Some(&result));
+    }
+
+    let data = schema
+        .execute_ok(
+            &customer,
+            "{ userOrders(filter: ALL) { indexedOrder { id status } } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        data["userOrders"],
+        json!([{ "indexedOrder": { "id": order_id, "status": "DELIVERED" } }])
+    );
+}
+
+#[actix_web::test]
+async fn category_with_food_isnt_deleted() {
+    let schema = TestSchema::new();
+    let manager = schema.add_user("manager", UserRole::Manager).await;
+    let category_id = schema.add_category(&manager, "Soups").await;
+    let food_id = schema
+        .add_food(&manager, &category_id, "Borscht", "4.50")
+        .await;
+
+    let query = "mutation($id: Int!) { deleteCategory(id: $id) }";
+    let response = schema
+        .execute(&manager, query, json!({ "id": category_id }))
+        .await;
+    assert!(response.get("errors").is_some(), "{response}");
+
+    schema
+        .execute_ok(
+            &manager,
+            "mutation($id: Int!) { deleteFood(id: $id) }",
+            json!({ "id": food_id }),
+        )
+        .await;
+    let data = schema
+        .execute_ok(&manager, query, json!({ "id": category_id }))
+        .await;
+    assert_eq!(data["deleteCategory"], true);
+}
+
+#[actix_web::test]
+async fn revoking_all_sessions_changes_password() {
+    let schema = TestSchema::new();
+    let customer = schema.add_user("customer", UserRole::Customer).await;
+    let query = "mutation($oldPassword: String!, $newPassword: String!) {
+        revokeAllSessions(oldPassword: $oldPassword, newPassword: $newPassword)
+    }";
+
+    let response = schema
+        .execute(
+            &customer,
+            query,
+            json!({ "oldPassword": "wrong-password", "newPassword": "new-password-1" }),
+        )
+        .await;
+    assert!(response.get("errors").is_some(), "{response}");
+
+    let data = schema
+        .execute_ok(
+            &customer,
+            query,
+            json!({ "oldPassword": "test-password", "newPassword": "new-password-1" }),
+        )
+        .await;
+    assert_eq!(data["revokeAllSessions"], true);
+    let datastore = &schema.datastore;
+    assert!(!datastore
+        .is_credentials_valid("customer", "test-password")
+        .await
+        .unwrap());
+    assert!(datastore
+        .is_credentials_valid("customer", "new-password-1")
+        .await
+        .unwrap());
+}
+
+#[actix_web::test]
+async fn reorder_suggestions_names_invalid_argument() {
+    let schema = TestSchema::new();
+    let manager = schema.add_user("manager", UserRole::Manager).await;
+    let query = "query($days: Int!, $lookbackDays: Int!, $coverDays: Int!) {
+        reorderSuggestions(days: $days, lookbackDays: $lookbackDays, coverDays: $coverDays) {
+            __typename
+        }
+    }";
+
+    for field in ["days", "lookbackDays", "coverDays"] {
+        let mut variables = json!({ "days": 7, "lookbackDays": 30, "coverDays": 30 });
+        variables[field] = json!(0);
+        let response = schema.execute(&manager, query, variables).await;
+        let error = &response["errors"][0];
+        assert_eq!(error["extensions"]["field"], field, "{response}");
+        assert_eq!(error["message"], format!("{field} must be positive"));
+    }
+}